@@ -0,0 +1,216 @@
+//! Automatic topic assignment.
+//!
+//! Every topic can have rules: keywords, or regular expressions written between slashes. The Auto
+//! operation scans the question and answer of every card, suggests putting cards in the topics
+//! whose rules match, and applies the suggestions the user accepts.
+use std::fmt;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::quiz::{FlashcardKey, Study, TopicKey};
+
+/// A rule that matches card text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Rule {
+    /// Matches the keyword as a whole word or phrase, ignoring case.
+    Keyword(String),
+    /// Matches a regular expression, ignoring case.
+    Regex(String),
+}
+
+/// Why a rule could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleError(String);
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+impl Rule {
+    /// Reads a rule as typed by the user: `/pattern/` is a regular expression, anything else a
+    /// keyword. An empty rule would match every card, so it is refused.
+    pub fn parse(text: &str) -> Result<Rule, RuleError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(RuleError("the rule is empty".to_string()));
+        }
+        let rule = match text
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+        {
+            Some(pattern) if !pattern.is_empty() => Rule::Regex(pattern.to_string()),
+            _ => Rule::Keyword(text.to_string()),
+        };
+        rule.compile()
+            .map_err(|error| RuleError(error.to_string()))?;
+        Ok(rule)
+    }
+
+    fn compile(&self) -> Result<Regex, regex::Error> {
+        let pattern = match self {
+            // Word boundaries only make sense next to word characters, so `C++` still matches.
+            Rule::Keyword(keyword) => {
+                let keyword = keyword.trim();
+                let boundary = |character: Option<char>| {
+                    if character.is_some_and(|character| character.is_alphanumeric()) {
+                        r"\b"
+                    } else {
+                        ""
+                    }
+                };
+                format!(
+                    "{}{}{}",
+                    boundary(keyword.chars().next()),
+                    regex::escape(keyword),
+                    boundary(keyword.chars().last())
+                )
+            }
+            Rule::Regex(pattern) => pattern.clone(),
+        };
+        RegexBuilder::new(&pattern).case_insensitive(true).build()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::Keyword(keyword) => write!(f, "{keyword}"),
+            Rule::Regex(pattern) => write!(f, "/{pattern}/"),
+        }
+    }
+}
+
+/// A proposal to put a card in a topic because one of the topic's rules matched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub card: FlashcardKey,
+    pub topic: TopicKey,
+    pub rule: Rule,
+}
+
+impl Suggestion {
+    /// Describes the suggestion as a line of a diff, e.g. `+ Math: What is 2+2? (matched sum)`.
+    pub fn describe(&self, study: &Study) -> String {
+        let topic = study
+            .topics
+            .get(self.topic)
+            .map_or("?", |topic| topic.content.as_str());
+        let question = study
+            .cards
+            .get(self.card)
+            .map_or("?", |card| card.question.as_str());
+        format!("+ {topic}: {question} (matched {})", self.rule)
+    }
+}
+
+/// Returns a suggestion for every card whose question or answer matches a rule of a topic the
+/// card is not in yet. Only the first matching rule of a topic is reported.
+pub fn suggest(study: &Study) -> Vec<Suggestion> {
+    let rules: Vec<(TopicKey, Rule, Regex)> = study
+        .topics
+        .iter()
+        .flat_map(|(key, topic)| {
+            // Rules are checked when they are added, but a deck edited by hand could still
+            // contain broken ones; those are skipped.
+            topic
+                .rules
+                .iter()
+                .filter_map(move |rule| Some((key, rule.clone(), rule.compile().ok()?)))
+        })
+        .collect();
+
+    let mut suggestions = vec![];
+    for (card_key, card) in &study.cards {
+        let mut suggested: Vec<TopicKey> = vec![];
+        for (topic, rule, regex) in &rules {
+            if card.topics.contains(topic) || suggested.contains(topic) {
+                continue;
+            }
+            if regex.is_match(&card.question) || regex.is_match(&card.answer) {
+                suggested.push(*topic);
+                suggestions.push(Suggestion {
+                    card: card_key,
+                    topic: *topic,
+                    rule: rule.clone(),
+                });
+            }
+        }
+    }
+    suggestions
+}
+
+/// Puts the cards of the suggestions in their topics.
+pub fn apply(study: &mut Study, suggestions: &[Suggestion]) {
+    for suggestion in suggestions {
+        study.link(suggestion.card, suggestion.topic);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::Flashcard;
+
+    #[test]
+    fn parses_rules() {
+        assert_eq!(
+            Rule::parse(" photosynthesis ").unwrap(),
+            Rule::Keyword("photosynthesis".to_string())
+        );
+        assert_eq!(
+            Rule::parse(r"/\d+\s*\+\s*\d+/").unwrap(),
+            Rule::Regex(r"\d+\s*\+\s*\d+".to_string())
+        );
+        assert!(Rule::parse("/(unclosed/").is_err());
+        assert!(Rule::parse("").is_err());
+        assert!(Rule::parse("  ").is_err());
+        assert_eq!(Rule::parse("/x/").unwrap().to_string(), "/x/");
+    }
+
+    #[test]
+    fn suggests_and_applies_topics() {
+        let mut study = Study::default();
+        let math = study.topic_by_name_or_insert("Math");
+        let biology = study.topic_by_name_or_insert("Biology");
+        study.topics[math].rules = vec![Rule::parse(r"/\d\s*\+\s*\d/").unwrap()];
+        study.topics[biology].rules = vec![Rule::parse("cell").unwrap()];
+
+        let sum = study.insert_card(Flashcard {
+            question: "What is 2 + 2?".to_string(),
+            ..Default::default()
+        });
+        let cell = study.insert_card(Flashcard {
+            question: "What is the unit of life?".to_string(),
+            answer: "The Cell".to_string(),
+            ..Default::default()
+        });
+        // Keywords only match whole words.
+        study.insert_card(Flashcard {
+            question: "What does a cellist play?".to_string(),
+            ..Default::default()
+        });
+        study.insert_card(Flashcard {
+            question: "1+1?".to_string(),
+            topics: vec![math],
+            ..Default::default()
+        });
+
+        let suggestions = suggest(&study);
+        let pairs: Vec<_> = suggestions.iter().map(|s| (s.card, s.topic)).collect();
+        assert_eq!(pairs, [(sum, math), (cell, biology)]);
+        assert_eq!(
+            suggestions[1].describe(&study),
+            "+ Biology: What is the unit of life? (matched cell)"
+        );
+
+        apply(&mut study, &suggestions[..1]);
+        assert_eq!(study.cards[sum].topics, [math]);
+        assert!(study.topics[math].qna.contains(&sum));
+        assert_eq!(suggest(&study).len(), 1);
+    }
+}
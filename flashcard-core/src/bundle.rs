@@ -0,0 +1,199 @@
+//! Single-file deck bundles for sharing decks between installs.
+//!
+//! A bundle is a zip archive holding a `manifest.json`, the serialized [`Study`] in `study.json`
+//! and the card images under `media/`. Slot map keys are only valid in the process that made
+//! them, so importing a bundle copies its cards and topics into the running study session under
+//! fresh keys. Cards and topics keep their ids, so importing the same bundle twice updates the
+//! cards from the first import instead of adding them again.
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::import::{self, ImportError, ImportSummary};
+use crate::quiz::{unix_now, Study, TopicKey};
+
+/// The file extension of deck bundles.
+pub const EXTENSION: &str = "fcdeck";
+
+const FORMAT: &str = "flashcard-rs-bundle";
+const VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+const STUDY: &str = "study.json";
+const MEDIA: &str = "media/";
+
+/// Describes what a bundle holds.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format: String,
+    version: u32,
+    created: u64,
+    cards: usize,
+    topics: usize,
+    media: Vec<String>,
+}
+
+/// Writes the cards of `topics` to a bundle at `path`.
+///
+/// Images that cannot be read are left out of the bundle and reported on stderr.
+pub fn write_bundle(study: &Study, topics: &[TopicKey], path: &Path) -> io::Result<()> {
+    let mut bundle = Study::default();
+    bundle.merge_from(study, topics, &study.cards_in_topics(topics));
+
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default();
+    let mut media = vec![];
+    for (index, card) in bundle.cards.values_mut().enumerate() {
+        let Some(image) = card.image.take() else {
+            continue;
+        };
+        let bytes = match fs::read(&image) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                eprintln!("Skipping image {}: {error}", image.display());
+                continue;
+            }
+        };
+        let file_name = image
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // The index keeps images of different cards with the same file name apart.
+        let name = format!("{index}-{file_name}");
+        zip.start_file(format!("{MEDIA}{name}"), options)?;
+        zip.write_all(&bytes)?;
+        card.image = Some(PathBuf::from(format!("{MEDIA}{name}")));
+        media.push(name);
+    }
+
+    let manifest = Manifest {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        created: unix_now(),
+        cards: bundle.cards.len(),
+        topics: bundle.topics.len(),
+        media,
+    };
+    zip.start_file(MANIFEST, options)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest)?;
+    zip.start_file(STUDY, options)?;
+    serde_json::to_writer(&mut zip, &bundle)?;
+    zip.finish()?;
+    Ok(())
+}
+
+/// Returns the folder that images of the bundle at `path` are unpacked into: a folder next to
+/// the bundle named after it.
+pub fn media_dir(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{stem}-media"))
+}
+
+/// Imports the bundle at `path` into `study`, unpacking its images into `media_dir`.
+pub fn import_bundle(
+    study: &mut Study,
+    path: &Path,
+    media_dir: &Path,
+) -> Result<ImportSummary, ImportError> {
+    let mut zip = ZipArchive::new(File::open(path)?)?;
+
+    let manifest: Manifest = serde_json::from_reader(zip.by_name(MANIFEST)?)?;
+    if manifest.format != FORMAT || manifest.version > VERSION {
+        return Err(ImportError::Format(format!(
+            "{} bundle version {} is not supported",
+            manifest.format, manifest.version
+        )));
+    }
+    let mut bundle: Study = serde_json::from_reader(zip.by_name(STUDY)?)?;
+
+    for card in bundle.cards.values_mut() {
+        let Some(name) = card
+            .image
+            .as_ref()
+            .and_then(|image| image.to_str())
+            .and_then(|image| image.strip_prefix(MEDIA))
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        // Only the file name is used, so entries cannot point outside of the media folder.
+        let Some(file_name) = Path::new(&name).file_name() else {
+            card.image = None;
+            continue;
+        };
+        let mut bytes = vec![];
+        zip.by_name(&format!("{MEDIA}{name}"))?
+            .read_to_end(&mut bytes)?;
+        fs::create_dir_all(media_dir)?;
+        let target = media_dir.join(file_name);
+        fs::write(&target, bytes)?;
+        card.image = Some(target);
+    }
+
+    Ok(import::merge_all(study, &bundle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::{Color, Flashcard};
+
+    #[test]
+    fn round_trips_through_a_bundle() {
+        let name = format!("flashcard-rs-bundle-test-{}", std::process::id());
+        let dir = std::env::temp_dir().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        let image = dir.join("cell.png");
+        fs::write(&image, b"image").unwrap();
+
+        let mut source = Study::default();
+        let biology = source.topic_by_name_or_insert("Biology");
+        let other = source.topic_by_name_or_insert("Other");
+        let cell = source.insert_card(Flashcard {
+            question: "Cell?".to_string(),
+            answer: "Unit of life".to_string(),
+            image: Some(image),
+            bg_color: Some(Color::BLACK),
+            topics: vec![biology, other],
+            ..Default::default()
+        });
+        source.insert_card(Flashcard {
+            question: "Left behind".to_string(),
+            topics: vec![other],
+            ..Default::default()
+        });
+        let path = dir.join("deck.fcdeck");
+        write_bundle(&source, &[biology], &path).unwrap();
+
+        // The receiving session already has a Biology topic and keys of its own.
+        let mut target = Study::default();
+        target.topic_by_name_or_insert("Chemistry");
+        let existing = target.topic_by_name_or_insert("biology");
+        let summary = import_bundle(&mut target, &path, &media_dir(&path)).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                cards: 1,
+                topics: 0
+            }
+        );
+
+        let card = target.cards.values().next().unwrap();
+        assert_eq!(card.question, "Cell?");
+        assert_eq!(card.id, source.cards[cell].id);
+        assert_eq!(card.topics, [existing]);
+        assert_eq!(card.bg_color, Some(Color::BLACK));
+        assert_eq!(target.topics[existing].qna.len(), 1);
+        let image = card.image.as_ref().unwrap();
+        assert!(image.starts_with(media_dir(&path)));
+        assert_eq!(fs::read(image).unwrap(), b"image");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
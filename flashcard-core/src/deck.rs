@@ -0,0 +1,99 @@
+//! Saving and loading decks.
+//!
+//! A deck file is the serialized [`Study`] as JSON. Both front ends open the deck at
+//! [`default_path`] unless told otherwise, so cards made in one show up in the other.
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::import::{self, ImportError, ImportSummary};
+use crate::integrity;
+use crate::quiz::Study;
+
+/// The file extension of deck files.
+pub const EXTENSION: &str = "json";
+
+/// Returns where the deck is kept when no other file is given: `flashcard-rs/deck.json` in the
+/// user's data folder, or in the working directory when there is none.
+pub fn default_path() -> PathBuf {
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    match data_dir {
+        Some(data_dir) => data_dir.join("flashcard-rs").join("deck.json"),
+        None => PathBuf::from("deck.json"),
+    }
+}
+
+/// Writes the deck to `path`, creating its folder if needed.
+pub fn save_deck(study: &Study, path: &Path) -> io::Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec_pretty(study)?)
+}
+
+/// Reads the deck at `path`, repairing broken links between its cards and topics. The repairs
+/// are reported on stderr.
+pub fn load_deck(path: &Path) -> Result<Study, ImportError> {
+    let mut study: Study = serde_json::from_slice(&fs::read(path)?)?;
+    let problems = integrity::check(&study);
+    for problem in &problems {
+        eprintln!("Repairing {}: {}", path.display(), problem.describe(&study));
+    }
+    integrity::repair(&mut study);
+    Ok(study)
+}
+
+/// Reads the deck at `path`, starting with an empty one if the file does not exist yet.
+pub fn load_or_default(path: &Path) -> Result<Study, ImportError> {
+    match load_deck(path) {
+        Err(ImportError::Io(error)) if error.kind() == io::ErrorKind::NotFound => {
+            Ok(Study::default())
+        }
+        result => result,
+    }
+}
+
+/// Adds all cards and topics of the deck at `path` to `study`.
+pub fn import_deck(study: &mut Study, path: &Path) -> Result<ImportSummary, ImportError> {
+    Ok(import::merge_all(study, &load_deck(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::{Color, Flashcard};
+
+    #[test]
+    fn saves_and_loads_a_deck() {
+        let path = env::temp_dir()
+            .join(format!("flashcard-rs-deck-test-{}", std::process::id()))
+            .join("deck.json");
+        let mut study = Study::default();
+        let topic = study.topic_by_name_or_insert("Math");
+        let card = study.insert_card(Flashcard {
+            question: "1+1?".to_string(),
+            answer: "2".to_string(),
+            bg_color: Some(Color::WHITE),
+            topics: vec![topic],
+            ..Default::default()
+        });
+        save_deck(&study, &path).unwrap();
+
+        let loaded = load_deck(&path).unwrap();
+        let loaded_card = &loaded.cards[card];
+        assert_eq!(loaded_card.id, study.cards[card].id);
+        assert_eq!(loaded_card.answer, "2");
+        assert_eq!(loaded_card.bg_color, Some(Color::WHITE));
+        assert_eq!(loaded.topics[topic].qna, [card]);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert!(load_or_default(&path).unwrap().cards.is_empty());
+    }
+}
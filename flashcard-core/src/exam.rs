@@ -0,0 +1,337 @@
+//! Timed exams, for practising for tests that have a time limit.
+//!
+//! An exam asks a fixed number of questions picked at random from the quizzed cards, each once.
+//! Nothing is said about the answers until the exam is over: wrong answers are not requeued, and
+//! the marks are given in a report at the end. The time limit is either for the whole exam or for
+//! each question; a question that runs out of time is left unanswered and the exam moves on.
+//!
+//! The caller passes the time in, so the exam can be driven by a timer or checked after each
+//! answer.
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::quiz::{Question, Quiz, Study};
+use crate::random::Rng;
+use crate::review::Grade;
+
+// The longest time limit, a day, so the deadlines of an exam can always be reached.
+const MAX_LIMIT: u64 = 24 * 60 * 60;
+
+/// How long an exam may take, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeLimit {
+    /// For the whole exam.
+    Total(u64),
+    /// For each question.
+    PerQuestion(u64),
+}
+
+impl TimeLimit {
+    fn duration(self) -> Duration {
+        match self {
+            TimeLimit::Total(seconds) | TimeLimit::PerQuestion(seconds) => {
+                Duration::from_secs(seconds)
+            }
+        }
+    }
+}
+
+impl fmt::Display for TimeLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (TimeLimit::Total(seconds) | TimeLimit::PerQuestion(seconds)) = *self;
+        if seconds % 60 == 0 {
+            write!(f, "{}m", seconds / 60)?;
+        } else {
+            write!(f, "{seconds}s")?;
+        }
+        if let TimeLimit::PerQuestion(_) = self {
+            write!(f, " each")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a limit like `20m` for the whole exam or `30s each` for each question. A number without
+/// a unit is in seconds. Limits longer than a day are refused.
+impl FromStr for TimeLimit {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let (amount, per_question) = match text.strip_suffix("each") {
+            Some(amount) => (amount.trim(), true),
+            None => (text, false),
+        };
+        let (number, unit) = match amount.strip_suffix('m') {
+            Some(number) => (number, 60),
+            None => (amount.strip_suffix('s').unwrap_or(amount), 1),
+        };
+        let seconds = match number.trim().parse::<u64>() {
+            Ok(number) if number > 0 => number.checked_mul(unit),
+            _ => {
+                return Err(format!(
+                    "invalid time limit {text}, expected e.g. 20m for the whole exam or 30s each"
+                ))
+            }
+        };
+        let seconds = match seconds {
+            Some(seconds) if seconds <= MAX_LIMIT => seconds,
+            _ => return Err(format!("time limit {text} is longer than a day")),
+        };
+        Ok(if per_question {
+            TimeLimit::PerQuestion(seconds)
+        } else {
+            TimeLimit::Total(seconds)
+        })
+    }
+}
+
+/// An exam being taken.
+#[derive(Debug, Clone)]
+pub struct Exam {
+    pub questions: Vec<Question>,
+    /// The answers so far, with how long they took in milliseconds. A question that ran out of
+    /// time has no answer.
+    pub answers: Vec<Option<(String, u64)>>,
+    pub limit: TimeLimit,
+    started: Instant,
+    // When the current question was shown.
+    shown: Instant,
+}
+
+impl Exam {
+    /// Starts an exam at `now` on `count` of the questions, picked with the seed.
+    pub fn new(
+        questions: Vec<Question>,
+        count: usize,
+        limit: TimeLimit,
+        seed: u64,
+        now: Instant,
+    ) -> Self {
+        Self {
+            questions: Rng::new(seed).sample(questions, count),
+            answers: Vec::new(),
+            limit,
+            started: now,
+            shown: now,
+        }
+    }
+
+    /// Returns the question being asked, or None when the exam is over.
+    pub fn current(&self) -> Option<&Question> {
+        self.questions.get(self.answers.len())
+    }
+
+    /// Returns whether every question was answered or ran out of time.
+    pub fn is_finished(&self) -> bool {
+        self.answers.len() >= self.questions.len()
+    }
+
+    /// Returns the time left at `now`, for the whole exam or the current question.
+    pub fn remaining(&self, now: Instant) -> Duration {
+        let from = match self.limit {
+            TimeLimit::Total(_) => self.started,
+            TimeLimit::PerQuestion(_) => self.shown,
+        };
+        from.checked_add(self.limit.duration())
+            .map_or(Duration::MAX, |end| end.saturating_duration_since(now))
+    }
+
+    /// Moves past the questions that ran out of time by `now` and returns whether there were any.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let before = self.answers.len();
+        match self.limit {
+            TimeLimit::Total(_) if self.remaining(now).is_zero() => {
+                self.answers.resize(self.questions.len(), None);
+            }
+            TimeLimit::Total(_) => {}
+            TimeLimit::PerQuestion(_) => {
+                while !self.is_finished() && self.remaining(now).is_zero() {
+                    self.answers.push(None);
+                    self.shown += self.limit.duration();
+                }
+            }
+        }
+        self.answers.len() > before
+    }
+
+    /// Answers the current question at `now` and returns whether the answer was in time. An
+    /// answer that came too late is dropped.
+    pub fn answer(&mut self, answer: String, now: Instant) -> bool {
+        if self.tick(now) || self.is_finished() {
+            return false;
+        }
+        let response_ms = now.saturating_duration_since(self.shown).as_millis() as u64;
+        self.answers.push(Some((answer, response_ms)));
+        self.shown = now;
+        true
+    }
+
+    /// Ends the exam at `now`, marks it and logs every answer in the study session. Questions
+    /// that were not answered are marked wrong.
+    pub fn finish(mut self, study_session: &mut Study, now: Instant) -> ExamReport {
+        self.answers.resize(self.questions.len(), None);
+        let results: Vec<ExamResult> = self
+            .questions
+            .into_iter()
+            .zip(self.answers)
+            .map(|(question, answer)| {
+                let correct = answer
+                    .as_ref()
+                    .is_some_and(|(answer, _)| question.check(answer));
+                if let Some(card) = study_session.card_by_id(question.id) {
+                    let (answer, response_ms) = answer.clone().unwrap_or_default();
+                    let grade = Grade::from_correct(correct);
+                    study_session.record_review(card, answer, grade, response_ms);
+                }
+                ExamResult {
+                    question,
+                    answer: answer.map(|(answer, _)| answer),
+                    correct,
+                }
+            })
+            .collect();
+        ExamReport {
+            results,
+            elapsed: now.saturating_duration_since(self.started),
+        }
+    }
+}
+
+/// The mark for one question of an exam.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExamResult {
+    pub question: Question,
+    /// None when the question ran out of time or the exam ended first.
+    pub answer: Option<String>,
+    pub correct: bool,
+}
+
+/// The marks of a finished exam.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExamReport {
+    pub results: Vec<ExamResult>,
+    pub elapsed: Duration,
+}
+
+impl ExamReport {
+    /// Returns how many answers were right.
+    pub fn score(&self) -> usize {
+        self.results.iter().filter(|result| result.correct).count()
+    }
+
+    /// Returns the share of right answers in percent.
+    pub fn percent(&self) -> f32 {
+        if self.results.is_empty() {
+            0.0
+        } else {
+            self.score() as f32 * 100.0 / self.results.len() as f32
+        }
+    }
+}
+
+impl fmt::Display for ExamReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} right ({:.0}%) in {}",
+            self.score(),
+            self.results.len(),
+            self.percent(),
+            clock(self.elapsed)
+        )
+    }
+}
+
+/// Formats a time like a countdown clock, in minutes and seconds.
+pub fn clock(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl Quiz {
+    /// Starts an exam at `now` on `count` questions picked with the seed from the cards that a
+    /// quiz would ask.
+    pub fn start_exam(
+        &mut self,
+        study_session: &Study,
+        count: usize,
+        limit: TimeLimit,
+        seed: u64,
+        now: Instant,
+    ) {
+        self.start_quiz(study_session);
+        let questions = std::mem::take(&mut self.qna_queue).into();
+        let exam = Exam::new(questions, count, limit, seed, now);
+        println!(
+            "Exam started with {} questions, {limit}.",
+            exam.questions.len()
+        );
+        self.exam = Some(exam);
+    }
+
+    /// Ends the exam at `now` and returns its report, or None when no exam is being taken.
+    pub fn finish_exam(&mut self, study_session: &mut Study, now: Instant) -> Option<ExamReport> {
+        let report = self.exam.take()?.finish(study_session, now);
+        println!("Exam finished: {report}.");
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::{Flashcard, Topic};
+
+    #[test]
+    fn marks_exams_at_the_end() {
+        let mut study = Study::default();
+        let math = study.insert_topic(Topic {
+            content: "Math".to_string(),
+            enabled: true,
+            ..Default::default()
+        });
+        for n in 1..=5 {
+            study.insert_card(Flashcard {
+                question: format!("{n}+{n}?"),
+                answer: (n + n).to_string(),
+                topics: vec![math],
+                ..Default::default()
+            });
+        }
+        let start = Instant::now();
+        let mut quiz = Quiz::default();
+        quiz.start_exam(&study, 3, TimeLimit::PerQuestion(30), 1, start);
+        let exam = quiz.exam.as_mut().unwrap();
+        assert_eq!(exam.questions.len(), 3);
+
+        // A right answer, a wrong one, and one that runs out of time.
+        let right = exam.current().unwrap().answer.clone();
+        assert!(exam.answer(right, start + Duration::from_secs(10)));
+        assert!(exam.answer("wrong".to_string(), start + Duration::from_secs(20)));
+        assert_eq!(
+            exam.remaining(start + Duration::from_secs(45)),
+            Duration::from_secs(5)
+        );
+        assert!(!exam.answer("late".to_string(), start + Duration::from_secs(51)));
+        assert!(exam.is_finished());
+
+        let report = quiz
+            .finish_exam(&mut study, start + Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(report.score(), 1);
+        assert_eq!(report.results[2].answer, None);
+        assert_eq!(report.to_string(), "1 of 3 right (33%) in 1:00");
+        assert_eq!(study.reviews.len(), 3);
+        assert!(quiz.exam.is_none());
+
+        assert_eq!("20m".parse(), Ok(TimeLimit::Total(1200)));
+        assert_eq!("45 each".parse(), Ok(TimeLimit::PerQuestion(45)));
+        assert_eq!(TimeLimit::PerQuestion(90).to_string(), "90s each");
+        assert!("soon".parse::<TimeLimit>().is_err());
+        assert!("18446744073709551615m".parse::<TimeLimit>().is_err());
+        assert!("25h".parse::<TimeLimit>().is_err());
+        assert!("86401s each".parse::<TimeLimit>().is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Forecasts of how many reviews will be due, for planning the study load ahead of an exam.
+//!
+//! Every card is followed forward along its schedule as if each review were answered Good, so a
+//! forecast shows the least work ahead; cards that are forgotten come back sooner. Cards that were
+//! never reviewed have no schedule yet and are left out, but new cards can be added to see what
+//! starting some every day would cost. Suspended cards never come due, and buried cards not before
+//! they come back.
+use crate::quiz::{Schedule, Study, SECONDS_PER_DAY};
+use crate::review::Grade;
+
+/// The reviews due on one day of a forecast.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ForecastDay {
+    /// Days since the unix epoch.
+    pub day: u64,
+    /// Reviews of cards that were studied before.
+    pub due: usize,
+    /// New cards studied for the first time.
+    pub new: usize,
+}
+
+impl ForecastDay {
+    /// Returns every review of the day.
+    pub fn total(&self) -> usize {
+        self.due + self.new
+    }
+}
+
+/// Returns the reviews due on each of `days` days starting with the day of the unix time `now`,
+/// when `new_per_day` new cards are started every day. Overdue cards are due on the first day.
+pub fn forecast(study: &Study, now: u64, days: usize, new_per_day: usize) -> Vec<ForecastDay> {
+    let today = now / SECONDS_PER_DAY;
+    let mut result: Vec<ForecastDay> = (today..today + days as u64)
+        .map(|day| ForecastDay {
+            day,
+            ..Default::default()
+        })
+        .collect();
+    for card in study.cards.values() {
+        if card.suspended {
+            continue;
+        }
+        let mut schedule = card.schedule.clone();
+        if card.is_buried(now) {
+            let until = card.buried_until.unwrap_or(now);
+            schedule.next_review = schedule.next_review.map(|next| next.max(until));
+        }
+        follow(schedule, 1, &mut result);
+    }
+    if new_per_day > 0 {
+        // The new cards of a day all have the same schedule, so they are followed together.
+        for index in 0..result.len() {
+            result[index].new += new_per_day;
+            let mut schedule = Schedule::default();
+            schedule.review(Grade::Good, result[index].day * SECONDS_PER_DAY);
+            follow(schedule, new_per_day, &mut result);
+        }
+    }
+    result
+}
+
+// Adds the reviews of `count` cards with `schedule` to the days of the forecast.
+fn follow(mut schedule: Schedule, count: usize, result: &mut [ForecastDay]) {
+    let (Some(first), Some(last)) = (result.first(), result.last()) else {
+        return;
+    };
+    let (first, last) = (first.day, last.day);
+    while let Some(next) = schedule.next_review {
+        let day = (next / SECONDS_PER_DAY).max(first);
+        if day > last {
+            break;
+        }
+        result[(day - first) as usize].due += count;
+        schedule.review(Grade::Good, day * SECONDS_PER_DAY);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::Flashcard;
+
+    #[test]
+    fn forecasts_due_reviews() {
+        let mut study = Study::default();
+        let now = 100 * SECONDS_PER_DAY + 60;
+        // Reviewed twice, so after this review it comes back in fifteen days.
+        study.insert_card(Flashcard {
+            schedule: Schedule {
+                repetitions: 2,
+                interval: 6,
+                next_review: Some(102 * SECONDS_PER_DAY + 3600),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        // An overdue card, and a card that was never reviewed.
+        study.insert_card(Flashcard {
+            schedule: Schedule {
+                repetitions: 1,
+                interval: 1,
+                next_review: Some(90 * SECONDS_PER_DAY),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        study.insert_card(Flashcard::default());
+        // A suspended card is never due, and a buried one only once it comes back.
+        let mut overdue = Schedule {
+            repetitions: 1,
+            interval: 1,
+            next_review: Some(90 * SECONDS_PER_DAY),
+            ..Default::default()
+        };
+        study.insert_card(Flashcard {
+            schedule: overdue.clone(),
+            suspended: true,
+            ..Default::default()
+        });
+        overdue.next_review = Some(101 * SECONDS_PER_DAY);
+        study.insert_card(Flashcard {
+            schedule: overdue,
+            buried_until: Some(104 * SECONDS_PER_DAY),
+            ..Default::default()
+        });
+
+        let days = forecast(&study, now, 20, 0);
+        let due: Vec<usize> = days.iter().map(|day| day.due).collect();
+        let mut expected = vec![0; 20];
+        expected[0] = 1;
+        expected[2] = 1;
+        expected[4] = 1;
+        expected[6] = 1;
+        expected[10] = 1;
+        expected[17] = 1;
+        assert_eq!(due, expected);
+        assert_eq!(days[0].day, 100);
+
+        // A new card comes back the next day, then six days later.
+        let days = forecast(&Study::default(), now, 10, 3);
+        let totals: Vec<usize> = days.iter().map(ForecastDay::total).collect();
+        assert_eq!(totals, [3, 6, 6, 6, 6, 6, 6, 9, 9, 9]);
+    }
+}
@@ -0,0 +1,532 @@
+//! Undo and redo for edits to a study session.
+//!
+//! Every edit is recorded as the changes it made to cards, topics and namespaces, together with
+//! the inverse changes that put them back. Changes refer to cards, topics and namespaces by id
+//! rather than by key, because a card that is removed and restored gets a new key.
+//!
+//! Reviews are not edits and are never undone. The parts of a card that reviews change, its
+//! schedule and whether it is a leech or suspended, are only put back by an edit that changed them
+//! itself, so undoing an older edit to a card keeps the reviews given since.
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::quiz::{Flashcard, FlashcardKey, Namespace, Schedule, Study, Topic};
+
+// A card with its topics given by id. The parts that reviews change are kept apart, and are None
+// in a change that leaves them as they are.
+#[derive(Debug, Clone, PartialEq)]
+struct CardState {
+    card: Flashcard,
+    topics: Vec<Uuid>,
+    schedule: Option<Schedule>,
+    leech: Option<bool>,
+    suspended: Option<bool>,
+}
+
+impl CardState {
+    // Leaves out the parts that reviews change where they are the same in `other`.
+    fn keep_reviewed(&mut self, other: &mut CardState) {
+        if self.schedule == other.schedule {
+            (self.schedule, other.schedule) = (None, None);
+        }
+        if self.leech == other.leech {
+            (self.leech, other.leech) = (None, None);
+        }
+        if self.suspended == other.suspended {
+            (self.suspended, other.suspended) = (None, None);
+        }
+    }
+}
+
+// A topic with its parent and namespaces given by id. The Q&A list is left out, it follows from
+// the topics of the cards.
+#[derive(Debug, Clone, PartialEq)]
+struct TopicState {
+    topic: Topic,
+    parent: Option<Uuid>,
+    namespaces: Vec<Uuid>,
+}
+
+// Sets a card, topic or namespace to a state, or removes it when the state is None.
+#[derive(Debug, Clone)]
+enum Change {
+    Card(Uuid, Option<CardState>),
+    Topic(Uuid, Option<TopicState>),
+    Namespace(Uuid, Option<Namespace>),
+}
+
+/// The saved parts of a study session at one point in time, taken before an edit so the edit can
+/// be recorded with [`History::commit`].
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    cards: Vec<(Uuid, CardState)>,
+    card_keys: HashMap<Uuid, FlashcardKey>,
+    topics: Vec<(Uuid, TopicState)>,
+    namespaces: Vec<(Uuid, Namespace)>,
+}
+
+impl Snapshot {
+    /// Takes a snapshot of the cards, topics and namespaces of `study`.
+    pub fn take(study: &Study) -> Self {
+        let topic_id = |key| study.topics.get(key).map(|topic: &Topic| topic.id);
+        let cards = study
+            .cards
+            .values()
+            .map(|card| {
+                let state = CardState {
+                    card: Flashcard {
+                        topics: vec![],
+                        schedule: Schedule::default(),
+                        leech: false,
+                        suspended: false,
+                        ..card.clone()
+                    },
+                    topics: card
+                        .topics
+                        .iter()
+                        .filter_map(|&key| topic_id(key))
+                        .collect(),
+                    schedule: Some(card.schedule.clone()),
+                    leech: Some(card.leech),
+                    suspended: Some(card.suspended),
+                };
+                (card.id, state)
+            })
+            .collect();
+        let topics = study
+            .topics
+            .values()
+            .map(|topic| {
+                let state = TopicState {
+                    topic: Topic {
+                        qna: vec![],
+                        namespaces: vec![],
+                        parent: None,
+                        ..topic.clone()
+                    },
+                    parent: topic.parent.and_then(topic_id),
+                    namespaces: topic
+                        .namespaces
+                        .iter()
+                        .filter_map(|&key| study.namespaces.get(key))
+                        .map(|namespace| namespace.id)
+                        .collect(),
+                };
+                (topic.id, state)
+            })
+            .collect();
+        let namespaces = study
+            .namespaces
+            .values()
+            .map(|namespace| (namespace.id, namespace.clone()))
+            .collect();
+        Self {
+            cards,
+            card_keys: study
+                .cards
+                .iter()
+                .map(|(key, card)| (card.id, key))
+                .collect(),
+            topics,
+            namespaces,
+        }
+    }
+}
+
+// One recorded edit.
+#[derive(Debug, Clone)]
+struct Step {
+    label: String,
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+}
+
+/// The edits that can be undone and redone. There is no limit on their number.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+impl History {
+    /// Runs `edit` on `study` and records what it changed under `label`.
+    pub fn record<T>(
+        &mut self,
+        study: &mut Study,
+        label: &str,
+        edit: impl FnOnce(&mut Study) -> T,
+    ) -> T {
+        let before = Snapshot::take(study);
+        let result = edit(study);
+        self.commit(label, before, study);
+        result
+    }
+
+    /// Records the changes from `before` to `study` under `label`. Nothing is recorded when
+    /// nothing changed. Recording an edit forgets the edits that were undone.
+    ///
+    /// Returns the cards the edit added, changed or removed and the cards of the topics it
+    /// changed, so whatever is kept about them elsewhere can be brought up to date. Removed cards
+    /// are returned under the key they had.
+    pub fn commit(&mut self, label: &str, before: Snapshot, study: &Study) -> Vec<FlashcardKey> {
+        let after = Snapshot::take(study);
+        let mut undo = vec![];
+        let mut redo = vec![];
+        diff(
+            &before.namespaces,
+            &after.namespaces,
+            Change::Namespace,
+            &mut undo,
+            &mut redo,
+        );
+        diff(
+            &before.topics,
+            &after.topics,
+            Change::Topic,
+            &mut undo,
+            &mut redo,
+        );
+        let first_card = undo.len();
+        diff(
+            &before.cards,
+            &after.cards,
+            Change::Card,
+            &mut undo,
+            &mut redo,
+        );
+        for (old, new) in undo[first_card..].iter_mut().zip(&mut redo[first_card..]) {
+            if let (Change::Card(_, Some(old)), Change::Card(_, Some(new))) = (old, new) {
+                old.keep_reviewed(new);
+            }
+        }
+        if undo.is_empty() {
+            return vec![];
+        }
+        let changed = changed_cards(&redo, &before, &after);
+        self.undo.push(Step {
+            label: label.to_string(),
+            undo,
+            redo,
+        });
+        self.redo.clear();
+        changed
+    }
+
+    /// Undoes the last edit and returns its label, or None when there is nothing to undo.
+    pub fn undo(&mut self, study: &mut Study) -> Option<String> {
+        let step = self.undo.pop()?;
+        apply(study, &step.undo);
+        let label = step.label.clone();
+        self.redo.push(step);
+        Some(label)
+    }
+
+    /// Redoes the last undone edit and returns its label, or None when there is nothing to redo.
+    pub fn redo(&mut self, study: &mut Study) -> Option<String> {
+        let step = self.redo.pop()?;
+        apply(study, &step.redo);
+        let label = step.label.clone();
+        self.undo.push(step);
+        Some(label)
+    }
+
+    /// Returns whether there is an edit to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns whether there is an undone edit to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+// Adds a change for every entry that differs between `before` and `after`: one restoring the old
+// state to `undo` and one setting the new state to `redo`.
+fn diff<T: Clone + PartialEq>(
+    before: &[(Uuid, T)],
+    after: &[(Uuid, T)],
+    change: fn(Uuid, Option<T>) -> Change,
+    undo: &mut Vec<Change>,
+    redo: &mut Vec<Change>,
+) {
+    let before_by_id: HashMap<Uuid, &T> = before.iter().map(|(id, state)| (*id, state)).collect();
+    let after_by_id: HashMap<Uuid, &T> = after.iter().map(|(id, state)| (*id, state)).collect();
+    let ids = before.iter().map(|(id, _)| *id).chain(
+        after
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| !before_by_id.contains_key(id)),
+    );
+    for id in ids {
+        let old = before_by_id.get(&id).copied();
+        let new = after_by_id.get(&id).copied();
+        if old != new {
+            undo.push(change(id, old.cloned()));
+            redo.push(change(id, new.cloned()));
+        }
+    }
+}
+
+// Returns the keys of the cards that `changes` set, and of the cards that are in a topic they set,
+// taken from both `before` and `after`.
+fn changed_cards(changes: &[Change], before: &Snapshot, after: &Snapshot) -> Vec<FlashcardKey> {
+    let mut cards = HashSet::new();
+    let mut topics = HashSet::new();
+    for change in changes {
+        match change {
+            Change::Card(id, _) => {
+                cards.insert(*id);
+            }
+            Change::Topic(id, _) => {
+                topics.insert(*id);
+            }
+            Change::Namespace(..) => {}
+        }
+    }
+    let mut changed: Vec<FlashcardKey> = [before, after]
+        .into_iter()
+        .flat_map(|snapshot| {
+            snapshot
+                .cards
+                .iter()
+                .filter(|(id, state)| {
+                    cards.contains(id) || state.topics.iter().any(|id| topics.contains(id))
+                })
+                .map(|(id, _)| snapshot.card_keys[id])
+        })
+        .collect();
+    changed.sort();
+    changed.dedup();
+    changed
+}
+
+// Applies changes, adding and updating namespaces, topics and cards before removing any, so every
+// id a state refers to can be found.
+fn apply(study: &mut Study, changes: &[Change]) {
+    for change in changes {
+        if let Change::Namespace(id, Some(namespace)) = change {
+            let key = match study.namespace_by_id(*id) {
+                Some(key) => key,
+                None => study.insert_namespace(namespace.clone()),
+            };
+            study.namespaces[key] = namespace.clone();
+        }
+    }
+    for change in changes {
+        if let Change::Topic(id, Some(state)) = change {
+            let namespaces = state
+                .namespaces
+                .iter()
+                .filter_map(|&id| study.namespace_by_id(id))
+                .collect();
+            match study.topic_by_id(*id) {
+                Some(key) => {
+                    let qna = std::mem::take(&mut study.topics[key].qna);
+                    study.topics[key] = Topic {
+                        qna,
+                        namespaces,
+                        ..state.topic.clone()
+                    };
+                }
+                None => {
+                    study.insert_topic(Topic {
+                        namespaces,
+                        ..state.topic.clone()
+                    });
+                }
+            }
+        }
+    }
+    for change in changes {
+        if let Change::Topic(id, Some(state)) = change {
+            if let Some(key) = study.topic_by_id(*id) {
+                study.topics[key].parent = state.parent.and_then(|id| study.topic_by_id(id));
+            }
+        }
+    }
+    for change in changes {
+        if let Change::Card(id, Some(state)) = change {
+            let topics: Vec<_> = state
+                .topics
+                .iter()
+                .filter_map(|&id| study.topic_by_id(id))
+                .collect();
+            match study.card_by_id(*id) {
+                Some(key) => {
+                    let card = &mut study.cards[key];
+                    let linked = std::mem::take(&mut card.topics);
+                    *card = Flashcard {
+                        topics: linked,
+                        schedule: state.schedule.clone().unwrap_or(card.schedule.clone()),
+                        leech: state.leech.unwrap_or(card.leech),
+                        suspended: state.suspended.unwrap_or(card.suspended),
+                        ..state.card.clone()
+                    };
+                    study.set_card_topics(key, &topics);
+                }
+                None => {
+                    study.insert_card(Flashcard {
+                        topics,
+                        schedule: state.schedule.clone().unwrap_or_default(),
+                        leech: state.leech.unwrap_or_default(),
+                        suspended: state.suspended.unwrap_or_default(),
+                        ..state.card.clone()
+                    });
+                }
+            }
+        }
+    }
+    for change in changes {
+        match change {
+            Change::Card(id, None) => {
+                if let Some(key) = study.card_by_id(*id) {
+                    study.remove_card(key);
+                }
+            }
+            Change::Topic(id, None) => {
+                if let Some(key) = study.topic_by_id(*id) {
+                    study.remove_topic(key);
+                }
+            }
+            _ => {}
+        }
+    }
+    for change in changes {
+        if let Change::Namespace(id, None) = change {
+            if let Some(key) = study.namespace_by_id(*id) {
+                study.remove_namespace(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity;
+    use crate::leech::LeechSettings;
+    use crate::quiz::Namespace;
+    use crate::review::Grade;
+
+    #[test]
+    fn undoes_and_redoes_edits() {
+        let mut study = Study::default();
+        let mut history = History::default();
+        let biology = history.record(&mut study, "add topic", |study| {
+            study.topic_by_name_or_insert("Biology")
+        });
+        let cell = history.record(&mut study, "add topic", |study| {
+            let cell = study.topic_by_name_or_insert("Cell");
+            study.set_parent(cell, Some(biology)).unwrap();
+            let exam = study.insert_namespace(Namespace {
+                name: "Exam".to_string(),
+                ..Default::default()
+            });
+            study.add_to_namespace(cell, exam);
+            cell
+        });
+        let card = history.record(&mut study, "add card", |study| {
+            study.insert_card(Flashcard {
+                question: "Powerhouse?".to_string(),
+                topics: vec![cell],
+                ..Default::default()
+            })
+        });
+        let card_id = study.cards[card].id;
+        let cell_id = study.topics[cell].id;
+
+        // Nothing changed, so nothing is recorded.
+        history.record(&mut study, "nothing", |_| {});
+        history.record(&mut study, "delete topic", |study| study.remove_topic(cell));
+        assert!(study.topics.len() == 1 && study.cards[card].topics.is_empty());
+
+        assert_eq!(history.undo(&mut study).as_deref(), Some("delete topic"));
+        let cell = study.topic_by_id(cell_id).unwrap();
+        assert_eq!(study.topic_path(cell), "Biology > Cell");
+        assert_eq!(study.cards[card].topics, [cell]);
+        assert_eq!(study.topics[cell].namespaces.len(), 1);
+        assert!(integrity::check(&study).is_empty());
+
+        history.undo(&mut study);
+        assert!(study.card_by_id(card_id).is_none());
+        assert_eq!(history.redo(&mut study).as_deref(), Some("add card"));
+        let card = study.card_by_id(card_id).unwrap();
+        assert_eq!(study.cards[card].question, "Powerhouse?");
+        assert!(study.topics[cell].qna.contains(&card));
+
+        while history.undo(&mut study).is_some() {}
+        assert!(study.topics.is_empty() && study.cards.is_empty() && study.namespaces.is_empty());
+        assert!(!history.can_undo() && history.can_redo());
+        // Redoing everything ends with the topic deleted again.
+        while history.redo(&mut study).is_some() {}
+        assert!(study.topic_by_id(cell_id).is_none());
+        assert_eq!(study.cards.len(), 1);
+        assert!(integrity::check(&study).is_empty());
+    }
+
+    #[test]
+    fn undoing_an_edit_keeps_later_reviews() {
+        let mut study = Study::default();
+        let mut history = History::default();
+        study.leech_settings = LeechSettings {
+            threshold: 1,
+            suspend: true,
+        };
+        let card = study.insert_card(Flashcard {
+            question: "Powerhouse?".to_string(),
+            ..Default::default()
+        });
+        history.record(&mut study, "edit card", |study| {
+            study.cards[card].question = "Mitochondria?".to_string();
+        });
+        for _ in 0..2 {
+            study.record_review(card, String::new(), Grade::Again, 0);
+        }
+        let reviewed = study.cards[card].clone();
+        assert!(reviewed.leech && reviewed.suspended);
+
+        history.undo(&mut study);
+        let undone = &study.cards[card];
+        assert_eq!(undone.question, "Powerhouse?");
+        assert_eq!(undone.schedule, reviewed.schedule);
+        assert!(undone.leech && undone.suspended);
+        history.redo(&mut study);
+        assert_eq!(study.cards[card], reviewed);
+
+        // Edits that change these parts themselves are still undone.
+        history.record(&mut study, "forgive leech", |study| {
+            study.forgive_leech(card)
+        });
+        history.undo(&mut study);
+        assert!(study.cards[card].leech && study.cards[card].suspended);
+        assert_eq!(study.reviews_of(card).len(), 2);
+    }
+    #[test]
+    fn returns_the_changed_cards() {
+        let mut study = Study::default();
+        let mut history = History::default();
+        let math = study.topic_by_name_or_insert("Math");
+        let [sum, product, other] = ["1+1?", "2*2?", "Capital of France?"].map(|question| {
+            study.insert_card(Flashcard {
+                question: question.to_string(),
+                ..Default::default()
+            })
+        });
+        study.link(sum, math);
+        study.link(product, math);
+
+        let before = Snapshot::take(&study);
+        study.topics[math].content = "Maths".to_string();
+        assert_eq!(
+            history.commit("rename topic", before, &study),
+            [sum, product]
+        );
+        let before = Snapshot::take(&study);
+        study.remove_card(other);
+        assert_eq!(history.commit("delete card", before, &study), [other]);
+        let before = Snapshot::take(&study);
+        assert!(history.commit("nothing", before, &study).is_empty());
+    }
+}
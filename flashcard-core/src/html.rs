@@ -0,0 +1,208 @@
+//! Static HTML export of a deck.
+//!
+//! The export is a single self-contained page that can be opened in any browser. Every selected
+//! topic gets a section listing its cards, card text goes through the same [`markup`] rendering
+//! the app uses and card images are embedded as data URLs. A small script lets readers flip the
+//! cards and quiz themselves on a topic.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use base64::Engine;
+
+use crate::markup::{self, escape_html};
+use crate::quiz::{ink_color, Color, Flashcard, Study, TopicKey};
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; background: #2b2b2b; color: #fff; margin: 0 auto; max-width: 960px; padding: 1em; }
+nav a { color: #fff; margin-right: 1em; }
+.topic h2 { display: inline-block; margin-right: 1em; }
+.cards { display: grid; grid-template-columns: repeat(auto-fill, minmax(260px, 1fr)); gap: 1em; }
+.card { border-radius: 12px; padding: 1em; min-height: 120px; cursor: pointer; box-shadow: 0 2px 6px #0008; }
+.card .answer, .card.flipped .question { display: none; }
+.card.flipped .answer { display: block; }
+.card img { max-width: 100%; border-radius: 6px; }
+.side p { margin: 0 0 0.4em; }
+#quiz { position: fixed; inset: 0; background: #000c; display: flex; flex-direction: column; align-items: center; justify-content: center; }
+#quiz[hidden] { display: none; }
+#quiz .card { width: 420px; cursor: default; }
+#quiz button { margin: 0.5em; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll('.cards .card').forEach(card =>
+  card.addEventListener('click', () => card.classList.toggle('flipped')));
+
+const quiz = document.getElementById('quiz');
+const slot = document.getElementById('quiz-card');
+const progress = document.getElementById('quiz-progress');
+let queue = [];
+let total = 0;
+
+function showCard() {
+  slot.replaceChildren();
+  if (queue.length === 0) {
+    progress.textContent = 'Finished ' + total + ' cards!';
+    return;
+  }
+  slot.appendChild(queue[0].cloneNode(true));
+  progress.textContent = (total - queue.length + 1) + ' / ' + total;
+}
+
+document.querySelectorAll('.start-quiz').forEach(button =>
+  button.addEventListener('click', () => {
+    queue = [...document.querySelectorAll('#' + button.dataset.topic + ' .cards .card')];
+    for (let i = queue.length - 1; i > 0; i--) {
+      const j = Math.floor(Math.random() * (i + 1));
+      [queue[i], queue[j]] = [queue[j], queue[i]];
+    }
+    total = queue.length;
+    quiz.hidden = false;
+    showCard();
+  }));
+
+document.getElementById('quiz-flip').addEventListener('click', () =>
+  slot.firstChild && slot.firstChild.classList.toggle('flipped'));
+document.getElementById('quiz-again').addEventListener('click', () => {
+  if (queue.length > 0) { queue.push(queue.shift()); showCard(); }
+});
+document.getElementById('quiz-good').addEventListener('click', () => {
+  if (queue.length > 0) { queue.shift(); showCard(); }
+});
+document.getElementById('quiz-close').addEventListener('click', () => quiz.hidden = true);
+"#;
+
+/// Renders the cards of `topics` into a self-contained HTML page.
+pub fn export_html(study: &Study, topics: &[TopicKey]) -> String {
+    let mut nav = String::new();
+    let mut sections = String::new();
+    for (index, topic_key) in topics.iter().enumerate() {
+        let Some(topic) = study.topics.get(*topic_key) else {
+            continue;
+        };
+        let id = format!("topic-{index}");
+        let name = escape_html(&topic.content);
+        nav.push_str(&format!("<a href=\"#{id}\">{name}</a>"));
+
+        sections.push_str(&format!(
+            "<section class=\"topic\" id=\"{id}\"><h2>{name}</h2>\
+             <button class=\"start-quiz\" data-topic=\"{id}\">Quiz</button><div class=\"cards\">"
+        ));
+        for card in study
+            .cards_in_topics(&[*topic_key])
+            .into_iter()
+            .filter_map(|key| study.cards.get(key))
+        {
+            sections.push_str(&card_html(card));
+        }
+        sections.push_str("</div></section>");
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <title>Flashcards</title><style>{STYLE}</style></head><body>\
+         <h1>Flashcards</h1><nav>{nav}</nav>{sections}\
+         <div id=\"quiz\" hidden><div id=\"quiz-card\"></div><p id=\"quiz-progress\"></p><div>\
+         <button id=\"quiz-flip\">Flip</button><button id=\"quiz-again\">Again</button>\
+         <button id=\"quiz-good\">Got it</button><button id=\"quiz-close\">Close</button>\
+         </div></div><script>{SCRIPT}</script></body></html>\n"
+    )
+}
+
+/// Renders the cards of `topics` and writes the page to `path`.
+pub fn write_html(study: &Study, topics: &[TopicKey], path: &Path) -> io::Result<()> {
+    fs::write(path, export_html(study, topics))
+}
+
+fn card_html(card: &Flashcard) -> String {
+    let background = card.bg_color.unwrap_or(Color::WHITE);
+    let image = card
+        .image
+        .as_deref()
+        .and_then(image_data_url)
+        .map(|url| format!("<img src=\"{url}\" alt=\"\">"))
+        .unwrap_or_default();
+    format!(
+        "<article class=\"card\" style=\"background: {}; color: {}\">\
+         <div class=\"side question\">{}</div><div class=\"side answer\">{}</div>{image}</article>",
+        css_color(background),
+        css_color(ink_color(background)),
+        markup::to_html(&card.question),
+        markup::to_html(&card.answer),
+    )
+}
+
+// Images that cannot be read are left out rather than failing the whole export, with a note on
+// stderr.
+fn image_data_url(path: &Path) -> Option<String> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            eprintln!("Skipping image {}: {error}", path.display());
+            return None;
+        }
+    };
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let mime = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => "application/octet-stream",
+    };
+    let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+    Some(format!("data:{mime};base64,{data}"))
+}
+
+fn css_color(color: Color) -> String {
+    let [r, g, b, _] = color.into_rgba8();
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_a_section_per_topic() {
+        let mut study = Study::default();
+        let biology = study.topic_by_name_or_insert("Biology");
+        let chemistry = study.topic_by_name_or_insert("Chemistry & Physics");
+        let hidden = study.topic_by_name_or_insert("Hidden");
+        study.insert_card(Flashcard {
+            question: "What is **ATP**?".to_string(),
+            answer: "Energy".to_string(),
+            bg_color: Some(Color::BLACK),
+            topics: vec![biology],
+            ..Default::default()
+        });
+        study.insert_card(Flashcard {
+            question: "Secret".to_string(),
+            topics: vec![hidden],
+            ..Default::default()
+        });
+
+        let html = export_html(&study, &[biology, chemistry]);
+        assert!(html.contains("<h2>Biology</h2>"));
+        assert!(html.contains("<h2>Chemistry &amp; Physics</h2>"));
+        assert!(html.contains("<p>What is <strong>ATP</strong>?</p>"));
+        assert!(html.contains("background: #000000; color: #ffffff"));
+        assert!(!html.contains("Secret"));
+    }
+
+    #[test]
+    fn embeds_images() {
+        let name = format!("flashcard-rs-html-test-{}.png", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, b"png").unwrap();
+        assert_eq!(image_data_url(&path).unwrap(), "data:image/png;base64,cG5n");
+        fs::remove_file(&path).unwrap();
+        assert_eq!(image_data_url(&path), None);
+    }
+}
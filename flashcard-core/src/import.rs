@@ -0,0 +1,145 @@
+//! Importers that bring decks made in other flashcard programs, and deck files and bundles shared
+//! by other users, into a [`Study`].
+//!
+//! Every importer adds its cards to the study session, turning the categories of the source
+//! program into topics (topics that already exist with the same name are reused) and carrying
+//! over the repetition history into [`Schedule`](crate::quiz::Schedule) when the format has one.
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::quiz::{FlashcardKey, Study, TopicKey};
+use crate::{bundle, deck};
+
+pub mod mnemosyne;
+pub mod supermemo;
+
+/// An error raised while importing a deck.
+#[derive(Debug)]
+pub enum ImportError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file is not well-formed XML.
+    Xml(roxmltree::Error),
+    /// The file is not a valid zip archive.
+    Zip(zip::result::ZipError),
+    /// A JSON document inside the file is malformed.
+    Json(serde_json::Error),
+    /// The file was readable but not in a format the importer understands.
+    Format(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(error) => write!(f, "could not read file: {error}"),
+            ImportError::Xml(error) => write!(f, "invalid XML: {error}"),
+            ImportError::Zip(error) => write!(f, "invalid archive: {error}"),
+            ImportError::Json(error) => write!(f, "invalid JSON: {error}"),
+            ImportError::Format(reason) => write!(f, "unsupported deck: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(error: io::Error) -> Self {
+        ImportError::Io(error)
+    }
+}
+
+impl From<roxmltree::Error> for ImportError {
+    fn from(error: roxmltree::Error) -> Self {
+        ImportError::Xml(error)
+    }
+}
+
+impl From<zip::result::ZipError> for ImportError {
+    fn from(error: zip::result::ZipError) -> Self {
+        ImportError::Zip(error)
+    }
+}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(error: serde_json::Error) -> Self {
+        ImportError::Json(error)
+    }
+}
+
+/// What an import added to the study session.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ImportSummary {
+    pub cards: usize,
+    pub topics: usize,
+}
+
+impl fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "imported {} cards and {} topics",
+            self.cards, self.topics
+        )
+    }
+}
+
+/// Imports the deck at `path`, picking the importer from the file extension and, for XML
+/// files, from the root element.
+pub fn import_file(study: &mut Study, path: &Path) -> Result<ImportSummary, ImportError> {
+    let extension = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "cards" => mnemosyne::import_cards_file(study, path),
+        bundle::EXTENSION => bundle::import_bundle(study, path, &bundle::media_dir(path)),
+        deck::EXTENSION => deck::import_deck(study, path),
+        "txt" => supermemo::import_qa_text(study, &fs::read_to_string(path)?),
+        _ => import_xml(study, &fs::read_to_string(path)?),
+    }
+}
+
+/// Imports an XML export from either Mnemosyne or SuperMemo.
+pub fn import_xml(study: &mut Study, xml: &str) -> Result<ImportSummary, ImportError> {
+    let document = roxmltree::Document::parse(xml)?;
+    match document.root_element().tag_name().name() {
+        "mnemosyne" | "openSM2sync" => mnemosyne::import_document(study, &document),
+        "SuperMemoCollection" | "SuperMemoElement" => supermemo::import_document(study, &document),
+        other => Err(ImportError::Format(format!(
+            "unknown root element <{other}>"
+        ))),
+    }
+}
+
+/// Copies every card and topic of `other` into `study`.
+pub(crate) fn merge_all(study: &mut Study, other: &Study) -> ImportSummary {
+    let existing_topics = study.topics.len();
+    let topics: Vec<TopicKey> = other.topics.keys().collect();
+    let cards: Vec<FlashcardKey> = other.cards.keys().collect();
+    let imported = study.merge_from(other, &topics, &cards);
+    ImportSummary {
+        cards: imported.len(),
+        topics: study.topics.len() - existing_topics,
+    }
+}
+
+/// Counts what was added to `study` since `cards` cards and `topics` topics were present.
+fn summary_since(study: &Study, cards: usize, topics: usize) -> ImportSummary {
+    ImportSummary {
+        cards: study.cards.len() - cards,
+        topics: study.topics.len() - topics,
+    }
+}
+
+/// Returns the trimmed text of the first child element called `name`.
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+        .map(str::trim)
+}
@@ -0,0 +1,273 @@
+//! Importer for Mnemosyne decks.
+//!
+//! Mnemosyne 1.x exports a single XML file with a `<mnemosyne>` root, in which `<category>`
+//! elements name the categories and `<item>` elements hold the cards. Mnemosyne 2.x exports
+//! `.cards` files: a zip archive whose `cards.xml` is an `<openSM2sync>` log of the tags, facts
+//! and cards that were added, plus any later edits and repetitions.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+use roxmltree::{Document, Node};
+
+use super::{child_text, summary_since, ImportError, ImportSummary};
+use crate::quiz::{Flashcard, FlashcardKey, Schedule, Study, TopicKey, SECONDS_PER_DAY};
+
+// Log entry types used by the Mnemosyne 2.x sync format.
+const ADDED_CARD: &str = "6";
+const EDITED_CARD: &str = "7";
+const REPETITION: &str = "9";
+const ADDED_TAG: &str = "10";
+const ADDED_FACT: &str = "16";
+
+// Mnemosyne tags every card without a tag with this one; it is not a real category.
+const UNTAGGED: &str = "__UNTAGGED__";
+
+/// Imports a Mnemosyne 2.x `.cards` archive.
+pub fn import_cards_file(study: &mut Study, path: &Path) -> Result<ImportSummary, ImportError> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    let mut xml = String::new();
+    archive.by_name("cards.xml")?.read_to_string(&mut xml)?;
+    super::import_xml(study, &xml)
+}
+
+/// Imports a parsed Mnemosyne 1.x export or Mnemosyne 2.x `cards.xml`.
+pub fn import_document(
+    study: &mut Study,
+    document: &Document,
+) -> Result<ImportSummary, ImportError> {
+    let (cards, topics) = (study.cards.len(), study.topics.len());
+    let root = document.root_element();
+    if root.has_tag_name("mnemosyne") {
+        import_items(study, root);
+    } else {
+        import_log(study, root);
+    }
+    Ok(summary_since(study, cards, topics))
+}
+
+// Mnemosyne 1.x stores review days relative to the `time_of_start` of the collection. Days that
+// do not fit a time, as only a corrupt file can have, are not converted.
+fn import_items(study: &mut Study, root: Node) {
+    let start: u64 = attribute(root, "time_of_start").unwrap_or(0);
+    let to_time = |days: i64| {
+        u64::try_from(days)
+            .ok()?
+            .checked_mul(SECONDS_PER_DAY)?
+            .checked_add(start)
+    };
+
+    for category in root.children().filter(|node| node.has_tag_name("category")) {
+        if let Some(name) = child_text(category, "name").filter(|name| !name.is_empty()) {
+            study.topic_by_name_or_insert(name);
+        }
+    }
+
+    for item in root.children().filter(|node| node.has_tag_name("item")) {
+        let topics = child_text(item, "cat")
+            .filter(|name| !name.is_empty())
+            .map(|name| vec![study.topic_by_name_or_insert(name)])
+            .unwrap_or_default();
+        study.insert_card(Flashcard {
+            question: child_text(item, "Q").unwrap_or_default().to_string(),
+            answer: child_text(item, "A").unwrap_or_default().to_string(),
+            topics,
+            schedule: schedule_from(item, to_time),
+            ..Default::default()
+        });
+    }
+}
+
+// Mnemosyne 2.x replays a log, where cards refer to their fact and tags by id.
+fn import_log(study: &mut Study, root: Node) {
+    let mut tags: HashMap<&str, TopicKey> = HashMap::new();
+    let mut facts: HashMap<&str, Node> = HashMap::new();
+    let mut cards: HashMap<&str, FlashcardKey> = HashMap::new();
+    let to_time = |time: i64| u64::try_from(time).ok();
+
+    for log in root.children().filter(|node| node.has_tag_name("log")) {
+        let Some(id) = log.attribute("o_id") else {
+            continue;
+        };
+        match log.attribute("type") {
+            Some(ADDED_TAG) => {
+                if let Some(name) = child_text(log, "name").filter(|name| *name != UNTAGGED) {
+                    tags.insert(id, study.topic_by_name_or_insert(name));
+                }
+            }
+            Some(ADDED_FACT) => {
+                facts.insert(id, log);
+            }
+            Some(ADDED_CARD) => {
+                let Some(fact) = log.attribute("fact").and_then(|fact| facts.get(fact)) else {
+                    continue;
+                };
+                let (front, back) = fact_sides(*fact);
+                // The second view of a fact (e.g. "2.2") is the reverse card.
+                let reversed = log
+                    .attribute("fact_v")
+                    .is_some_and(|view| view.ends_with(".2"));
+                let (question, answer) = if reversed {
+                    (back, front)
+                } else {
+                    (front, back)
+                };
+                let topics = log
+                    .attribute("tags")
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter_map(|tag| tags.get(tag.trim()).copied())
+                    .collect();
+                let key = study.insert_card(Flashcard {
+                    question,
+                    answer,
+                    topics,
+                    schedule: schedule_from(log, to_time),
+                    ..Default::default()
+                });
+                cards.insert(id, key);
+            }
+            Some(EDITED_CARD) | Some(REPETITION) => {
+                if let Some(card) = cards.get(id).and_then(|key| study.cards.get_mut(*key)) {
+                    card.schedule = schedule_from(log, to_time);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Front-to-back facts use `f` and `b`; vocabulary facts spread the back over pronunciation
+// (`p_1`) and meaning (`m_1`).
+fn fact_sides(fact: Node) -> (String, String) {
+    let front = child_text(fact, "f").unwrap_or_default().to_string();
+    let back = match child_text(fact, "b") {
+        Some(back) => back.to_string(),
+        None => ["p_1", "m_1"]
+            .iter()
+            .filter_map(|key| child_text(fact, key))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    (front, back)
+}
+
+// Reads the repetition state shared by 1.x items and 2.x card logs. A grade of -1 marks a card
+// that was never reviewed, whose schedule is left at its default, and so is the schedule of a
+// card whose review times cannot be converted.
+fn schedule_from(node: Node, to_time: impl Fn(i64) -> Option<u64>) -> Schedule {
+    if attribute::<i32>(node, "gr").unwrap_or(-1) < 0 {
+        return Schedule::default();
+    }
+    let time = |name| match attribute(node, name) {
+        Some(time) => to_time(time).map(Some).ok_or(()),
+        None => Ok(None),
+    };
+    let (Ok(last_review), Ok(next_review)) = (time("l_rp"), time("n_rp")) else {
+        return Schedule::default();
+    };
+    let interval = match (last_review, next_review) {
+        (Some(last), Some(next)) => next.saturating_sub(last) / SECONDS_PER_DAY,
+        _ => 0,
+    };
+    Schedule {
+        repetitions: attribute::<u32>(node, "ac_rp")
+            .unwrap_or(0)
+            .saturating_add(attribute::<u32>(node, "rt_rp").unwrap_or(0)),
+        lapses: attribute(node, "lps").unwrap_or(0),
+        easiness: attribute(node, "e").unwrap_or(Schedule::default().easiness),
+        interval: u32::try_from(interval).unwrap_or(u32::MAX),
+        last_review,
+        next_review,
+    }
+}
+
+fn attribute<T: FromStr>(node: Node, name: &str) -> Option<T> {
+    node.attribute(name)?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::import_xml;
+
+    #[test]
+    fn imports_version_one_items() {
+        let xml = r#"<mnemosyne core_version="1" time_of_start="1000">
+            <category active="1"><name>Biology</name></category>
+            <item id="a" gr="4" e="2.6" ac_rp="1" rt_rp="2" lps="1" l_rp="2" n_rp="6">
+                <cat>Biology</cat><Q>What is a cell?</Q><A>The unit of life</A>
+            </item>
+            <item id="b" gr="-1"><cat>Chemistry</cat><Q>H2O</Q><A>Water</A></item>
+        </mnemosyne>"#;
+
+        let mut study = Study::default();
+        let summary = import_xml(&mut study, xml).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                cards: 2,
+                topics: 2
+            }
+        );
+
+        let (_, cell) = study
+            .cards
+            .iter()
+            .find(|(_, card)| card.answer == "The unit of life")
+            .unwrap();
+        assert_eq!(study.topics[cell.topics[0]].content, "Biology");
+        assert_eq!(cell.schedule.repetitions, 3);
+        assert_eq!(cell.schedule.lapses, 1);
+        assert_eq!(cell.schedule.interval, 4);
+        assert_eq!(cell.schedule.last_review, Some(1000 + 2 * SECONDS_PER_DAY));
+
+        // Days too large for a time leave the card unscheduled instead of overflowing.
+        let xml = r#"<mnemosyne core_version="1" time_of_start="1000">
+            <item id="c" gr="4" ac_rp="1" l_rp="2" n_rp="9223372036854775807">
+                <Q>Big?</Q><A>Yes</A>
+            </item>
+        </mnemosyne>"#;
+        import_xml(&mut study, xml).unwrap();
+        let (_, big) = study
+            .cards
+            .iter()
+            .find(|(_, card)| card.answer == "Yes")
+            .unwrap();
+        assert_eq!(big.schedule, Schedule::default());
+    }
+
+    #[test]
+    fn imports_version_two_log() {
+        let xml = r#"<openSM2sync number_of_entries="5">
+            <log type="10" o_id="t1"><name>Spanish</name></log>
+            <log type="16" o_id="f1"><f>perro</f><b>dog</b></log>
+            <log type="6" o_id="c1" card_t="2" fact="f1" fact_v="2.1" tags="t1" gr="-1"/>
+            <log type="6" o_id="c2" card_t="2" fact="f1" fact_v="2.2" tags="t1" gr="-1"/>
+            <log type="9" o_id="c1" gr="5" e="2.7" ac_rp="1" rt_rp="0" lps="0" l_rp="86400" n_rp="259200"/>
+        </openSM2sync>"#;
+
+        let mut study = Study::default();
+        import_xml(&mut study, xml).unwrap();
+
+        let questions: Vec<_> = study
+            .cards
+            .values()
+            .map(|card| card.question.as_str())
+            .collect();
+        assert_eq!(questions, ["perro", "dog"]);
+        let (topic_key, topic) = study.topics.iter().next().unwrap();
+        assert_eq!(topic.content, "Spanish");
+        assert_eq!(topic.qna.len(), 2);
+        let reviewed = study
+            .cards
+            .values()
+            .find(|card| card.question == "perro")
+            .unwrap();
+        assert_eq!(reviewed.topics, [topic_key]);
+        assert_eq!(reviewed.schedule.interval, 2);
+    }
+}
@@ -0,0 +1,224 @@
+//! Importer for SuperMemo decks.
+//!
+//! SuperMemo can export a collection as XML, where `<SuperMemoElement>`s nest inside each other:
+//! topic and concept elements act as categories and item elements hold a question and answer
+//! along with their `<LearningData>`. It can also export items as Q&A text, one `Q:` line and
+//! one `A:` line per card with cards separated by blank lines.
+use roxmltree::{Document, Node};
+
+use super::{child_text, summary_since, ImportError, ImportSummary};
+use crate::quiz::{Flashcard, Schedule, Study, SECONDS_PER_DAY};
+
+/// Imports a parsed SuperMemo XML collection.
+pub fn import_document(
+    study: &mut Study,
+    document: &Document,
+) -> Result<ImportSummary, ImportError> {
+    let (cards, topics) = (study.cards.len(), study.topics.len());
+    import_element(study, document.root_element(), None);
+    Ok(summary_since(study, cards, topics))
+}
+
+/// Imports a SuperMemo Q&A text export.
+///
+/// Lines without a `Q:` or `A:` prefix continue the question or answer above them.
+pub fn import_qa_text(study: &mut Study, text: &str) -> Result<ImportSummary, ImportError> {
+    let (cards, topics) = (study.cards.len(), study.topics.len());
+    let mut card = Flashcard::default();
+    let mut in_answer = false;
+
+    for line in text.lines().chain(std::iter::once("")) {
+        let line = line.trim_end();
+        if let Some(question) = strip_prefix(line, 'q') {
+            flush_card(study, &mut card);
+            card.question = question.to_string();
+            in_answer = false;
+        } else if let Some(answer) = strip_prefix(line, 'a') {
+            card.answer = answer.to_string();
+            in_answer = true;
+        } else if line.trim().is_empty() {
+            flush_card(study, &mut card);
+            in_answer = false;
+        } else if in_answer || !card.question.is_empty() {
+            let field = if in_answer {
+                &mut card.answer
+            } else {
+                &mut card.question
+            };
+            field.push('\n');
+            field.push_str(line.trim());
+        }
+    }
+
+    if study.cards.len() == cards {
+        return Err(ImportError::Format("no Q: and A: pairs found".to_string()));
+    }
+    Ok(summary_since(study, cards, topics))
+}
+
+fn strip_prefix(line: &str, prefix: char) -> Option<&str> {
+    let mut chars = line.chars();
+    if chars.next()?.eq_ignore_ascii_case(&prefix) && chars.next()? == ':' {
+        Some(chars.as_str().trim())
+    } else {
+        None
+    }
+}
+
+fn flush_card(study: &mut Study, card: &mut Flashcard) {
+    if !card.question.is_empty() || !card.answer.is_empty() {
+        study.insert_card(std::mem::take(card));
+    }
+}
+
+// Walks the element tree, remembering the name of the closest enclosing topic or concept.
+fn import_element(study: &mut Study, element: Node, category: Option<&str>) {
+    let mut category = category;
+    if element.has_tag_name("SuperMemoElement") {
+        match child_text(element, "Type").unwrap_or_default() {
+            "Item" => {
+                import_item(study, element, category);
+                return;
+            }
+            _ => {
+                let content = element.children().find(|node| node.has_tag_name("Content"));
+                category = child_text(element, "Title")
+                    .or_else(|| content.and_then(|content| child_text(content, "Question")))
+                    .filter(|title| !title.is_empty())
+                    .or(category);
+            }
+        }
+    }
+    for child in element
+        .children()
+        .filter(|node| node.has_tag_name("SuperMemoElement"))
+    {
+        import_element(study, child, category);
+    }
+}
+
+fn import_item(study: &mut Study, element: Node, category: Option<&str>) {
+    let Some(content) = element.children().find(|node| node.has_tag_name("Content")) else {
+        return;
+    };
+    let topics = category
+        .map(|name| vec![study.topic_by_name_or_insert(name)])
+        .unwrap_or_default();
+    let schedule = element
+        .children()
+        .find(|node| node.has_tag_name("LearningData"))
+        .map(schedule_from)
+        .unwrap_or_default();
+    study.insert_card(Flashcard {
+        question: child_text(content, "Question")
+            .unwrap_or_default()
+            .to_string(),
+        answer: child_text(content, "Answer")
+            .unwrap_or_default()
+            .to_string(),
+        topics,
+        schedule,
+        ..Default::default()
+    });
+}
+
+fn schedule_from(learning_data: Node) -> Schedule {
+    let number = |name| child_text(learning_data, name).and_then(|text| text.parse().ok());
+    let interval: u32 = number("Interval").unwrap_or(0);
+    let last_review = child_text(learning_data, "LastRepetition").and_then(parse_date);
+    Schedule {
+        repetitions: number("Repetitions").unwrap_or(0),
+        lapses: number("Lapses").unwrap_or(0),
+        easiness: child_text(learning_data, "AFactor")
+            .and_then(|text| text.parse().ok())
+            .unwrap_or(Schedule::default().easiness),
+        interval,
+        last_review,
+        next_review: last_review.map(|last| last + u64::from(interval) * SECONDS_PER_DAY),
+    }
+}
+
+// SuperMemo writes dates as `dd.mm.yyyy`; they are turned into a unix timestamp at midnight.
+fn parse_date(date: &str) -> Option<u64> {
+    let mut parts = date.split('.').map(|part| part.trim().parse::<i64>().ok());
+    let (day, month, year) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Days since 1970-01-01 in the proleptic Gregorian calendar.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    u64::try_from(days).ok().map(|days| days * SECONDS_PER_DAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::import_xml;
+
+    #[test]
+    fn imports_xml_collection() {
+        let xml = r#"<SuperMemoCollection>
+            <Count>2</Count>
+            <SuperMemoElement>
+                <ID>1</ID><Title>Geography</Title><Type>Topic</Type>
+                <SuperMemoElement>
+                    <ID>2</ID><Type>Item</Type>
+                    <Content><Question>Capital of France?</Question><Answer>Paris</Answer></Content>
+                    <LearningData>
+                        <Interval>10</Interval><Repetitions>3</Repetitions><Lapses>1</Lapses>
+                        <LastRepetition>02.01.1970</LastRepetition><AFactor>3.1</AFactor>
+                    </LearningData>
+                </SuperMemoElement>
+            </SuperMemoElement>
+        </SuperMemoCollection>"#;
+
+        let mut study = Study::default();
+        let summary = import_xml(&mut study, xml).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                cards: 1,
+                topics: 1
+            }
+        );
+
+        let card = study.cards.values().next().unwrap();
+        assert_eq!(card.answer, "Paris");
+        assert_eq!(study.topics[card.topics[0]].content, "Geography");
+        assert_eq!(card.schedule.last_review, Some(SECONDS_PER_DAY));
+        assert_eq!(card.schedule.next_review, Some(11 * SECONDS_PER_DAY));
+    }
+
+    #[test]
+    fn imports_qa_text() {
+        let text = "Q: First question\nA: First answer\n\nQ: Second\nquestion\nA: Second answer\n";
+        let mut study = Study::default();
+        import_qa_text(&mut study, text).unwrap();
+
+        let cards: Vec<_> = study
+            .cards
+            .values()
+            .map(|card| (card.question.as_str(), card.answer.as_str()))
+            .collect();
+        assert_eq!(
+            cards,
+            [
+                ("First question", "First answer"),
+                ("Second\nquestion", "Second answer")
+            ]
+        );
+        assert!(import_qa_text(&mut study, "nothing here").is_err());
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(parse_date("01.01.1970"), Some(0));
+        assert_eq!(parse_date("29.02.2000"), Some(11_016 * SECONDS_PER_DAY));
+        assert_eq!(parse_date("garbage"), None);
+    }
+}
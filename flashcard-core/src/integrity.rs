@@ -0,0 +1,276 @@
+//! Checking and repairing the links inside a study session.
+//!
+//! A card lists its topics and a topic lists its cards, so every link is stored twice. The
+//! [`Study`] methods keep both sides in sync, but decks written by older versions or edited by
+//! hand can contain dangling keys or links that only one side knows about. [`check`] reports those
+//! problems and [`repair`] fixes them; decks are repaired when they are loaded.
+use crate::quiz::{FlashcardKey, NamespaceKey, Study, TopicKey};
+
+/// Something wrong with the links of a study session.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// A card lists a topic that does not exist. Repaired by dropping the topic from the card.
+    MissingTopic { card: FlashcardKey, topic: TopicKey },
+    /// A topic lists a card that does not exist. Repaired by dropping the card from the topic.
+    MissingCard { topic: TopicKey, card: FlashcardKey },
+    /// Only one side of a link between a card and a topic knows about it. Repaired by adding the
+    /// other side.
+    OneSidedLink { card: FlashcardKey, topic: TopicKey },
+    /// A card or a topic lists the same link more than once. Repaired by keeping the first one.
+    DuplicateLink { card: FlashcardKey, topic: TopicKey },
+    /// The parent of a topic does not exist, or the topic is its own ancestor. Repaired by moving
+    /// the topic to the top level.
+    BrokenParent { topic: TopicKey },
+    /// A topic lists a namespace that does not exist. Repaired by dropping the namespace.
+    MissingNamespace {
+        topic: TopicKey,
+        namespace: NamespaceKey,
+    },
+}
+
+impl Problem {
+    /// Describes the problem, naming the cards and topics involved.
+    pub fn describe(&self, study: &Study) -> String {
+        let card = |key: &FlashcardKey| {
+            study
+                .cards
+                .get(*key)
+                .map_or("a missing card".to_string(), |card| {
+                    format!("card '{}'", card.question)
+                })
+        };
+        let topic = |key: &TopicKey| {
+            study
+                .topics
+                .get(*key)
+                .map_or("a missing topic".to_string(), |topic| {
+                    format!("topic '{}'", topic.content)
+                })
+        };
+        match self {
+            Problem::MissingTopic { card: key, .. } => {
+                format!("{} is in a topic that does not exist", card(key))
+            }
+            Problem::MissingCard { topic: key, .. } => {
+                format!("{} lists a card that does not exist", topic(key))
+            }
+            Problem::OneSidedLink {
+                card: card_key,
+                topic: topic_key,
+            } => format!(
+                "{} and {} only have half a link",
+                card(card_key),
+                topic(topic_key)
+            ),
+            Problem::DuplicateLink {
+                card: card_key,
+                topic: topic_key,
+            } => format!(
+                "{} and {} are linked twice",
+                card(card_key),
+                topic(topic_key)
+            ),
+            Problem::BrokenParent { topic: key } => {
+                format!(
+                    "{} has a parent that does not exist or is its own sub-topic",
+                    topic(key)
+                )
+            }
+            Problem::MissingNamespace { topic: key, .. } => {
+                format!("{} is in a namespace that does not exist", topic(key))
+            }
+        }
+    }
+}
+
+/// Returns every problem with the links of `study`, without changing anything.
+pub fn check(study: &Study) -> Vec<Problem> {
+    let mut problems = vec![];
+    for (card_key, card) in &study.cards {
+        for (index, &topic) in card.topics.iter().enumerate() {
+            if card.topics[..index].contains(&topic) {
+                problems.push(Problem::DuplicateLink {
+                    card: card_key,
+                    topic,
+                });
+            } else if !study.topics.contains_key(topic) {
+                problems.push(Problem::MissingTopic {
+                    card: card_key,
+                    topic,
+                });
+            } else if !study.topics[topic].qna.contains(&card_key) {
+                problems.push(Problem::OneSidedLink {
+                    card: card_key,
+                    topic,
+                });
+            }
+        }
+    }
+    for (topic_key, topic) in &study.topics {
+        for (index, &card) in topic.qna.iter().enumerate() {
+            if topic.qna[..index].contains(&card) {
+                problems.push(Problem::DuplicateLink {
+                    card,
+                    topic: topic_key,
+                });
+            } else if !study.cards.contains_key(card) {
+                problems.push(Problem::MissingCard {
+                    topic: topic_key,
+                    card,
+                });
+            } else if !study.cards[card].topics.contains(&topic_key) {
+                problems.push(Problem::OneSidedLink {
+                    card,
+                    topic: topic_key,
+                });
+            }
+        }
+        if has_broken_parent(study, topic_key) {
+            problems.push(Problem::BrokenParent { topic: topic_key });
+        }
+        for &namespace in &topic.namespaces {
+            if !study.namespaces.contains_key(namespace) {
+                problems.push(Problem::MissingNamespace {
+                    topic: topic_key,
+                    namespace,
+                });
+            }
+        }
+    }
+    problems
+}
+
+/// Fixes every problem found by [`check`] and returns them.
+pub fn repair(study: &mut Study) -> Vec<Problem> {
+    let problems = check(study);
+    for problem in &problems {
+        match *problem {
+            Problem::MissingTopic { card, topic } => {
+                study.cards[card].topics.retain(|&other| other != topic);
+            }
+            Problem::MissingCard { topic, card } => {
+                study.topics[topic].qna.retain(|&other| other != card);
+            }
+            Problem::OneSidedLink { card, topic } => study.link(card, topic),
+            Problem::DuplicateLink { card, topic } => {
+                if let Some(card_entry) = study.cards.get_mut(card) {
+                    keep_first(&mut card_entry.topics, topic);
+                }
+                if let Some(topic_entry) = study.topics.get_mut(topic) {
+                    keep_first(&mut topic_entry.qna, card);
+                }
+            }
+            // Cutting one topic of a cycle repairs the others, so each is checked again.
+            Problem::BrokenParent { topic } => {
+                if has_broken_parent(study, topic) {
+                    study.topics[topic].parent = None;
+                }
+            }
+            Problem::MissingNamespace { topic, namespace } => {
+                study.topics[topic]
+                    .namespaces
+                    .retain(|&other| other != namespace);
+            }
+        }
+    }
+    problems
+}
+
+// Whether the parent of a topic is missing, or following the parents leads back to the topic.
+fn has_broken_parent(study: &Study, topic: TopicKey) -> bool {
+    let Some(parent) = study.topics[topic].parent else {
+        return false;
+    };
+    if !study.topics.contains_key(parent) {
+        return true;
+    }
+    let mut current = Some(parent);
+    // A cycle further up that does not reach the topic is reported for the topics in it.
+    for _ in 0..study.topics.len() {
+        match current {
+            Some(key) if key == topic => return true,
+            Some(key) => current = study.topics.get(key).and_then(|topic| topic.parent),
+            None => return false,
+        }
+    }
+    false
+}
+
+// Removes every copy of `key` after the first.
+fn keep_first<K: PartialEq + Copy>(keys: &mut Vec<K>, key: K) {
+    let mut seen = false;
+    keys.retain(|&other| {
+        if other != key {
+            return true;
+        }
+        let first = !seen;
+        seen = true;
+        first
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::Flashcard;
+
+    #[test]
+    fn finds_and_repairs_broken_links() {
+        let mut study = Study::default();
+        let math = study.topic_by_name_or_insert("Math");
+        let gone = study.topic_by_name_or_insert("Gone");
+        let card = study.insert_card(Flashcard {
+            question: "1+1?".to_string(),
+            topics: vec![math, gone],
+            ..Default::default()
+        });
+        // Removing the topic by hand leaves the card pointing at it.
+        study.topics.remove(gone);
+        // One-sided and duplicate links, as left by older versions.
+        let other = study.topic_by_name_or_insert("Other");
+        study.topics[other].qna.push(card);
+        study.cards[card].topics.push(math);
+        study.topics[math].parent = Some(math);
+
+        let problems = check(&study);
+        assert_eq!(
+            problems,
+            [
+                Problem::MissingTopic { card, topic: gone },
+                Problem::DuplicateLink { card, topic: math },
+                Problem::BrokenParent { topic: math },
+                Problem::OneSidedLink { card, topic: other },
+            ]
+        );
+        assert_eq!(
+            problems[0].describe(&study),
+            "card '1+1?' is in a topic that does not exist"
+        );
+
+        assert_eq!(repair(&mut study), problems);
+        assert!(check(&study).is_empty());
+        assert_eq!(study.cards[card].topics, [math, other]);
+        assert_eq!(study.topics[math].parent, None);
+    }
+
+    #[test]
+    fn link_api_keeps_both_sides_in_sync() {
+        let mut study = Study::default();
+        let math = study.topic_by_name_or_insert("Math");
+        let history = study.topic_by_name_or_insert("History");
+        let card = study.insert_card(Flashcard {
+            topics: vec![math],
+            ..Default::default()
+        });
+        study.set_card_topics(card, &[history, math]);
+        study.unlink(card, math);
+        study.link(card, history);
+        assert_eq!(study.cards[card].topics, [history]);
+        assert_eq!(study.topics[history].qna, [card]);
+        assert!(study.topics[math].qna.is_empty());
+
+        study.remove_topic(history);
+        study.remove_card(card);
+        assert!(check(&study).is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+//! Leeches: cards that keep being forgotten.
+//!
+//! A card becomes a leech when it has been forgotten more times than the threshold of
+//! [`LeechSettings`]. Leeches are flagged, and suspended as well when the settings ask for it, so
+//! they stop taking up study time until they are rewritten. A leech is not requeued in a running
+//! quiz either, so a card that is always answered wrong cannot keep a quiz going forever.
+use serde::{Deserialize, Serialize};
+
+use crate::quiz::{FlashcardKey, Study};
+
+// How many lapses a card may have before it is a leech, and whether leeches are suspended.
+// Saved with the deck.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LeechSettings {
+    pub threshold: u32,
+    pub suspend: bool,
+}
+
+impl Default for LeechSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 8,
+            suspend: false,
+        }
+    }
+}
+
+impl Study {
+    /// Flags a card as a leech once its lapses pass the threshold, suspending it when the
+    /// settings say so. Returns whether the card became a leech just now.
+    pub fn check_leech(&mut self, card: FlashcardKey) -> bool {
+        let settings = self.leech_settings;
+        let Some(card) = self.cards.get_mut(card) else {
+            return false;
+        };
+        if card.leech || settings.threshold == 0 || card.schedule.lapses <= settings.threshold {
+            return false;
+        }
+        card.leech = true;
+        card.suspended |= settings.suspend;
+        true
+    }
+
+    /// Returns the leeches, the most often forgotten first.
+    pub fn leeches(&self) -> Vec<FlashcardKey> {
+        let mut leeches: Vec<FlashcardKey> = self
+            .cards
+            .iter()
+            .filter(|(_, card)| card.leech)
+            .map(|(key, _)| key)
+            .collect();
+        leeches.sort_by_key(|&key| std::cmp::Reverse(self.cards[key].schedule.lapses));
+        leeches
+    }
+
+    /// Takes the leech flag off a card, typically after rewriting it, and unsuspends it. Its
+    /// lapses are counted again from zero, so it only becomes a leech again if the new version
+    /// keeps being forgotten too.
+    pub fn forgive_leech(&mut self, card: FlashcardKey) {
+        if let Some(card) = self.cards.get_mut(card) {
+            card.leech = false;
+            card.suspended = false;
+            card.schedule.lapses = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::{Flashcard, Quiz, Topic};
+
+    #[test]
+    fn suspends_leeches() {
+        let mut study = Study {
+            leech_settings: LeechSettings {
+                threshold: 3,
+                suspend: true,
+            },
+            ..Default::default()
+        };
+        let math = study.insert_topic(Topic {
+            content: "Math".to_string(),
+            enabled: true,
+            ..Default::default()
+        });
+        let hard = study.insert_card(Flashcard {
+            question: "17*23?".to_string(),
+            answer: "391".to_string(),
+            topics: vec![math],
+            ..Default::default()
+        });
+        let easy = study.insert_card(Flashcard {
+            question: "1+1?".to_string(),
+            answer: "2".to_string(),
+            topics: vec![math],
+            ..Default::default()
+        });
+        let mut quiz = Quiz::default();
+        quiz.start_quiz(&study);
+
+        // The quiz ends even though the hard card is never answered right: after its fourth lapse
+        // it is a leech and is not asked again.
+        let mut answers = 0;
+        while let Some(question) = quiz.qna_queue.front() {
+            let answer = if question.answer == "2" { "2" } else { "400" };
+            quiz.answer_current(&mut study, answer.to_string(), 0);
+            answers += 1;
+        }
+        assert_eq!(answers, 5);
+        assert!(study.cards[hard].leech && study.cards[hard].suspended);
+        assert_eq!(study.leeches(), [hard]);
+
+        // Suspended cards are left out of the next quiz.
+        quiz.start_quiz(&study);
+        assert_eq!(quiz.cards, [easy]);
+
+        study.forgive_leech(hard);
+        assert!(study.leeches().is_empty());
+        assert_eq!(study.cards[hard].schedule.lapses, 0);
+        quiz.start_quiz(&study);
+        assert_eq!(quiz.cards.len(), 2);
+    }
+}
@@ -0,0 +1,25 @@
+//! The flashcard model shared by the iced app and the command line front end.
+//!
+//! It owns the [`Study`](quiz::Study) session with its cards and topics, the quiz engine, saving
+//! and loading decks, and importing and exporting them, so a deck made in one front end can be
+//! opened in the other.
+pub mod auto;
+pub mod bundle;
+pub mod deck;
+pub mod exam;
+pub mod forecast;
+pub mod history;
+pub mod html;
+pub mod import;
+pub mod integrity;
+pub mod leech;
+pub mod markup;
+pub mod order;
+pub mod pdf;
+pub mod query;
+pub mod quiz;
+pub mod random;
+pub mod review;
+pub mod search;
+pub mod state;
+pub mod stats;
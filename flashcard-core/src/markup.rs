@@ -0,0 +1,249 @@
+//! The light Markdown used in card text.
+//!
+//! Questions and answers may use `**bold**`, `*italic*` and `` `code` `` inline, and lines
+//! starting with `- ` or `* ` become list items. Everything else is plain text with its line
+//! breaks kept. The app draws the parsed text with rich text spans and the HTML export writes
+//! the same structure as tags, so a card reads the same everywhere.
+
+/// How a run of text is styled.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+}
+
+/// A run of text sharing one style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment {
+    pub text: String,
+    pub style: Style,
+}
+
+/// A line of card text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Line {
+    Text(Vec<Fragment>),
+    Item(Vec<Fragment>),
+}
+
+/// Parses card text into lines of styled fragments.
+pub fn parse(text: &str) -> Vec<Line> {
+    text.lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            match trimmed
+                .strip_prefix("- ")
+                .or_else(|| trimmed.strip_prefix("* "))
+            {
+                Some(item) => Line::Item(parse_inline(item)),
+                None => Line::Text(parse_inline(line)),
+            }
+        })
+        .collect()
+}
+
+// A marker only opens a style when it is closed again later on the same line, so stray
+// asterisks such as in `2*3` stay as they are. A backslash escapes the next character.
+fn parse_inline(line: &str) -> Vec<Fragment> {
+    let mut fragments = vec![];
+    let mut style = Style::default();
+    let mut current = String::new();
+    let mut rest = line;
+
+    while let Some(character) = rest.chars().next() {
+        // The marker at this position, the style it switches to and whether it ends a style.
+        let (marker, toggled, closes) = if style.code {
+            (
+                "`",
+                Style {
+                    code: false,
+                    ..style
+                },
+                true,
+            )
+        } else if rest.starts_with("**") {
+            (
+                "**",
+                Style {
+                    bold: !style.bold,
+                    ..style
+                },
+                style.bold,
+            )
+        } else if character == '*' {
+            (
+                "*",
+                Style {
+                    italic: !style.italic,
+                    ..style
+                },
+                style.italic,
+            )
+        } else if character == '`' {
+            (
+                "`",
+                Style {
+                    code: true,
+                    ..style
+                },
+                false,
+            )
+        } else {
+            ("", style, false)
+        };
+
+        if character == '\\' && !style.code && rest.len() > 1 {
+            let escaped = rest[1..].chars().next().unwrap();
+            current.push(escaped);
+            rest = &rest[1 + escaped.len_utf8()..];
+            continue;
+        }
+
+        let after = &rest[marker.len()..];
+        if rest.starts_with(marker) && !marker.is_empty() && (closes || after.contains(marker)) {
+            if !current.is_empty() {
+                fragments.push(Fragment {
+                    text: std::mem::take(&mut current),
+                    style,
+                });
+            }
+            style = toggled;
+            rest = after;
+        } else {
+            current.push(character);
+            rest = &rest[character.len_utf8()..];
+        }
+    }
+
+    if !current.is_empty() {
+        fragments.push(Fragment {
+            text: current,
+            style,
+        });
+    }
+    fragments
+}
+
+/// Renders card text as HTML.
+pub fn to_html(text: &str) -> String {
+    let mut html = String::new();
+    let mut in_list = false;
+    for line in parse(text) {
+        let (is_item, fragments) = match line {
+            Line::Text(fragments) => (false, fragments),
+            Line::Item(fragments) => (true, fragments),
+        };
+        match (in_list, is_item) {
+            (false, true) => html.push_str("<ul>"),
+            (true, false) => html.push_str("</ul>"),
+            _ => {}
+        }
+        in_list = is_item;
+
+        html.push_str(if is_item { "<li>" } else { "<p>" });
+        for fragment in fragments {
+            let tags: Vec<&str> = [
+                (fragment.style.bold, "strong"),
+                (fragment.style.italic, "em"),
+                (fragment.style.code, "code"),
+            ]
+            .into_iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, tag)| tag)
+            .collect();
+            for tag in &tags {
+                html.push_str(&format!("<{tag}>"));
+            }
+            html.push_str(&escape_html(&fragment.text));
+            for tag in tags.iter().rev() {
+                html.push_str(&format!("</{tag}>"));
+            }
+        }
+        html.push_str(if is_item { "</li>" } else { "</p>" });
+    }
+    if in_list {
+        html.push_str("</ul>");
+    }
+    html
+}
+
+/// Escapes the characters that have a meaning in HTML.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &str) -> Fragment {
+        Fragment {
+            text: text.to_string(),
+            style: Style::default(),
+        }
+    }
+
+    #[test]
+    fn parses_inline_styles() {
+        let fragments = parse_inline("a **bold** and *italic* `co*de`");
+        let styles: Vec<_> = fragments
+            .iter()
+            .map(|fragment| (fragment.text.as_str(), fragment.style))
+            .collect();
+        assert_eq!(
+            styles,
+            [
+                ("a ", Style::default()),
+                (
+                    "bold",
+                    Style {
+                        bold: true,
+                        ..Default::default()
+                    }
+                ),
+                (" and ", Style::default()),
+                (
+                    "italic",
+                    Style {
+                        italic: true,
+                        ..Default::default()
+                    }
+                ),
+                (" ", Style::default()),
+                (
+                    "co*de",
+                    Style {
+                        code: true,
+                        ..Default::default()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_unmatched_markers_alone() {
+        assert_eq!(parse_inline("2*3 = 6"), [plain("2*3 = 6")]);
+        assert_eq!(parse_inline(r"\*not italic\*"), [plain("*not italic*")]);
+    }
+
+    #[test]
+    fn renders_html() {
+        assert_eq!(
+            to_html("Parts:\n- **nucleus**\n- <membrane>\nDone"),
+            "<p>Parts:</p><ul><li><strong>nucleus</strong></li><li>&lt;membrane&gt;</li></ul><p>Done</p>"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! The order questions are asked in.
+//!
+//! Asking the cards in the order they were made lets the order give the answers away, and keeps
+//! the cards of a topic together. The other orders shuffle the cards, with a seed so the same
+//! shuffle can be asked again; take turns between the topics; or put the cards that need the most
+//! work first.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::quiz::{FlashcardKey, Study, TopicKey};
+use crate::random::Rng;
+
+/// An order to ask cards in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Order {
+    /// The order the cards were made in.
+    #[default]
+    Created,
+    /// A random order, picked with the seed of the quiz.
+    Shuffled,
+    /// One card of each topic in turn, by the first topic of each card.
+    Interleaved,
+    /// The cards with the lowest ease first, then the most lapses.
+    Hardest,
+    /// The cards reviewed longest ago first, after the cards that were never reviewed.
+    Oldest,
+}
+
+impl Order {
+    pub const ALL: [Order; 5] = [
+        Order::Created,
+        Order::Shuffled,
+        Order::Interleaved,
+        Order::Hardest,
+        Order::Oldest,
+    ];
+
+    /// Returns a longer name for the order, for menus.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Order::Created => "Order made",
+            Order::Shuffled => "Shuffled",
+            Order::Interleaved => "Topics interleaved",
+            Order::Hardest => "Hardest first",
+            Order::Oldest => "Oldest review first",
+        }
+    }
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Order::Created => "created",
+            Order::Shuffled => "shuffled",
+            Order::Interleaved => "interleaved",
+            Order::Hardest => "hardest",
+            Order::Oldest => "oldest",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Order {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Order::ALL
+            .into_iter()
+            .find(|order| order.to_string().eq_ignore_ascii_case(text.trim()))
+            .ok_or_else(|| {
+                format!(
+                    "unknown order {text}, expected created, shuffled, interleaved, hardest or \
+                     oldest"
+                )
+            })
+    }
+}
+
+impl Study {
+    /// Returns the cards in `order`. The seed is only used to shuffle.
+    pub fn order_cards(
+        &self,
+        mut cards: Vec<FlashcardKey>,
+        order: Order,
+        seed: u64,
+    ) -> Vec<FlashcardKey> {
+        match order {
+            Order::Created => {}
+            Order::Shuffled => Rng::new(seed).shuffle(&mut cards),
+            Order::Interleaved => cards = self.interleave(cards),
+            Order::Hardest => cards.sort_by(|&a, &b| {
+                let (a, b) = (&self.cards[a].schedule, &self.cards[b].schedule);
+                a.easiness
+                    .total_cmp(&b.easiness)
+                    .then(b.lapses.cmp(&a.lapses))
+            }),
+            Order::Oldest => cards.sort_by_key(|&key| self.cards[key].schedule.last_review),
+        }
+        cards
+    }
+
+    // Takes one card from each topic in turn, keeping the order of the cards within a topic.
+    fn interleave(&self, cards: Vec<FlashcardKey>) -> Vec<FlashcardKey> {
+        let mut groups: Vec<(Option<TopicKey>, Vec<FlashcardKey>)> = Vec::new();
+        for key in cards {
+            let topic = self.cards[key].topics.first().copied();
+            match groups.iter_mut().find(|(group, _)| *group == topic) {
+                Some((_, group)) => group.push(key),
+                None => groups.push((topic, vec![key])),
+            }
+        }
+        let longest = groups.iter().map(|(_, group)| group.len()).max();
+        (0..longest.unwrap_or(0))
+            .flat_map(|index| {
+                groups
+                    .iter()
+                    .filter_map(move |(_, group)| group.get(index).copied())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::{Flashcard, Quiz, Topic};
+    use crate::review::Grade;
+
+    #[test]
+    fn orders_quizzes() {
+        let mut study = Study::default();
+        let [math, words] = ["Math", "Words"].map(|name| {
+            study.insert_topic(Topic {
+                content: name.to_string(),
+                enabled: true,
+                ..Default::default()
+            })
+        });
+        let [sum, product, cat, dog] = [
+            ("1+1?", math),
+            ("2*2?", math),
+            ("Cat?", words),
+            ("Dog?", words),
+        ]
+        .map(|(question, topic)| {
+            study.insert_card(Flashcard {
+                question: question.to_string(),
+                topics: vec![topic],
+                ..Default::default()
+            })
+        });
+        let cards = vec![sum, product, cat, dog];
+        assert_eq!(
+            study.order_cards(cards.clone(), Order::Interleaved, 0),
+            [sum, cat, product, dog]
+        );
+
+        study.record_review(product, String::new(), Grade::Again, 0);
+        study.record_review(sum, String::new(), Grade::Good, 0);
+        study.cards[sum].schedule.last_review = Some(200);
+        study.cards[product].schedule.last_review = Some(100);
+        assert_eq!(
+            study.order_cards(cards.clone(), Order::Hardest, 0)[0],
+            product
+        );
+        assert_eq!(
+            study.order_cards(cards.clone(), Order::Oldest, 0),
+            [cat, dog, product, sum]
+        );
+
+        // The same seed asks the cards in the same order.
+        let mut quiz = Quiz {
+            order: Order::Shuffled,
+            seed: 42,
+            ..Default::default()
+        };
+        quiz.start_quiz(&study);
+        let first = quiz.cards.clone();
+        quiz.start_quiz(&study);
+        assert_eq!(quiz.cards, first);
+        assert_eq!(first, study.order_cards(cards, Order::Shuffled, 42));
+        assert_eq!(quiz.qna_queue[0].id, study.cards[first[0]].id);
+
+        // Quizzes on chosen topics are ordered too.
+        quiz.order = Order::Interleaved;
+        quiz.start_quiz_on(&study, &[math, words]);
+        assert_eq!(quiz.cards, [sum, cat, product, dog]);
+        assert_eq!(quiz.qna_queue[1].id, study.cards[cat].id);
+        assert_eq!("Hardest".parse(), Ok(Order::Hardest));
+    }
+}
@@ -0,0 +1,310 @@
+//! Printable PDF export of flashcards.
+//!
+//! Cards are laid out in a grid on A4 pages meant for double-sided printing. Every page of
+//! questions is followed by a page with the matching answers, whose columns are mirrored so that
+//! each answer ends up behind its question once the sheet is flipped along its long edge and cut.
+//! The document only uses the standard Helvetica font, so no font files are needed.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use pdf_writer::{Content, Name, Pdf, Rect, Ref, Str};
+
+use crate::quiz::{ink_color, Color, Flashcard, Study, TopicKey};
+
+// A4 in points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 36.0;
+const COLUMNS: usize = 2;
+const ROWS: usize = 4;
+const CARDS_PER_PAGE: usize = COLUMNS * ROWS;
+const CARD_WIDTH: f32 = (PAGE_WIDTH - 2.0 * MARGIN) / COLUMNS as f32;
+const CARD_HEIGHT: f32 = (PAGE_HEIGHT - 2.0 * MARGIN) / ROWS as f32;
+const PADDING: f32 = 14.0;
+
+const MAX_FONT_SIZE: f32 = 16.0;
+const MIN_FONT_SIZE: f32 = 7.0;
+const LABEL_FONT_SIZE: f32 = 7.0;
+const LINE_HEIGHT: f32 = 1.25;
+
+const FONT: Name = Name(b"F1");
+
+/// Which side of the cards a page shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Side {
+    Front,
+    Back,
+}
+
+/// Renders the cards of `topics` into a PDF document.
+pub fn export_pdf(study: &Study, topics: &[TopicKey]) -> Vec<u8> {
+    let cards: Vec<&Flashcard> = study
+        .cards_in_topics(topics)
+        .into_iter()
+        .filter_map(|key| study.cards.get(key))
+        .collect();
+
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let mut next_id = 4;
+
+    let mut pdf = Pdf::new();
+    let mut page_ids = vec![];
+    for (sheet, chunk) in cards.chunks(CARDS_PER_PAGE).enumerate() {
+        for side in [Side::Front, Side::Back] {
+            let page_id = Ref::new(next_id);
+            let content_id = Ref::new(next_id + 1);
+            next_id += 2;
+
+            let mut content = Content::new();
+            for (slot, card) in chunk.iter().enumerate() {
+                draw_card(
+                    &mut content,
+                    card,
+                    sheet * CARDS_PER_PAGE + slot + 1,
+                    slot,
+                    side,
+                );
+            }
+            pdf.stream(content_id, &content.finish());
+
+            let mut page = pdf.page(page_id);
+            page.parent(page_tree_id)
+                .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+                .contents(content_id);
+            page.resources().fonts().pair(FONT, font_id);
+            page_ids.push(page_id);
+        }
+    }
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .count(page_ids.len() as i32)
+        .kids(page_ids);
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.finish()
+}
+
+/// Renders the cards of `topics` and writes the PDF to `path`.
+pub fn write_pdf(study: &Study, topics: &[TopicKey], path: &Path) -> io::Result<()> {
+    fs::write(path, export_pdf(study, topics))
+}
+
+// Returns the bottom left corner of a slot. On the back the columns are mirrored.
+fn slot_origin(slot: usize, side: Side) -> (f32, f32) {
+    let row = slot / COLUMNS;
+    let column = match side {
+        Side::Front => slot % COLUMNS,
+        Side::Back => COLUMNS - 1 - slot % COLUMNS,
+    };
+    (
+        MARGIN + column as f32 * CARD_WIDTH,
+        PAGE_HEIGHT - MARGIN - (row + 1) as f32 * CARD_HEIGHT,
+    )
+}
+
+fn draw_card(content: &mut Content, card: &Flashcard, number: usize, slot: usize, side: Side) {
+    let (x, y) = slot_origin(slot, side);
+    let background = card.bg_color.unwrap_or(Color::WHITE);
+    let ink = ink_color(background);
+
+    // Card background and a light cut guide around it.
+    content.save_state();
+    content.set_fill_rgb(background.r, background.g, background.b);
+    content.rect(x, y, CARD_WIDTH, CARD_HEIGHT).fill_nonzero();
+    content.set_stroke_rgb(0.75, 0.75, 0.75).set_line_width(0.5);
+    content.rect(x, y, CARD_WIDTH, CARD_HEIGHT).stroke();
+    content.restore_state();
+
+    let text = match side {
+        Side::Front => &card.question,
+        Side::Back => &card.answer,
+    };
+    let (size, lines) = fit_text(
+        text,
+        CARD_WIDTH - 2.0 * PADDING,
+        CARD_HEIGHT - 3.0 * PADDING,
+    );
+    let leading = size * LINE_HEIGHT;
+    let block_height = lines.len() as f32 * leading;
+
+    content.begin_text();
+    content.set_fill_rgb(ink.r, ink.g, ink.b);
+    content.set_font(FONT, size);
+    // Text positions are relative to the start of the previous line.
+    let mut previous = (0.0, 0.0);
+    let mut baseline = y + (CARD_HEIGHT + block_height) / 2.0 - size;
+    for line in &lines {
+        let line_x = x + (CARD_WIDTH - text_width(line, size)) / 2.0;
+        content.next_line(line_x - previous.0, baseline - previous.1);
+        content.show(Str(&encode(line)));
+        previous = (line_x, baseline);
+        baseline -= leading;
+    }
+
+    let label = match side {
+        Side::Front => format!("{number} Q"),
+        Side::Back => format!("{number} A"),
+    };
+    content.set_font(FONT, LABEL_FONT_SIZE);
+    content.next_line(
+        x + PADDING / 2.0 - previous.0,
+        y + PADDING / 2.0 - previous.1,
+    );
+    content.show(Str(&encode(&label)));
+    content.end_text();
+}
+
+// Picks the largest font size at which the text fits the box. Text that does not even fit at
+// the smallest size is cut off with an ellipsis.
+fn fit_text(text: &str, width: f32, height: f32) -> (f32, Vec<String>) {
+    let mut size = MAX_FONT_SIZE;
+    loop {
+        let lines = wrap(text, size, width);
+        let max_lines = (height / (size * LINE_HEIGHT)).floor().max(1.0) as usize;
+        if lines.len() <= max_lines {
+            return (size, lines);
+        }
+        if size <= MIN_FONT_SIZE {
+            let mut lines = lines;
+            lines.truncate(max_lines);
+            if let Some(last) = lines.last_mut() {
+                while !last.is_empty() && text_width(&format!("{last}..."), size) > width {
+                    last.pop();
+                }
+                last.push_str("...");
+            }
+            return (size, lines);
+        }
+        size -= 1.0;
+    }
+}
+
+// Greedy word wrapping that keeps the line breaks of the original text. Words wider than a line
+// are broken between characters.
+fn wrap(text: &str, size: f32, width: f32) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{line} {word}")
+            };
+            if text_width(&candidate, size) <= width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for character in word.chars() {
+                if text_width(&line, size) + char_width(character, size) > width && !line.is_empty()
+                {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push(character);
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars()
+        .map(|character| char_width(character, size))
+        .sum()
+}
+
+// Advance widths of the printable ASCII characters in Helvetica, in thousandths of an em.
+// Anything else is measured as a digit.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+fn char_width(character: char, size: f32) -> f32 {
+    let width = match character {
+        ' '..='~' => HELVETICA_WIDTHS[character as usize - ' ' as usize],
+        _ => 556,
+    };
+    f32::from(width) * size / 1000.0
+}
+
+// Encodes text in WinAnsiEncoding, replacing characters it cannot represent with '?'.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|character| match character {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => character as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_pages_mirror_columns() {
+        assert_eq!(slot_origin(0, Side::Front), slot_origin(1, Side::Back));
+        assert_eq!(slot_origin(7, Side::Front), slot_origin(6, Side::Back));
+        assert_eq!(slot_origin(2, Side::Front).1, slot_origin(2, Side::Back).1);
+    }
+
+    #[test]
+    fn exports_a_front_and_back_page_per_sheet() {
+        let mut study = Study::default();
+        let topic = study.topic_by_name_or_insert("Math");
+        for number in 0..9 {
+            study.insert_card(Flashcard {
+                question: format!("{number} + {number}?"),
+                answer: (number * 2).to_string(),
+                topics: vec![topic],
+                ..Default::default()
+            });
+        }
+        study.insert_card(Flashcard {
+            question: "Not exported".to_string(),
+            ..Default::default()
+        });
+
+        let pdf = export_pdf(&study, &[topic]);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF"));
+        assert!(text.contains("/Count 4"));
+        assert!(text.contains("(8 + 8?)"));
+        assert!(!text.contains("Not exported"));
+    }
+
+    #[test]
+    fn long_text_is_wrapped_and_cut() {
+        let (_, lines) = fit_text("word ".repeat(20).trim(), 100.0, 200.0);
+        assert!(lines.len() > 1);
+        assert!(lines
+            .iter()
+            .all(|line| text_width(line, MAX_FONT_SIZE) <= 100.0));
+
+        let (size, lines) = fit_text(&"word ".repeat(1000), 100.0, 40.0);
+        assert_eq!(size, MIN_FONT_SIZE);
+        assert!(lines.last().unwrap().ends_with("..."));
+    }
+}
@@ -0,0 +1,514 @@
+//! Queries that pick cards by their topics, text and review history.
+//!
+//! Smart topics hold a query instead of a list of cards, e.g.
+//! `topic:Biology AND NOT topic:Done AND lapses>3 AND created:<30d`. Conditions are combined with
+//! `AND`, `OR`, `NOT` and parentheses; conditions next to each other are combined with `AND`.
+//!
+//! | Condition | Matches cards |
+//! |---|---|
+//! | `word` | with the word in the question or answer |
+//! | `question:word`, `answer:word` | with the word in the question or the answer |
+//! | `topic:Name`, `namespace:Name` | in the topic or a sub-topic, or in a topic of the namespace |
+//! | `lapses>3`, `reviews<=2`, `interval>=7`, `ease<2.5` | with a review count or setting |
+//! | `created:<30d`, `reviewed:>1w`, `due:<=2d` | made, last reviewed or due within a time |
+//!
+//! Names with spaces go in double quotes: `topic:"Cell biology"`. Times are in hours (`h`), days
+//! (`d`, the default) or weeks (`w`); a time without a comparison means at most that long.
+use std::fmt;
+
+use crate::quiz::{unix_now, FlashcardKey, Study, SECONDS_PER_DAY};
+
+/// A parsed query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Condition(Condition),
+}
+
+/// A single condition on a card.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// The text contains the word, ignoring case.
+    Text(TextField, String),
+    /// The card is in the topic called this, or in one of its sub-topics.
+    Topic(String),
+    /// The card is in a topic of the namespace called this.
+    Namespace(String),
+    /// A number from the schedule of the card compares to the value.
+    Number(NumberField, Comparison, f64),
+    /// The time since a card was made or reviewed, or until it is due, in seconds compares to the
+    /// value. Cards without that time never match.
+    Time(TimeField, Comparison, i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextField {
+    Any,
+    Question,
+    Answer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumberField {
+    Lapses,
+    Reviews,
+    Interval,
+    Ease,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeField {
+    Created,
+    Reviewed,
+    Due,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+/// Why a query could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError(String);
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(String),
+}
+
+impl Query {
+    /// Reads a query as typed by the user.
+    pub fn parse(text: &str) -> Result<Query, QueryError> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let query = parser.or()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(query),
+            Some(Token::Close) => Err(QueryError("there is a ) too many".to_string())),
+            Some(token) => Err(QueryError(format!("unexpected {token:?}"))),
+        }
+    }
+
+    /// Returns whether the card matches the query at the unix time `now`.
+    pub fn matches(&self, study: &Study, card: FlashcardKey, now: u64) -> bool {
+        match self {
+            Query::And(left, right) => {
+                left.matches(study, card, now) && right.matches(study, card, now)
+            }
+            Query::Or(left, right) => {
+                left.matches(study, card, now) || right.matches(study, card, now)
+            }
+            Query::Not(query) => !query.matches(study, card, now),
+            Query::Condition(condition) => condition.matches(study, card, now),
+        }
+    }
+
+    /// Returns the cards of `study` that match the query now, in the order they were made.
+    pub fn cards(&self, study: &Study) -> Vec<FlashcardKey> {
+        let now = unix_now();
+        study
+            .cards
+            .keys()
+            .filter(|&key| self.matches(study, key, now))
+            .collect()
+    }
+}
+
+impl Condition {
+    fn matches(&self, study: &Study, card_key: FlashcardKey, now: u64) -> bool {
+        let Some(card) = study.cards.get(card_key) else {
+            return false;
+        };
+        match self {
+            Condition::Text(field, word) => {
+                let word = word.to_lowercase();
+                let contains = |text: &str| text.to_lowercase().contains(&word);
+                match field {
+                    TextField::Any => contains(&card.question) || contains(&card.answer),
+                    TextField::Question => contains(&card.question),
+                    TextField::Answer => contains(&card.answer),
+                }
+            }
+            // Only the cards linked to the topic count, so smart topics cannot refer to each
+            // other in a loop.
+            Condition::Topic(name) => study.topic_by_name(name).is_some_and(|topic| {
+                let topics = study.with_descendants(&[topic]);
+                card.topics.iter().any(|key| topics.contains(key))
+            }),
+            Condition::Namespace(name) => study.namespace_by_name(name).is_some_and(|namespace| {
+                card.topics.iter().any(|&topic| {
+                    study
+                        .topics
+                        .get(topic)
+                        .is_some_and(|topic| topic.namespaces.contains(&namespace))
+                })
+            }),
+            Condition::Number(field, comparison, value) => {
+                let schedule = &card.schedule;
+                let number = match field {
+                    NumberField::Lapses => f64::from(schedule.lapses),
+                    NumberField::Reviews => f64::from(schedule.repetitions),
+                    NumberField::Interval => f64::from(schedule.interval),
+                    NumberField::Ease => f64::from(schedule.easiness),
+                };
+                comparison.holds(number, *value)
+            }
+            Condition::Time(field, comparison, seconds) => {
+                // Times too large to compare come from broken decks, and never match.
+                let signed = |time: Option<u64>| time.and_then(|time| i64::try_from(time).ok());
+                let now = signed(Some(now));
+                let between = |from: Option<i64>, to: Option<i64>| to?.checked_sub(from?);
+                let span = match field {
+                    TimeField::Created => between(signed(card.created), now),
+                    TimeField::Reviewed => between(signed(card.schedule.last_review), now),
+                    TimeField::Due => between(now, signed(card.schedule.next_review)),
+                };
+                span.is_some_and(|span| comparison.holds(span, *seconds))
+            }
+        }
+    }
+}
+
+// Splits a query into parentheses, keywords and terms. Double quotes keep spaces and keywords
+// inside a term.
+fn tokenize(text: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = vec![];
+    let mut characters = text.chars().peekable();
+    while let Some(&character) = characters.peek() {
+        match character {
+            '(' => {
+                characters.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                characters.next();
+                tokens.push(Token::Close);
+            }
+            _ if character.is_whitespace() => {
+                characters.next();
+            }
+            _ => {
+                let mut term = String::new();
+                let mut quoted = false;
+                let mut in_quotes = false;
+                while let Some(&character) = characters.peek() {
+                    if !in_quotes && (character.is_whitespace() || "()".contains(character)) {
+                        break;
+                    }
+                    characters.next();
+                    if character == '"' {
+                        quoted = true;
+                        in_quotes = !in_quotes;
+                    } else {
+                        term.push(character);
+                    }
+                }
+                if in_quotes {
+                    return Err(QueryError("a quote is not closed".to_string()));
+                }
+                tokens.push(match term.to_uppercase().as_str() {
+                    "AND" if !quoted => Token::And,
+                    "OR" if !quoted => Token::Or,
+                    "NOT" if !quoted => Token::Not,
+                    _ => Token::Term(term),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.and()?;
+        while self.tokens.get(self.position) == Some(&Token::Or) {
+            self.position += 1;
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.not()?;
+        loop {
+            match self.tokens.get(self.position) {
+                Some(Token::And) => self.position += 1,
+                Some(Token::Not | Token::Open | Token::Term(_)) => {}
+                _ => return Ok(query),
+            }
+            query = Query::And(Box::new(query), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Query, QueryError> {
+        if self.tokens.get(self.position) == Some(&Token::Not) {
+            self.position += 1;
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+        match self.next() {
+            Some(Token::Open) => {
+                let query = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(query),
+                    _ => Err(QueryError("a ( is not closed".to_string())),
+                }
+            }
+            Some(Token::Term(term)) => Ok(Query::Condition(condition(&term)?)),
+            Some(token) => Err(QueryError(format!("expected a condition before {token:?}"))),
+            None => Err(QueryError("expected a condition at the end".to_string())),
+        }
+    }
+}
+
+// Reads a condition such as `topic:Biology`, `lapses>3` or `created:<30d`.
+fn condition(term: &str) -> Result<Condition, QueryError> {
+    let Some(split) = term.find([':', '<', '>', '=']) else {
+        return Ok(Condition::Text(TextField::Any, term.to_string()));
+    };
+    let field = term[..split].to_lowercase();
+    // Text such as `2+2=4` is a word, not a field.
+    if field.is_empty() || !field.chars().all(char::is_alphabetic) {
+        return Ok(Condition::Text(TextField::Any, term.to_string()));
+    }
+    let rest = term[split..].strip_prefix(':').unwrap_or(&term[split..]);
+    let name = || {
+        if rest.trim().is_empty() {
+            Err(QueryError(format!("{field}: needs a name or word")))
+        } else {
+            Ok(rest.to_string())
+        }
+    };
+    let (comparison, value) = comparison(rest);
+    let number = || {
+        value
+            .parse::<f64>()
+            .map_err(|_| QueryError(format!("{value} is not a number in {term}")))
+    };
+    let time_field = match field.as_str() {
+        "question" => return Ok(Condition::Text(TextField::Question, name()?)),
+        "answer" => return Ok(Condition::Text(TextField::Answer, name()?)),
+        "topic" => return Ok(Condition::Topic(name()?)),
+        "namespace" => return Ok(Condition::Namespace(name()?)),
+        "lapses" => {
+            return Ok(Condition::Number(
+                NumberField::Lapses,
+                comparison,
+                number()?,
+            ))
+        }
+        "reviews" => {
+            return Ok(Condition::Number(
+                NumberField::Reviews,
+                comparison,
+                number()?,
+            ))
+        }
+        "interval" => {
+            return Ok(Condition::Number(
+                NumberField::Interval,
+                comparison,
+                number()?,
+            ))
+        }
+        "ease" => return Ok(Condition::Number(NumberField::Ease, comparison, number()?)),
+        "created" => TimeField::Created,
+        "reviewed" => TimeField::Reviewed,
+        "due" => TimeField::Due,
+        _ => return Err(QueryError(format!("there is no field called {field}"))),
+    };
+    let (amount, unit) = match value.char_indices().last() {
+        Some((index, unit)) if unit.is_ascii_alphabetic() => (&value[..index], unit),
+        _ => (value, 'd'),
+    };
+    let seconds = match unit.to_ascii_lowercase() {
+        'h' => 3_600,
+        'd' => SECONDS_PER_DAY as i64,
+        'w' => 7 * SECONDS_PER_DAY as i64,
+        _ => return Err(QueryError(format!("{unit} is not h, d or w in {term}"))),
+    };
+    let amount: f64 = amount
+        .parse()
+        .map_err(|_| QueryError(format!("{amount} is not a number in {term}")))?;
+    // A time on its own means "at most".
+    let comparison = if comparison == Comparison::Equal && !rest.starts_with('=') {
+        Comparison::LessOrEqual
+    } else {
+        comparison
+    };
+    Ok(Condition::Time(
+        time_field,
+        comparison,
+        (amount * seconds as f64) as i64,
+    ))
+}
+
+// Splits the comparison off the front of a value, which is equality when there is none.
+fn comparison(text: &str) -> (Comparison, &str) {
+    for (prefix, comparison) in [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+    ] {
+        if let Some(value) = text.strip_prefix(prefix) {
+            return (comparison, value.trim());
+        }
+    }
+    (Comparison::Equal, text.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::{Flashcard, Quiz, Schedule, Topic};
+
+    #[test]
+    fn parses_queries() {
+        let query = Query::parse("topic:Biology AND NOT topic:\"Done list\" lapses>3").unwrap();
+        let topic = |name: &str| Box::new(Query::Condition(Condition::Topic(name.to_string())));
+        assert_eq!(
+            query,
+            Query::And(
+                Box::new(Query::And(
+                    topic("Biology"),
+                    Box::new(Query::Not(topic("Done list")))
+                )),
+                Box::new(Query::Condition(Condition::Number(
+                    NumberField::Lapses,
+                    Comparison::Greater,
+                    3.0
+                ))),
+            )
+        );
+        assert_eq!(
+            Query::parse("created:30d").unwrap(),
+            Query::Condition(Condition::Time(
+                TimeField::Created,
+                Comparison::LessOrEqual,
+                30 * SECONDS_PER_DAY as i64
+            ))
+        );
+        assert!(Query::parse("(cell OR").is_err());
+        assert!(Query::parse("cell)").is_err());
+        assert!(Query::parse("colour:red").is_err());
+        assert!(Query::parse("lapses>many").is_err());
+        assert!(Query::parse("due:<3y").is_err());
+    }
+
+    #[test]
+    fn matches_cards() {
+        let now = unix_now();
+        let mut study = Study::default();
+        let biology = study.topic_by_name_or_insert("Biology");
+        let cell = study.topic_by_name_or_insert("Cell");
+        study.set_parent(cell, Some(biology)).unwrap();
+        let done = study.topic_by_name_or_insert("Done");
+        let hard = study.insert_card(Flashcard {
+            question: "Powerhouse of the cell?".to_string(),
+            topics: vec![cell],
+            created: Some(now - 10 * SECONDS_PER_DAY),
+            schedule: Schedule {
+                lapses: 5,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let old = study.insert_card(Flashcard {
+            question: "What is DNA?".to_string(),
+            topics: vec![biology],
+            created: Some(now - 60 * SECONDS_PER_DAY),
+            ..Default::default()
+        });
+        let finished = study.insert_card(Flashcard {
+            topics: vec![biology, done],
+            ..Default::default()
+        });
+
+        let matching = |text: &str| {
+            let query = Query::parse(text).unwrap();
+            let cards: Vec<FlashcardKey> = study
+                .cards
+                .keys()
+                .filter(|&key| query.matches(&study, key, now))
+                .collect();
+            cards
+        };
+        assert_eq!(matching("topic:biology AND NOT topic:Done"), [hard, old]);
+        assert_eq!(
+            matching("topic:Biology AND NOT topic:Done AND lapses>3 AND created:<30d"),
+            [hard]
+        );
+        assert_eq!(matching("created:>30d OR topic:done"), [old, finished]);
+        assert_eq!(matching("NOT (cell OR dna)"), [finished]);
+
+        // Enabled smart topics are quizzed like any other topic.
+        let smart = study.insert_topic(Topic {
+            content: "Old".to_string(),
+            query: Some("created:>30d".to_string()),
+            enabled: true,
+            ..Default::default()
+        });
+        let mut quiz = Quiz::default();
+        quiz.start_quiz(&study);
+        assert_eq!(quiz.cards, [old]);
+        assert_eq!(study.cards_in_topics(&[smart, done]), [old, finished]);
+
+        let broken = study.insert_card(Flashcard {
+            created: Some(u64::MAX),
+            schedule: Schedule {
+                next_review: Some(u64::MAX),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let query = Query::parse("created:<30d OR created:>30d OR due:<1d OR due:>1d").unwrap();
+        assert!(!query.matches(&study, broken, now));
+    }
+}
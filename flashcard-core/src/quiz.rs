@@ -0,0 +1,1389 @@
+use serde::{Deserialize, Serialize};
+use slotmap::{new_key_type, SlotMap};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::auto::Rule;
+use crate::exam::Exam;
+use crate::leech::LeechSettings;
+use crate::order::Order;
+use crate::query::Query;
+use crate::review::{Grade, Review};
+use crate::state::Flag;
+
+// Create key types for topics, flashcards and namespaces.
+new_key_type! {
+    pub struct TopicKey;
+    pub struct FlashcardKey;
+    pub struct NamespaceKey;
+}
+
+// A namespace is a user-made group of topics, such as "Semester 1" or "Exam prep". It has a name,
+// a colour to tell it apart and a position in the list of namespaces. Topics can be in several
+// namespaces at once.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Namespace {
+    pub id: Uuid,
+    pub name: String,
+    pub color: Color,
+    pub position: usize,
+}
+
+impl Default for Namespace {
+    fn default() -> Self {
+        Self {
+            id: Uuid::nil(),
+            name: String::new(),
+            color: Color::WHITE,
+            position: 0,
+        }
+    }
+}
+
+// A topic contains some text, the namespaces it belongs to, an optional parent topic, an enabled
+// flag (which might be used to indicate selection) and a list of flashcards (by key) that are
+// associated with this topic. Through their parents topics form a tree, e.g.
+// "Biology > Cell > Organelles". Unlike its key, the id of a topic stays the same across saves,
+// exports and imports. The rules decide which cards are suggested for the topic by the Auto
+// operation. A smart topic has a query instead, and its cards are the ones the query matches.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Topic {
+    pub id: Uuid,
+    pub content: String,
+    pub namespaces: Vec<NamespaceKey>,
+    pub parent: Option<TopicKey>,
+    pub enabled: bool,
+    pub qna: Vec<FlashcardKey>,
+    pub rules: Vec<Rule>,
+    pub query: Option<String>,
+}
+
+// A flashcard has a background color, a question, an answer, an optional image, an id and a list
+// of topics (by key) to which it belongs. The id is assigned when the card is inserted into a
+// study session and, unlike its key, stays the same across saves, exports and imports. So is the
+// creation time, a unix timestamp, which cards made by older versions do not have. A card that
+// keeps being forgotten is flagged as a leech. A suspended card is left out of quizzes, and so is
+// a buried card until the unix time it is buried until; a flag only marks the card.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Flashcard {
+    pub bg_color: Option<Color>,
+    pub question: String,
+    pub answer: String,
+    pub image: Option<PathBuf>,
+    pub id: Uuid,
+    pub topics: Vec<TopicKey>,
+    pub schedule: Schedule,
+    pub created: Option<u64>,
+    pub leech: bool,
+    pub suspended: bool,
+    pub buried_until: Option<u64>,
+    pub flag: Option<Flag>,
+}
+
+// An RGBA color with components between 0 and 1. Colors are saved as `[r, g, b, a]`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(from = "[f32; 4]", into = "[f32; 4]")]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::from_rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::from_rgb(1.0, 1.0, 1.0);
+
+    /// Creates an opaque color.
+    pub const fn from_rgb(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b, a: 1.0 }
+    }
+
+    /// Returns the components as bytes.
+    pub fn into_rgba8(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+            .map(|component| (component.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    /// Reads an opaque color written as `#rrggbb`; the `#` is optional.
+    pub fn from_hex(text: &str) -> Option<Self> {
+        let hex = text.trim().trim_start_matches('#');
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let component = |index: usize| {
+            u8::from_str_radix(&hex[index..index + 2], 16)
+                .ok()
+                .map(|byte| f32::from(byte) / 255.0)
+        };
+        Some(Self::from_rgb(component(0)?, component(2)?, component(4)?))
+    }
+
+    /// Writes the color as `#rrggbb`, leaving out the alpha.
+    pub fn to_hex(self) -> String {
+        let [r, g, b, _] = self.into_rgba8();
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+/// Returns black or white, whichever reads better on top of `background`.
+pub fn ink_color(background: Color) -> Color {
+    if background.r * 0.299 + background.g * 0.587 + background.b * 0.114 < 0.5 {
+        Color::WHITE
+    } else {
+        Color::BLACK
+    }
+}
+
+impl From<[f32; 4]> for Color {
+    fn from([r, g, b, a]: [f32; 4]) -> Self {
+        Self { r, g, b, a }
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(color: Color) -> Self {
+        [color.r, color.g, color.b, color.a]
+    }
+}
+
+#[cfg(feature = "iced")]
+impl From<Color> for iced::Color {
+    fn from(color: Color) -> Self {
+        iced::Color::from_rgba(color.r, color.g, color.b, color.a)
+    }
+}
+
+#[cfg(feature = "iced")]
+impl From<iced::Color> for Color {
+    fn from(color: iced::Color) -> Self {
+        Self {
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        }
+    }
+}
+
+// Number of seconds in a day, used to turn review intervals into timestamps.
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Returns the current time as a unix timestamp in seconds.
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// The repetition state of a flashcard. Times are stored as unix timestamps in seconds and the
+// interval is in days. Cards imported from other programs carry their history over in here.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Schedule {
+    pub repetitions: u32,
+    pub lapses: u32,
+    pub easiness: f32,
+    pub interval: u32,
+    pub last_review: Option<u64>,
+    pub next_review: Option<u64>,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            repetitions: 0,
+            lapses: 0,
+            easiness: 2.5,
+            interval: 0,
+            last_review: None,
+            next_review: None,
+        }
+    }
+}
+
+// The study session holds all flashcards, topics and namespaces, and the log of every answer given
+// in a quiz. It also holds some state about the currently selected topics or flashcard as well as
+// a staging field for a new topic. Only the cards, topics, namespaces and reviews are saved; the
+// rest is state of the running app.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Study {
+    pub cards: SlotMap<FlashcardKey, Flashcard>,
+    pub topics: SlotMap<TopicKey, Topic>,
+    #[serde(default)]
+    pub namespaces: SlotMap<NamespaceKey, Namespace>,
+    #[serde(default)]
+    pub reviews: Vec<Review>,
+    #[serde(default)]
+    pub leech_settings: LeechSettings,
+    #[serde(skip)]
+    pub current_topics: Vec<TopicKey>,
+    #[serde(skip)]
+    pub current_topic: Option<TopicKey>,
+    #[serde(skip)]
+    pub current_card: Option<FlashcardKey>,
+    #[serde(skip)]
+    pub staging_topic: String,
+}
+
+impl Study {
+    /// Returns the key of the topic called `name`, creating it if no topic matches.
+    ///
+    /// Names are compared ignoring case and surrounding whitespace, the same way topics are
+    /// matched when typed in by the user.
+    pub fn topic_by_name_or_insert(&mut self, name: &str) -> TopicKey {
+        if let Some(key) = self.topic_by_name(name) {
+            return key;
+        }
+        self.insert_topic(Topic {
+            content: name.trim().to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// Returns the key of the topic called `name`, compared like in
+    /// [`topic_by_name_or_insert`](Self::topic_by_name_or_insert).
+    pub fn topic_by_name(&self, name: &str) -> Option<TopicKey> {
+        let name = name.trim();
+        self.topics
+            .iter()
+            .find(|(_, topic)| topic.content.trim().eq_ignore_ascii_case(name))
+            .map(|(key, _)| key)
+    }
+
+    /// Returns the keys of the topics in a namespace.
+    pub fn topics_in_namespace(&self, namespace: NamespaceKey) -> Vec<TopicKey> {
+        self.topics
+            .iter()
+            .filter(|(_, topic)| topic.namespaces.contains(&namespace))
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Inserts a namespace at the end of the list, giving it a fresh id unless it already has one
+    /// that is not in use.
+    pub fn insert_namespace(&mut self, mut namespace: Namespace) -> NamespaceKey {
+        if namespace.id.is_nil() || self.namespace_by_id(namespace.id).is_some() {
+            namespace.id = Uuid::new_v4();
+        }
+        namespace.position = self
+            .namespaces
+            .values()
+            .map(|namespace| namespace.position + 1)
+            .max()
+            .unwrap_or(0);
+        self.namespaces.insert(namespace)
+    }
+
+    /// Returns the key of the namespace called `name`, compared like topic names.
+    pub fn namespace_by_name(&self, name: &str) -> Option<NamespaceKey> {
+        let name = name.trim();
+        self.namespaces
+            .iter()
+            .find(|(_, namespace)| namespace.name.trim().eq_ignore_ascii_case(name))
+            .map(|(key, _)| key)
+    }
+
+    /// Returns the key of the namespace with the given id.
+    pub fn namespace_by_id(&self, id: Uuid) -> Option<NamespaceKey> {
+        self.namespaces
+            .iter()
+            .find(|(_, namespace)| namespace.id == id)
+            .map(|(key, _)| key)
+    }
+
+    /// Returns the keys of the namespaces in the order the user gave them.
+    pub fn ordered_namespaces(&self) -> Vec<NamespaceKey> {
+        let mut keys: Vec<NamespaceKey> = self.namespaces.keys().collect();
+        keys.sort_by_key(|&key| {
+            let namespace = &self.namespaces[key];
+            (namespace.position, namespace.name.to_lowercase())
+        });
+        keys
+    }
+
+    /// Moves a namespace to `index` in the ordered list, shifting the others along.
+    pub fn move_namespace(&mut self, key: NamespaceKey, index: usize) {
+        if !self.namespaces.contains_key(key) {
+            return;
+        }
+        let mut order = self.ordered_namespaces();
+        order.retain(|&other| other != key);
+        order.insert(index.min(order.len()), key);
+        for (position, key) in order.into_iter().enumerate() {
+            self.namespaces[key].position = position;
+        }
+    }
+
+    /// Removes a namespace. Its topics are kept and stay in their other namespaces.
+    pub fn remove_namespace(&mut self, key: NamespaceKey) -> Option<Namespace> {
+        let namespace = self.namespaces.remove(key)?;
+        for topic in self.topics.values_mut() {
+            topic.namespaces.retain(|&namespace| namespace != key);
+        }
+        Some(namespace)
+    }
+
+    /// Puts a topic in a namespace. Its other namespaces are kept.
+    pub fn add_to_namespace(&mut self, topic: TopicKey, namespace: NamespaceKey) {
+        if !self.namespaces.contains_key(namespace) {
+            return;
+        }
+        if let Some(topic) = self.topics.get_mut(topic) {
+            if !topic.namespaces.contains(&namespace) {
+                topic.namespaces.push(namespace);
+            }
+        }
+    }
+
+    /// Takes a topic out of a namespace.
+    pub fn remove_from_namespace(&mut self, topic: TopicKey, namespace: NamespaceKey) {
+        if let Some(topic) = self.topics.get_mut(topic) {
+            topic.namespaces.retain(|&other| other != namespace);
+        }
+    }
+
+    /// Puts a card in a topic, keeping the card's topics and the topic's Q&A list in sync.
+    ///
+    /// Links between cards and topics should only be changed through this, [`unlink`],
+    /// [`set_card_topics`], the insert and the remove methods, so both sides always agree.
+    ///
+    /// [`unlink`]: Self::unlink
+    /// [`set_card_topics`]: Self::set_card_topics
+    pub fn link(&mut self, card: FlashcardKey, topic: TopicKey) {
+        let (Some(card_entry), Some(topic_entry)) =
+            (self.cards.get_mut(card), self.topics.get_mut(topic))
+        else {
+            return;
+        };
+        if !card_entry.topics.contains(&topic) {
+            card_entry.topics.push(topic);
+        }
+        if !topic_entry.qna.contains(&card) {
+            topic_entry.qna.push(card);
+        }
+    }
+
+    /// Takes a card out of a topic on both sides of the link.
+    pub fn unlink(&mut self, card: FlashcardKey, topic: TopicKey) {
+        if let Some(card_entry) = self.cards.get_mut(card) {
+            card_entry.topics.retain(|&other| other != topic);
+        }
+        if let Some(topic_entry) = self.topics.get_mut(topic) {
+            topic_entry.qna.retain(|&other| other != card);
+        }
+    }
+
+    /// Moves a card to exactly `topics`, in that order.
+    pub fn set_card_topics(&mut self, card: FlashcardKey, topics: &[TopicKey]) {
+        let Some(card_entry) = self.cards.get(card) else {
+            return;
+        };
+        for topic in card_entry.topics.clone() {
+            self.unlink(card, topic);
+        }
+        for &topic in topics {
+            self.link(card, topic);
+        }
+    }
+
+    /// Removes a card and takes it out of its topics.
+    pub fn remove_card(&mut self, key: FlashcardKey) -> Option<Flashcard> {
+        let card = self.cards.remove(key)?;
+        for topic in self.topics.values_mut() {
+            topic.qna.retain(|&card| card != key);
+        }
+        if self.current_card == Some(key) {
+            self.current_card = None;
+        }
+        Some(card)
+    }
+
+    /// Removes a topic and unlinks it from its cards. The cards themselves are kept, and the
+    /// sub-topics move up to the parent of the removed topic.
+    pub fn remove_topic(&mut self, key: TopicKey) -> Option<Topic> {
+        let topic = self.topics.remove(key)?;
+        for card in self.cards.values_mut() {
+            card.topics.retain(|&topic| topic != key);
+        }
+        for child in self.topics.values_mut() {
+            if child.parent == Some(key) {
+                child.parent = topic.parent;
+            }
+        }
+        Some(topic)
+    }
+
+    /// Returns the direct sub-topics of a topic, in the order they were created.
+    pub fn children(&self, topic: TopicKey) -> Vec<TopicKey> {
+        self.topics
+            .iter()
+            .filter(|&(key, child)| child.parent == Some(topic) && key != topic)
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Returns the parent, grandparent and so on of a topic, nearest first.
+    pub fn ancestors(&self, topic: TopicKey) -> Vec<TopicKey> {
+        let mut ancestors = vec![];
+        let mut current = self.topics.get(topic).and_then(|topic| topic.parent);
+        while let Some(key) = current {
+            // A deck edited by hand could contain a cycle, which is cut where it closes.
+            if key == topic || ancestors.contains(&key) {
+                break;
+            }
+            let Some(parent) = self.topics.get(key) else {
+                break;
+            };
+            ancestors.push(key);
+            current = parent.parent;
+        }
+        ancestors
+    }
+
+    /// Returns every topic below a topic, depth first.
+    pub fn descendants(&self, topic: TopicKey) -> Vec<TopicKey> {
+        let mut descendants = vec![];
+        let mut stack: Vec<TopicKey> = self.children(topic).into_iter().rev().collect();
+        while let Some(key) = stack.pop() {
+            if key == topic || descendants.contains(&key) {
+                continue;
+            }
+            descendants.push(key);
+            stack.extend(self.children(key).into_iter().rev());
+        }
+        descendants
+    }
+
+    /// Returns `topics` followed by their descendants, without duplicates.
+    pub fn with_descendants(&self, topics: &[TopicKey]) -> Vec<TopicKey> {
+        let mut all = topics.to_vec();
+        for &topic in topics {
+            for descendant in self.descendants(topic) {
+                if !all.contains(&descendant) {
+                    all.push(descendant);
+                }
+            }
+        }
+        all
+    }
+
+    /// Returns the name of a topic after the names of its ancestors, like
+    /// `Biology > Cell > Organelles`.
+    pub fn topic_path(&self, topic: TopicKey) -> String {
+        let mut names: Vec<&str> = self
+            .ancestors(topic)
+            .into_iter()
+            .rev()
+            .chain([topic])
+            .filter_map(|key| self.topics.get(key))
+            .map(|topic| topic.content.as_str())
+            .collect();
+        names.dedup();
+        names.join(" > ")
+    }
+
+    /// Returns every topic with its depth in the tree, each parent followed by its sub-topics.
+    pub fn topic_tree(&self) -> Vec<(TopicKey, usize)> {
+        let mut tree = vec![];
+        let roots = self.topics.iter().filter(|(_, topic)| {
+            topic
+                .parent
+                .is_none_or(|parent| !self.topics.contains_key(parent))
+        });
+        for (root, _) in roots {
+            tree.push((root, 0));
+            for descendant in self.descendants(root) {
+                tree.push((descendant, self.ancestors(descendant).len()));
+            }
+        }
+        // Topics in a cycle have no root; they are listed at the top level.
+        for key in self.topics.keys() {
+            if !tree.iter().any(|&(listed, _)| listed == key) {
+                tree.push((key, 0));
+            }
+        }
+        tree
+    }
+
+    /// Moves a topic below `parent`, or to the top level when `parent` is None. A topic cannot
+    /// be moved below itself or one of its own descendants.
+    pub fn set_parent(
+        &mut self,
+        topic: TopicKey,
+        parent: Option<TopicKey>,
+    ) -> Result<(), TopicTreeError> {
+        if !self.topics.contains_key(topic)
+            || parent.is_some_and(|parent| !self.topics.contains_key(parent))
+        {
+            return Err(TopicTreeError::NotFound);
+        }
+        if let Some(parent) = parent {
+            if parent == topic || self.descendants(topic).contains(&parent) {
+                return Err(TopicTreeError::Cycle);
+            }
+        }
+        self.topics[topic].parent = parent;
+        Ok(())
+    }
+
+    /// Returns the key of the card with the given id.
+    pub fn card_by_id(&self, id: Uuid) -> Option<FlashcardKey> {
+        self.cards
+            .iter()
+            .find(|(_, card)| card.id == id)
+            .map(|(key, _)| key)
+    }
+
+    /// Returns the key of the topic with the given id.
+    pub fn topic_by_id(&self, id: Uuid) -> Option<TopicKey> {
+        self.topics
+            .iter()
+            .find(|(_, topic)| topic.id == id)
+            .map(|(key, _)| key)
+    }
+
+    /// Inserts a topic, giving it a fresh id unless it already has one that is not in use.
+    ///
+    /// The cards in its Q&A list are linked to it, and a parent, cards or namespaces that do not
+    /// exist are left out.
+    pub fn insert_topic(&mut self, mut topic: Topic) -> TopicKey {
+        if topic.id.is_nil() || self.topic_by_id(topic.id).is_some() {
+            topic.id = Uuid::new_v4();
+        }
+        topic.parent = topic
+            .parent
+            .filter(|&parent| self.topics.contains_key(parent));
+        topic
+            .namespaces
+            .retain(|&namespace| self.namespaces.contains_key(namespace));
+        let cards = std::mem::take(&mut topic.qna);
+        let key = self.topics.insert(topic);
+        for card in cards {
+            self.link(card, key);
+        }
+        key
+    }
+
+    /// Returns the keys of every card that belongs to at least one of `topics`, or matches the
+    /// query of one of them, in the order the cards were created.
+    pub fn cards_in_topics(&self, topics: &[TopicKey]) -> Vec<FlashcardKey> {
+        let queries = self.smart_queries(topics.iter().copied());
+        let now = unix_now();
+        self.cards
+            .iter()
+            .filter(|&(key, card)| {
+                card.topics.iter().any(|topic| topics.contains(topic))
+                    || queries.iter().any(|query| query.matches(self, key, now))
+            })
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Returns the parsed queries of the smart topics among `topics`. Queries that cannot be read,
+    /// which only a deck edited by hand can have, match no cards.
+    pub fn smart_queries(&self, topics: impl IntoIterator<Item = TopicKey>) -> Vec<Query> {
+        topics
+            .into_iter()
+            .filter_map(|key| self.topics.get(key)?.query.as_deref())
+            .filter_map(|query| Query::parse(query).ok())
+            .collect()
+    }
+
+    /// Copies `topics` and `cards` from another study session into this one and returns the keys
+    /// of the copied cards.
+    ///
+    /// Keys only mean something inside the session that created them, so everything is inserted
+    /// under fresh keys. Cards and topics are matched by id first: a card that is already here is
+    /// updated in place, and a topic that is already here is reused. Other topics are merged with
+    /// existing topics of the same name. Namespaces of the copied topics are matched the same way.
+    /// The copied cards only keep their links to the copied topics, and bring their reviews along.
+    pub fn merge_from(
+        &mut self,
+        other: &Study,
+        topics: &[TopicKey],
+        cards: &[FlashcardKey],
+    ) -> Vec<FlashcardKey> {
+        let mut namespace_keys = HashMap::new();
+        let mut topic_keys = HashMap::new();
+        for &old_key in topics {
+            let Some(topic) = other.topics.get(old_key) else {
+                continue;
+            };
+            let mut namespaces = vec![];
+            for &old_namespace in &topic.namespaces {
+                let Some(namespace) = other.namespaces.get(old_namespace) else {
+                    continue;
+                };
+                let new_namespace = *namespace_keys.entry(old_namespace).or_insert_with(|| {
+                    match self.namespace_by_id(namespace.id) {
+                        Some(key) if !namespace.id.is_nil() => key,
+                        _ => match self.namespace_by_name(&namespace.name) {
+                            Some(key) => key,
+                            None => self.insert_namespace(namespace.clone()),
+                        },
+                    }
+                });
+                namespaces.push(new_namespace);
+            }
+            let new_key = match self.topic_by_id(topic.id) {
+                Some(key) if !topic.id.is_nil() => key,
+                _ => match self.topic_by_name(&topic.content) {
+                    Some(key) => key,
+                    None => self.insert_topic(Topic {
+                        qna: vec![],
+                        namespaces: vec![],
+                        parent: None,
+                        ..topic.clone()
+                    }),
+                },
+            };
+            for namespace in namespaces {
+                self.add_to_namespace(new_key, namespace);
+            }
+            topic_keys.insert(old_key, new_key);
+        }
+        // Copied topics keep their place in the tree when their parent was copied too.
+        for (&old_key, &new_key) in &topic_keys {
+            let old_parent = other.topics[old_key].parent;
+            if let Some(&new_parent) = old_parent.and_then(|parent| topic_keys.get(&parent)) {
+                if self.topics[new_key].parent.is_none() {
+                    // A cycle with the topics already here is left out.
+                    let _ = self.set_parent(new_key, Some(new_parent));
+                }
+            }
+        }
+
+        let mut copied = vec![];
+        for card in cards.iter().filter_map(|&old_key| other.cards.get(old_key)) {
+            let topics: Vec<TopicKey> = card
+                .topics
+                .iter()
+                .filter_map(|topic| topic_keys.get(topic).copied())
+                .collect();
+            let existing = self.card_by_id(card.id).filter(|_| !card.id.is_nil());
+            match existing {
+                Some(key) => {
+                    let linked = std::mem::take(&mut self.cards[key].topics);
+                    self.cards[key] = Flashcard {
+                        topics: linked,
+                        ..card.clone()
+                    };
+                    for topic in topics {
+                        self.link(key, topic);
+                    }
+                    copied.push(key);
+                }
+                None => copied.push(self.insert_card(Flashcard {
+                    topics,
+                    ..card.clone()
+                })),
+            }
+        }
+        let ids: Vec<Uuid> = copied.iter().map(|&key| self.cards[key].id).collect();
+        for review in &other.reviews {
+            if ids.contains(&review.card) && !self.reviews.contains(review) {
+                self.reviews.push(review.clone());
+            }
+        }
+        copied
+    }
+
+    /// Inserts a flashcard and registers it in the Q&A list of every topic it belongs to.
+    /// Topics that do not exist are left out.
+    ///
+    /// The card is given a fresh id unless it already has one that is not in use.
+    pub fn insert_card(&mut self, mut card: Flashcard) -> FlashcardKey {
+        if card.id.is_nil() || self.card_by_id(card.id).is_some() {
+            card.id = Uuid::new_v4();
+        }
+        card.created.get_or_insert_with(unix_now);
+        let topics = std::mem::take(&mut card.topics);
+        let key = self.cards.insert(card);
+        for topic_key in topics {
+            self.link(key, topic_key);
+        }
+        key
+    }
+}
+
+// The Quiz holds the flashcards (by key) that are to be quizzed and a queue of Q&A (converted from flashcards).
+#[derive(Default, Debug, Clone)]
+pub struct Quiz {
+    // Keys of flashcards that have been selected for the quiz.
+    pub cards: Vec<FlashcardKey>,
+    // Queue of questions built from the selected flashcards.
+    pub qna_queue: VecDeque<Question>,
+    // Whether quizzing on a topic also quizzes on its sub-topics.
+    pub include_subtopics: bool,
+    // The timed exam being taken, if any.
+    pub exam: Option<Exam>,
+    // The order questions are asked in, and the seed they are shuffled with.
+    pub order: Order,
+    pub seed: u64,
+}
+
+// Why a topic cannot be moved in the topic tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TopicTreeError {
+    NotFound,
+    Cycle,
+}
+
+impl fmt::Display for TopicTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopicTreeError::NotFound => write!(f, "no such topic"),
+            TopicTreeError::Cycle => write!(f, "a topic cannot be moved below itself"),
+        }
+    }
+}
+
+impl std::error::Error for TopicTreeError {}
+
+// An operation applied to every topic in a namespace at once.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NamespaceOperation {
+    Enable,
+    Disable,
+    Quiz,
+    Export,
+    Move(NamespaceKey),
+    Delete,
+}
+
+// A question in the quiz. In this simple implementation it directly mirrors the flashcard fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Question {
+    pub question: String,
+    pub answer: String,
+    pub id: Uuid,
+}
+
+impl From<&Flashcard> for Question {
+    fn from(card: &Flashcard) -> Self {
+        Self {
+            question: card.question.clone(),
+            answer: card.answer.clone(),
+            id: card.id,
+        }
+    }
+}
+
+impl Question {
+    /// Returns whether an answer is right, ignoring case and surrounding whitespace.
+    pub fn check(&self, answer: &str) -> bool {
+        self.answer.trim().eq_ignore_ascii_case(answer.trim())
+    }
+}
+
+impl Quiz {
+    /// Starts the quiz by scanning the study session for flashcards that qualify.
+    ///
+    /// A flashcard qualifies if it is associated with at least one topic that is enabled, or
+    /// with a sub-topic of an enabled topic when sub-topics are included. Suspended and buried
+    /// flashcards never qualify. The questions are asked in the order of the quiz.
+    pub fn start_quiz(&mut self, study_session: &Study) {
+        self.cards.clear();
+        self.qna_queue.clear();
+        let queries = self.smart_queries(study_session);
+        let now = unix_now();
+        for (card_key, card) in study_session
+            .cards
+            .iter()
+            .filter(|(_, card)| card.quizzable(now))
+        {
+            // Check if any topic linked to the card, or any smart topic matching it, qualifies.
+            let qualifies = card
+                .topics
+                .iter()
+                .any(|&topic_key| self.topic_qualifies(study_session, topic_key))
+                || queries
+                    .iter()
+                    .any(|query| query.matches(study_session, card_key, now));
+            if qualifies {
+                self.cards.push(card_key);
+            }
+        }
+        let cards = std::mem::take(&mut self.cards);
+        self.cards = study_session.order_cards(cards, self.order, self.seed);
+        for &card_key in &self.cards {
+            let card = &study_session.cards[card_key];
+            let question = Question {
+                question: card.question.clone(),
+                answer: card.answer.clone(),
+                id: card.id,
+            };
+            self.qna_queue.push_back(question);
+        }
+        println!(
+            "Quiz started with {} questions, {}.",
+            self.qna_queue.len(),
+            self.order
+        );
+    }
+
+    /// Returns whether the cards of a topic are quizzed: the topic is enabled, or sub-topics are
+    /// included and one of its ancestors is enabled.
+    fn topic_qualifies(&self, study_session: &Study, topic_key: TopicKey) -> bool {
+        let enabled = |key: TopicKey| {
+            study_session
+                .topics
+                .get(key)
+                .is_some_and(|topic| topic.enabled)
+        };
+        enabled(topic_key)
+            || (self.include_subtopics
+                && study_session.ancestors(topic_key).into_iter().any(enabled))
+    }
+
+    /// Returns the queries of the smart topics whose cards are quizzed.
+    fn smart_queries(&self, study_session: &Study) -> Vec<Query> {
+        study_session.smart_queries(
+            study_session
+                .topics
+                .iter()
+                .filter(|(key, topic)| {
+                    topic.query.is_some() && self.topic_qualifies(study_session, *key)
+                })
+                .map(|(key, _)| key),
+        )
+    }
+
+    /// Returns the current quiz layout (the list of questions).
+    pub fn get_layout(&self) -> VecDeque<Question> {
+        println!("Current Q&A layout: {:?}", self.qna_queue);
+        self.qna_queue.clone()
+    }
+
+    /// Submits a new card to the quiz.
+    ///
+    /// The card is only added if it qualifies (i.e. it has at least one associated enabled topic
+    /// and is neither suspended nor buried).
+    /// The caller must supply the flashcard key from the study session.
+    pub fn submit_card_to_quiz(
+        &mut self,
+        card: Flashcard,
+        card_key: FlashcardKey,
+        study_session: &Study,
+    ) {
+        let now = unix_now();
+        let qualifies = card.quizzable(now)
+            && (card
+                .topics
+                .iter()
+                .any(|&topic_key| self.topic_qualifies(study_session, topic_key))
+                || self
+                    .smart_queries(study_session)
+                    .iter()
+                    .any(|query| query.matches(study_session, card_key, now)));
+        if qualifies {
+            self.cards.push(card_key);
+            let question = Question {
+                question: card.question.clone(),
+                answer: card.answer.clone(),
+                id: card.id,
+            };
+            self.qna_queue.push_back(question);
+            println!("Card {} submitted to quiz.", card.id);
+        } else {
+            println!("Card {} not eligible for quiz.", card.id);
+        }
+    }
+
+    /// Called when the user selects (or toggles) a topic to be part of the quiz.
+    ///
+    /// In this simple implementation the method toggles the topic’s enabled flag.
+    /// Topics that are enabled will contribute their flashcards to the quiz.
+    pub fn select_topic_to_quiz(&mut self, study_session: &mut Study, topic: TopicKey) {
+        if let Some(topic_entry) = study_session.topics.get_mut(topic) {
+            topic_entry.enabled = !topic_entry.enabled;
+            println!(
+                "Topic '{}' is now {} for quiz.",
+                topic_entry.content,
+                if topic_entry.enabled { "enabled" } else { "disabled" }
+            );
+        }
+    }
+
+    /// Sets the staging topic.
+    ///
+    /// This is useful when editing or creating a new topic.
+    pub fn set_topic(&mut self, study_session: &mut Study, content: String) {
+        study_session.staging_topic = content;
+        println!("Staging topic set to '{}'.", study_session.staging_topic);
+    }
+
+    /// Updates a topic’s content.
+    pub fn update_topic(&mut self, study_session: &mut Study) {
+        if let Some(current_key) = study_session.current_card {
+            if let Some(card) = study_session.cards.get(current_key) {
+                let _new_qna = Question {
+                    question: card.question.clone(),
+                    answer: card.answer.clone(),
+                    id: card.id,
+                };
+                // Iterate over topics in the study session and update those associated with the card.
+                for topic in study_session.topics.values_mut() {
+                    if topic.qna.contains(&current_key) {
+                        // For example, here you might log or update some data.
+                        println!(
+                            "Updating topic '{}' with new Q&A for card {}",
+                            topic.content, card.id
+                        );
+                        // (You could update the topic here if needed.)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Submits a new topic to the study session’s list and returns its key.
+    pub fn submit_topic_to_list(&self, study_session: &mut Study, topic: Topic) -> TopicKey {
+        let key = study_session.insert_topic(topic);
+        println!("Topic added with key.");
+        key
+    }
+
+    /// Processes the answer to the current question and returns whether it was correct, or None
+    /// when there is no question.
+    ///
+    /// The answer and the time it took are logged in the study session, which reschedules the
+    /// card. If the answer is correct (ignoring case and surrounding whitespace) the question is
+    /// removed from the queue. Otherwise the question is requeued at the back, unless the card is
+    /// a leech.
+    pub fn answer_current(
+        &mut self,
+        study_session: &mut Study,
+        user_answer: String,
+        response_ms: u64,
+    ) -> Option<bool> {
+        let Some(current_question) = self.qna_queue.front() else {
+            println!("No current question.");
+            return None;
+        };
+        let correct = current_question.check(&user_answer);
+        self.finish_current(
+            study_session,
+            user_answer,
+            Grade::from_correct(correct),
+            response_ms,
+        );
+        Some(correct)
+    }
+
+    /// Grades the current question by hand, after its answer was revealed, and returns whether
+    /// there was a question.
+    ///
+    /// The grade is logged and reschedules the card like a typed answer. A question graded Again
+    /// is requeued at the back, unless the card is a leech; any other grade removes it.
+    pub fn grade_current(
+        &mut self,
+        study_session: &mut Study,
+        grade: Grade,
+        response_ms: u64,
+    ) -> bool {
+        if self.qna_queue.is_empty() {
+            println!("No current question.");
+            return false;
+        }
+        self.finish_current(study_session, String::new(), grade, response_ms);
+        true
+    }
+
+    // Logs the answer to the question at the front of the queue and takes it off the queue,
+    // requeueing it at the back when it was forgotten.
+    fn finish_current(
+        &mut self,
+        study_session: &mut Study,
+        user_answer: String,
+        grade: Grade,
+        response_ms: u64,
+    ) {
+        let Some(current_question) = self.qna_queue.pop_front() else {
+            return;
+        };
+        let card = study_session.card_by_id(current_question.id);
+        if let Some(card) = card {
+            study_session.record_review(card, user_answer, grade, response_ms);
+        }
+        if grade != Grade::Again {
+            println!("Correct answer for question {}!", current_question.id);
+        } else if card.is_some_and(|card| study_session.cards[card].leech) {
+            println!("Incorrect answer. The card is a leech, rewrite it.");
+        } else {
+            println!("Incorrect answer. Try again.");
+            self.qna_queue.push_back(current_question);
+        }
+    }
+
+    /// Ends the quiz by clearing the question queue.
+    pub fn end_quiz(&mut self) {
+        self.qna_queue.clear();
+        println!("Quiz ended!");
+    }
+
+    /// This helper toggles a topic on the current card.
+    ///
+    /// When called, if the topic is already enabled on the current card it will be removed;
+    /// otherwise it is added.
+    pub fn select_topic_for_card(&self, study_session: &mut Study, topic_key: TopicKey) {
+        let Some(topic) = study_session.topics.get(topic_key) else {
+            return;
+        };
+        let enabled = topic.enabled;
+        if enabled {
+            println!("Topic deselected: {}", topic.content);
+        } else {
+            println!("Topic selected: {}", topic.content);
+        }
+        if let Some(current_key) = study_session.current_card {
+            if enabled {
+                study_session.unlink(current_key, topic_key);
+            } else {
+                study_session.link(current_key, topic_key);
+            }
+        }
+        study_session.topics[topic_key].enabled = !enabled;
+    }
+
+    /// Adds a topic to a namespace, or takes it out if it is in the namespace already.
+    pub fn toggle_namespace(
+        &self,
+        study_session: &mut Study,
+        topic: TopicKey,
+        namespace: NamespaceKey,
+    ) {
+        let (Some(topic_entry), Some(namespace_entry)) = (
+            study_session.topics.get(topic),
+            study_session.namespaces.get(namespace),
+        ) else {
+            return;
+        };
+        if topic_entry.namespaces.contains(&namespace) {
+            println!(
+                "Topic '{}' removed from {}.",
+                topic_entry.content, namespace_entry.name
+            );
+            study_session.remove_from_namespace(topic, namespace);
+        } else {
+            println!(
+                "Topic '{}' added to {}.",
+                topic_entry.content, namespace_entry.name
+            );
+            study_session.add_to_namespace(topic, namespace);
+        }
+    }
+
+    /// Applies an operation to every topic in a namespace and returns the keys of those topics.
+    ///
+    /// Exporting is left to the caller, which writes the returned topics in the format it wants.
+    pub fn operation_for_namespace(
+        &mut self,
+        study_session: &mut Study,
+        namespace: NamespaceKey,
+        operation: &NamespaceOperation,
+    ) -> Vec<TopicKey> {
+        let topics = study_session.topics_in_namespace(namespace);
+        match operation {
+            NamespaceOperation::Enable | NamespaceOperation::Disable => {
+                for &topic in &topics {
+                    study_session.topics[topic].enabled = *operation == NamespaceOperation::Enable;
+                }
+            }
+            NamespaceOperation::Quiz => self.start_quiz_on(study_session, &topics),
+            NamespaceOperation::Export => {}
+            NamespaceOperation::Move(target) => {
+                for &topic in &topics {
+                    study_session.remove_from_namespace(topic, namespace);
+                    study_session.add_to_namespace(topic, *target);
+                }
+            }
+            NamespaceOperation::Delete => {
+                for &topic in &topics {
+                    study_session.remove_topic(topic);
+                }
+            }
+        }
+        println!(
+            "{:?} applied to {} topics in {}.",
+            operation,
+            topics.len(),
+            study_session
+                .namespaces
+                .get(namespace)
+                .map_or("?", |namespace| namespace.name.as_str())
+        );
+        topics
+    }
+
+    /// Starts a quiz on every card of the given topics, and of their sub-topics when those are
+    /// included, in the order of the quiz. Suspended and buried cards are left out.
+    pub fn start_quiz_on(&mut self, study_session: &Study, topics: &[TopicKey]) {
+        self.cards = if self.include_subtopics {
+            study_session.cards_in_topics(&study_session.with_descendants(topics))
+        } else {
+            study_session.cards_in_topics(topics)
+        };
+        let now = unix_now();
+        self.cards.retain(|&key| study_session.cards[key].quizzable(now));
+        let cards = std::mem::take(&mut self.cards);
+        self.cards = study_session.order_cards(cards, self.order, self.seed);
+        self.qna_queue = self
+            .cards
+            .iter()
+            .map(|&key| {
+                let card = &study_session.cards[key];
+                Question {
+                    question: card.question.clone(),
+                    answer: card.answer.clone(),
+                    id: card.id,
+                }
+            })
+            .collect();
+        println!(
+            "Quiz started with {} questions, {}.",
+            self.qna_queue.len(),
+            self.order
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiz_flow() {
+        // Create an empty study session.
+        let mut study = Study::default();
+
+        // Insert some topics.
+        let quiz_topic = Topic {
+            content: "Math".to_string(),
+            enabled: false,
+            qna: Vec::new(),
+            ..Default::default()
+        };
+        let quiz_topic_key = study.topics.insert(quiz_topic);
+
+        let configure_topic = Topic {
+            content: "History".to_string(),
+            enabled: false,
+            qna: Vec::new(),
+            ..Default::default()
+        };
+        let _configure_topic_key = study.topics.insert(configure_topic);
+
+        // Create a flashcard that belongs to the Math topic.
+        let flashcard = Flashcard {
+            bg_color: None,
+            question: "What is 2+2?".to_string(),
+            answer: "4".to_string(),
+            id: Uuid::new_v4(),
+            topics: vec![quiz_topic_key],
+            ..Default::default()
+        };
+        let _flashcard_key = study.cards.insert(flashcard);
+
+        // Enable the Math topic (so it qualifies for the quiz).
+        if let Some(topic) = study.topics.get_mut(quiz_topic_key) {
+            topic.enabled = true;
+        }
+
+        // Create a quiz and start it using the study session.
+        let mut quiz = Quiz::default();
+        quiz.start_quiz(&study);
+
+        // Check that the quiz has one question.
+        assert_eq!(quiz.qna_queue.len(), 1);
+
+        // Simulate answering the question incorrectly.
+        quiz.answer_current(&mut study, "3".to_string(), 0);
+        // The question should have been requeued.
+        assert_eq!(quiz.qna_queue.len(), 1);
+
+        // Now answer correctly.
+        quiz.answer_current(&mut study, "4".to_string(), 0);
+        // Now the question queue should be empty.
+        assert_eq!(quiz.qna_queue.len(), 0);
+
+        // End the quiz.
+        quiz.end_quiz();
+    }
+
+    #[test]
+    fn ids_are_assigned_and_unique() {
+        let mut study = Study::default();
+        let topic = study.topic_by_name_or_insert("Math");
+        let first = study.insert_card(Flashcard::default());
+        let copy = study.cards[first].clone();
+        let second = study.insert_card(copy);
+
+        let id = study.cards[first].id;
+        assert!(!id.is_nil());
+        assert!(!study.topics[topic].id.is_nil());
+        assert_ne!(study.cards[second].id, id);
+        assert_eq!(study.card_by_id(id), Some(first));
+        assert_eq!(study.topic_by_id(study.topics[topic].id), Some(topic));
+    }
+
+    #[test]
+    fn merging_matches_cards_by_id() {
+        let mut source = Study::default();
+        let topic = source.topic_by_name_or_insert("Math");
+        let card = source.insert_card(Flashcard {
+            question: "1+1?".to_string(),
+            topics: vec![topic],
+            ..Default::default()
+        });
+
+        let mut target = Study::default();
+        target.merge_from(&source, &[topic], &[card]);
+        source.cards[card].answer = "2".to_string();
+        let merged = target.merge_from(&source, &[topic], &[card]);
+
+        assert_eq!(target.cards.len(), 1);
+        assert_eq!(target.topics.len(), 1);
+        let merged = &target.cards[merged[0]];
+        assert_eq!(merged.id, source.cards[card].id);
+        assert_eq!(merged.answer, "2");
+        assert_eq!(target.topics.values().next().unwrap().qna.len(), 1);
+    }
+
+    #[test]
+    fn namespace_operations() {
+        let mut study = Study::default();
+        let semester = study.insert_namespace(Namespace {
+            name: "Semester 1".to_string(),
+            ..Default::default()
+        });
+        let exam = study.insert_namespace(Namespace {
+            name: "Exam prep".to_string(),
+            ..Default::default()
+        });
+        let algebra = study.insert_topic(Topic {
+            content: "Algebra".to_string(),
+            namespaces: vec![semester],
+            ..Default::default()
+        });
+        let geometry = study.insert_topic(Topic {
+            content: "Geometry".to_string(),
+            namespaces: vec![semester, exam],
+            ..Default::default()
+        });
+        let other = study.topic_by_name_or_insert("Other");
+        let card = study.insert_card(Flashcard {
+            question: "x + 1 = 2?".to_string(),
+            topics: vec![algebra, other],
+            ..Default::default()
+        });
+
+        let mut quiz = Quiz::default();
+        quiz.operation_for_namespace(&mut study, semester, &NamespaceOperation::Enable);
+        assert!(study.topics[algebra].enabled && study.topics[geometry].enabled);
+        assert!(!study.topics[other].enabled);
+
+        quiz.operation_for_namespace(&mut study, semester, &NamespaceOperation::Quiz);
+        assert_eq!(quiz.cards, [card]);
+
+        let moved = NamespaceOperation::Move(exam);
+        quiz.operation_for_namespace(&mut study, semester, &moved);
+        assert!(study.topics_in_namespace(semester).is_empty());
+        assert_eq!(study.topics_in_namespace(exam), [algebra, geometry]);
+        assert_eq!(study.topics[geometry].namespaces, [exam]);
+
+        quiz.operation_for_namespace(&mut study, exam, &NamespaceOperation::Delete);
+        assert_eq!(study.topics.len(), 1);
+        assert_eq!(study.cards[card].topics, [other]);
+    }
+
+    #[test]
+    fn namespaces_are_ordered_and_merged() {
+        let mut study = Study::default();
+        let names = ["Semester 1", "Semester 2", "Exam prep"];
+        let keys: Vec<NamespaceKey> = names
+            .iter()
+            .map(|name| {
+                study.insert_namespace(Namespace {
+                    name: name.to_string(),
+                    color: Color::from_hex("#336699").unwrap(),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        study.move_namespace(keys[2], 0);
+        assert_eq!(study.ordered_namespaces(), [keys[2], keys[0], keys[1]]);
+        assert_eq!(study.namespaces[keys[0]].color.to_hex(), "#336699");
+
+        let topic = study.insert_topic(Topic {
+            content: "Algebra".to_string(),
+            namespaces: vec![keys[0], keys[2]],
+            ..Default::default()
+        });
+        let card = study.insert_card(Flashcard {
+            topics: vec![topic],
+            ..Default::default()
+        });
+
+        let mut copy = Study::default();
+        copy.merge_from(&study, &[topic], &[card]);
+        let copied = copy.topics.values().next().unwrap();
+        let copied_names: Vec<&str> = copied
+            .namespaces
+            .iter()
+            .map(|&key| copy.namespaces[key].name.as_str())
+            .collect();
+        assert_eq!(copied_names, ["Semester 1", "Exam prep"]);
+        assert_eq!(copy.namespaces.len(), 2);
+
+        study.remove_namespace(keys[0]);
+        assert_eq!(study.topics[topic].namespaces, [keys[2]]);
+    }
+
+    #[test]
+    fn topic_tree() {
+        let mut study = Study::default();
+        let biology = study.topic_by_name_or_insert("Biology");
+        let cell = study.topic_by_name_or_insert("Cell");
+        let organelles = study.topic_by_name_or_insert("Organelles");
+        let history = study.topic_by_name_or_insert("History");
+        study.set_parent(cell, Some(biology)).unwrap();
+        study.set_parent(organelles, Some(cell)).unwrap();
+
+        assert_eq!(study.topic_path(organelles), "Biology > Cell > Organelles");
+        assert_eq!(
+            study.topic_tree(),
+            [(biology, 0), (cell, 1), (organelles, 2), (history, 0)]
+        );
+        assert_eq!(
+            study.set_parent(biology, Some(organelles)),
+            Err(TopicTreeError::Cycle)
+        );
+        assert_eq!(study.set_parent(cell, Some(cell)), Err(TopicTreeError::Cycle));
+
+        let card = study.insert_card(Flashcard {
+            question: "What makes energy?".to_string(),
+            topics: vec![organelles],
+            ..Default::default()
+        });
+        let mut quiz = Quiz::default();
+        quiz.start_quiz_on(&study, &[biology]);
+        assert!(quiz.cards.is_empty());
+        quiz.include_subtopics = true;
+        quiz.start_quiz_on(&study, &[biology]);
+        assert_eq!(quiz.cards, [card]);
+        study.topics[biology].enabled = true;
+        quiz.start_quiz(&study);
+        assert_eq!(quiz.cards, [card]);
+
+        study.remove_topic(cell);
+        assert_eq!(study.topics[organelles].parent, Some(biology));
+    }
+}
@@ -0,0 +1,73 @@
+//! A small seeded random number generator for picking and ordering questions.
+//!
+//! The same seed always gives the same numbers, so an exam or a shuffled quiz can be repeated.
+//! It is not meant for anything that has to be unpredictable.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A SplitMix64 generator.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Returns a seed taken from the clock, for when the order does not have to be repeated.
+    pub fn time_seed() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64)
+    }
+
+    /// Returns the next number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number below `bound`, which must not be zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Shuffles the items in place.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            let other = self.below(index + 1);
+            items.swap(index, other);
+        }
+    }
+
+    /// Returns `count` of the items picked at random, or all of them in a random order when there
+    /// are fewer.
+    pub fn sample<T>(&mut self, mut items: Vec<T>, count: usize) -> Vec<T> {
+        self.shuffle(&mut items);
+        items.truncate(count);
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeats_with_the_same_seed() {
+        let numbers: Vec<u32> = (0..20).collect();
+        let first = Rng::new(7).sample(numbers.clone(), 5);
+        assert_eq!(first, Rng::new(7).sample(numbers.clone(), 5));
+        assert_ne!(first, Rng::new(8).sample(numbers.clone(), 5));
+        assert_eq!(first.len(), 5);
+
+        let mut all = Rng::new(7).sample(numbers.clone(), 50);
+        all.sort();
+        assert_eq!(all, numbers);
+    }
+}
@@ -0,0 +1,242 @@
+//! The review log and the scheduling of cards.
+//!
+//! Every answer given in a quiz is kept in [`Study::reviews`], and moves the card along its
+//! [`Schedule`] with the SM-2 algorithm: a card that is remembered comes back after one day, then
+//! six, then after its last interval times its easiness, and a card that is forgotten starts over.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::quiz::{unix_now, FlashcardKey, Schedule, Study, SECONDS_PER_DAY};
+
+/// How well a card was remembered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Grade {
+    /// Forgotten; the card starts over.
+    Again,
+    /// Remembered with difficulty.
+    Hard,
+    /// Remembered.
+    Good,
+    /// Remembered without effort.
+    Easy,
+}
+
+impl Grade {
+    /// The grade of a typed answer.
+    pub fn from_correct(correct: bool) -> Self {
+        if correct {
+            Grade::Good
+        } else {
+            Grade::Again
+        }
+    }
+
+    // The quality of the answer on the 0 to 5 scale of SM-2.
+    fn quality(self) -> f32 {
+        match self {
+            Grade::Again => 1.0,
+            Grade::Hard => 3.0,
+            Grade::Good => 4.0,
+            Grade::Easy => 5.0,
+        }
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Grade::Again => "again",
+            Grade::Hard => "hard",
+            Grade::Good => "good",
+            Grade::Easy => "easy",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Grade {
+    type Err = String;
+
+    /// Parses a grade by name or by its number from 1 for Again to 4 for Easy.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        GRADES
+            .into_iter()
+            .enumerate()
+            .find(|(i, grade)| {
+                grade.to_string().eq_ignore_ascii_case(text) || (i + 1).to_string() == text
+            })
+            .map(|(_, grade)| grade)
+            .ok_or_else(|| format!("unknown grade {text}, expected again, hard, good or easy"))
+    }
+}
+
+/// Every grade, from worst to best.
+pub const GRADES: [Grade; 4] = [Grade::Again, Grade::Hard, Grade::Good, Grade::Easy];
+
+// One answer to a card. The card is referred to by id so the log survives saving and loading; the
+// answer is what was typed, empty when the card was graded without typing. Times are unix
+// timestamps in seconds, the response time is in milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Review {
+    pub card: Uuid,
+    pub time: u64,
+    pub answer: String,
+    pub grade: Grade,
+    pub response_ms: u64,
+}
+
+impl Review {
+    /// Returns whether the card was remembered.
+    pub fn correct(&self) -> bool {
+        self.grade != Grade::Again
+    }
+}
+
+impl Schedule {
+    /// Moves the schedule on after an answer with `grade` at the unix time `now`.
+    pub fn review(&mut self, grade: Grade, now: u64) {
+        let quality = grade.quality();
+        if grade == Grade::Again {
+            self.repetitions = 0;
+            self.lapses += 1;
+            self.interval = 1;
+        } else {
+            self.repetitions += 1;
+            self.interval = match self.repetitions {
+                1 => 1,
+                2 => 6,
+                _ => (self.interval as f32 * self.easiness).round() as u32,
+            };
+        }
+        self.easiness =
+            (self.easiness + 0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02)).max(1.3);
+        self.last_review = Some(now);
+        self.next_review = Some(now + u64::from(self.interval) * SECONDS_PER_DAY);
+    }
+}
+
+impl Study {
+    /// Logs an answer to a card and reschedules the card, flagging it as a leech when it was
+    /// forgotten too often. Returns whether the card became a leech, so the caller can say so.
+    /// Nothing happens when the card does not exist.
+    pub fn record_review(
+        &mut self,
+        card: FlashcardKey,
+        answer: String,
+        grade: Grade,
+        response_ms: u64,
+    ) -> bool {
+        let now = unix_now();
+        let Some(entry) = self.cards.get_mut(card) else {
+            return false;
+        };
+        entry.schedule.review(grade, now);
+        self.reviews.push(Review {
+            card: entry.id,
+            time: now,
+            answer,
+            grade,
+            response_ms,
+        });
+        grade == Grade::Again && self.check_leech(card)
+    }
+
+    /// Returns the reviews of a card, oldest first.
+    pub fn reviews_of(&self, card: FlashcardKey) -> Vec<&Review> {
+        let Some(card) = self.cards.get(card) else {
+            return vec![];
+        };
+        self.reviews
+            .iter()
+            .filter(|review| review.card == card.id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::{Flashcard, Quiz, Topic};
+
+    #[test]
+    fn schedules_with_sm2() {
+        let mut schedule = Schedule::default();
+        schedule.review(Grade::Good, 0);
+        assert_eq!(schedule.interval, 1);
+        schedule.review(Grade::Good, SECONDS_PER_DAY);
+        assert_eq!(schedule.interval, 6);
+        schedule.review(Grade::Easy, 7 * SECONDS_PER_DAY);
+        assert_eq!(schedule.interval, 15);
+        assert_eq!(schedule.next_review, Some(22 * SECONDS_PER_DAY));
+        assert!(schedule.easiness > 2.5);
+
+        schedule.review(Grade::Again, 23 * SECONDS_PER_DAY);
+        assert_eq!((schedule.repetitions, schedule.lapses), (0, 1));
+        assert_eq!(schedule.interval, 1);
+        for _ in 0..20 {
+            schedule.review(Grade::Again, 0);
+        }
+        assert_eq!(schedule.easiness, 1.3);
+    }
+
+    #[test]
+    fn quiz_answers_are_logged() {
+        let mut study = Study::default();
+        let math = study.insert_topic(Topic {
+            content: "Math".to_string(),
+            enabled: true,
+            ..Default::default()
+        });
+        let card = study.insert_card(Flashcard {
+            question: "2+2?".to_string(),
+            answer: "4".to_string(),
+            topics: vec![math],
+            ..Default::default()
+        });
+        let mut quiz = Quiz::default();
+        quiz.start_quiz(&study);
+        assert_eq!(
+            quiz.answer_current(&mut study, "5".to_string(), 1200),
+            Some(false)
+        );
+        assert_eq!(
+            quiz.answer_current(&mut study, " 4 ".to_string(), 800),
+            Some(true)
+        );
+        assert_eq!(quiz.answer_current(&mut study, "4".to_string(), 0), None);
+
+        let reviews = study.reviews_of(card);
+        assert_eq!(reviews.len(), 2);
+        assert_eq!(
+            (reviews[0].answer.as_str(), reviews[0].correct()),
+            ("5", false)
+        );
+        assert_eq!(
+            (reviews[1].grade, reviews[1].response_ms),
+            (Grade::Good, 800)
+        );
+        assert_eq!(study.cards[card].schedule.lapses, 1);
+        assert_eq!(study.cards[card].schedule.repetitions, 1);
+
+        // Graded by hand, Again requeues the card and the other grades take it off the queue.
+        quiz.start_quiz(&study);
+        assert!(quiz.grade_current(&mut study, Grade::Again, 3000));
+        assert_eq!(quiz.qna_queue.len(), 1);
+        assert!(quiz.grade_current(&mut study, "4".parse().unwrap(), 1000));
+        assert!(!quiz.grade_current(&mut study, Grade::Good, 0));
+        let grades: Vec<Grade> = study
+            .reviews_of(card)
+            .iter()
+            .map(|review| review.grade)
+            .collect();
+        assert_eq!(
+            grades,
+            [Grade::Again, Grade::Good, Grade::Again, Grade::Easy]
+        );
+        assert_eq!("hard".parse(), Ok(Grade::Hard));
+    }
+}
@@ -0,0 +1,349 @@
+//! Full-text search over the cards of a study session.
+//!
+//! [`SearchIndex`] keeps an inverted index from terms to the cards that contain them, built from
+//! the question, the answer and the topic names of every card. Words are lower cased and stemmed,
+//! so "Cells" finds "cell". A query word also matches the terms it is the start of, and terms that
+//! are one or two typos away from it. Every word of a query has to match for a card to be found,
+//! and the cards are ranked by how well and how rarely their words match.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+use crate::quiz::{FlashcardKey, Study};
+
+// How much a word counts towards the score, depending on where it is found.
+const QUESTION_WEIGHT: f32 = 3.0;
+const ANSWER_WEIGHT: f32 = 2.0;
+const TOPIC_WEIGHT: f32 = 1.0;
+
+// How much a match counts compared to an exact one.
+const PREFIX_FACTOR: f32 = 0.75;
+const FUZZY_FACTOR: f32 = 0.5;
+
+/// A card found by a search, with a score that is higher for better matches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub card: FlashcardKey,
+    pub score: f32,
+}
+
+// The terms of one indexed card, and a hash of its text to tell whether it changed.
+#[derive(Debug, Clone)]
+struct Document {
+    signature: u64,
+    terms: Vec<String>,
+}
+
+/// An inverted index over the cards of a study session.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    // Sorted, so the terms starting with a prefix are next to each other.
+    postings: BTreeMap<String, HashMap<FlashcardKey, f32>>,
+    documents: HashMap<FlashcardKey, Document>,
+}
+
+impl SearchIndex {
+    /// Indexes every card of `study`.
+    pub fn new(study: &Study) -> Self {
+        let mut index = Self::default();
+        index.refresh(study);
+        index
+    }
+
+    /// Brings the index up to date with `study` and returns how many cards were indexed again.
+    /// Only cards that were added, changed or removed since the last refresh are touched, but
+    /// every card is looked at, so after an edit to a few cards [`SearchIndex::update_card`] is
+    /// quicker.
+    pub fn refresh(&mut self, study: &Study) -> usize {
+        let removed: Vec<FlashcardKey> = self
+            .documents
+            .keys()
+            .filter(|&&key| !study.cards.contains_key(key))
+            .copied()
+            .collect();
+        let mut changed = removed.len();
+        for key in removed {
+            self.remove_card(key);
+        }
+        for key in study.cards.keys() {
+            changed += usize::from(self.update_card(study, key));
+        }
+        changed
+    }
+
+    /// Indexes one card of `study` again, or takes it out of the index when it was removed, and
+    /// returns whether anything changed.
+    pub fn update_card(&mut self, study: &Study, key: FlashcardKey) -> bool {
+        if !study.cards.contains_key(key) {
+            return self.remove_card(key);
+        }
+        let fields = fields(study, key);
+        let signature = signature(&fields);
+        if self
+            .documents
+            .get(&key)
+            .is_some_and(|document| document.signature == signature)
+        {
+            return false;
+        }
+        self.remove_card(key);
+        self.add_card(key, signature, &fields);
+        true
+    }
+
+    /// Takes a card out of the index and returns whether it was in it.
+    pub fn remove_card(&mut self, key: FlashcardKey) -> bool {
+        let Some(document) = self.documents.remove(&key) else {
+            return false;
+        };
+        for term in document.terms {
+            if let Some(cards) = self.postings.get_mut(&term) {
+                cards.remove(&key);
+                if cards.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns the cards matching every word of `query`, best match first. An empty query finds
+    /// nothing.
+    pub fn search(&self, query: &str) -> Vec<Hit> {
+        let words = tokenize(query);
+        if words.is_empty() {
+            return vec![];
+        }
+        let mut scores: Option<HashMap<FlashcardKey, f32>> = None;
+        for word in &words {
+            let matches = self.match_word(word);
+            scores = Some(match scores {
+                None => matches,
+                // A card has to match every word, so only cards matched so far are kept.
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(card, score)| Some((card, score + matches.get(&card)?)))
+                    .collect(),
+            });
+        }
+        let mut hits: Vec<Hit> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(card, score)| Hit { card, score })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.card.cmp(&b.card)));
+        hits
+    }
+
+    // Scores the cards matching one query word, taking the best match of each card.
+    fn match_word(&self, word: &str) -> HashMap<FlashcardKey, f32> {
+        let stemmed = stem(word);
+        let max_typos = match stemmed.chars().count() {
+            0..=3 => 0,
+            4..=7 => 1,
+            _ => 2,
+        };
+        let mut scores: HashMap<FlashcardKey, f32> = HashMap::new();
+        let mut add = |term: &str, factor: f32| {
+            let cards = &self.postings[term];
+            let rarity = (1.0 + self.documents.len() as f32 / cards.len() as f32).ln();
+            for (&card, &weight) in cards {
+                let score = scores.entry(card).or_default();
+                *score = score.max(factor * weight * rarity);
+            }
+        };
+        if self.postings.contains_key(&stemmed) {
+            add(&stemmed, 1.0);
+        }
+        // The word may be unfinished, so it is matched against the terms it starts.
+        for term in self
+            .postings
+            .range(word.to_string()..)
+            .map(|(term, _)| term)
+            .take_while(|term| term.starts_with(word))
+            .filter(|term| **term != stemmed)
+        {
+            add(term, PREFIX_FACTOR);
+        }
+        if max_typos > 0 {
+            let length = stemmed.chars().count();
+            let close: Vec<&String> = self
+                .postings
+                .keys()
+                .filter(|term| term.chars().count().abs_diff(length) <= max_typos)
+                .filter(|term| **term != stemmed && !term.starts_with(word))
+                .filter(|term| edit_distance(term, &stemmed) <= max_typos)
+                .collect();
+            for term in close {
+                add(term, FUZZY_FACTOR);
+            }
+        }
+        scores
+    }
+
+    fn add_card(&mut self, key: FlashcardKey, signature: u64, fields: &[(String, f32)]) {
+        let mut weights: HashMap<String, f32> = HashMap::new();
+        for (text, weight) in fields {
+            for word in tokenize(text) {
+                *weights.entry(stem(&word)).or_default() += weight;
+            }
+        }
+        let terms = weights.keys().cloned().collect();
+        for (term, weight) in weights {
+            self.postings.entry(term).or_default().insert(key, weight);
+        }
+        self.documents.insert(key, Document { signature, terms });
+    }
+}
+
+// The searchable text of a card with the weight of each part.
+fn fields(study: &Study, key: FlashcardKey) -> Vec<(String, f32)> {
+    let card = &study.cards[key];
+    let mut fields = vec![
+        (card.question.clone(), QUESTION_WEIGHT),
+        (card.answer.clone(), ANSWER_WEIGHT),
+    ];
+    fields.extend(
+        card.topics
+            .iter()
+            .filter_map(|&topic| study.topics.get(topic))
+            .map(|topic| (topic.content.clone(), TOPIC_WEIGHT)),
+    );
+    fields
+}
+
+fn signature(fields: &[(String, f32)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (text, weight) in fields {
+        text.hash(&mut hasher);
+        weight.to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Splits text into lower case words, dropping punctuation and markup.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Strips common English endings from a lower case word, so that "cells", "celled" and "cell"
+/// give the same term. This is far simpler than a full stemmer, but it is applied the same way
+/// to the cards and to the queries, which is all matching needs.
+pub fn stem(word: &str) -> String {
+    if word.chars().count() <= 3 || !word.is_ascii() {
+        return word.to_string();
+    }
+    let mut stem = if let Some(base) = word.strip_suffix("sses") {
+        format!("{base}ss")
+    } else if let Some(base) = word.strip_suffix("ies") {
+        format!("{base}y")
+    } else if word.ends_with("ss") || word.ends_with("us") || word.ends_with("is") {
+        word.to_string()
+    } else {
+        word.strip_suffix('s').unwrap_or(word).to_string()
+    };
+    for suffix in ["ing", "ed"] {
+        if let Some(base) = stem.strip_suffix(suffix) {
+            if base.len() >= 3 {
+                stem = base.to_string();
+                // "running" becomes "run" rather than "runn".
+                let bytes = stem.as_bytes();
+                let last = bytes[bytes.len() - 1];
+                if last == bytes[bytes.len() - 2] && !b"aeioulsz".contains(&last) {
+                    stem.pop();
+                }
+            }
+            break;
+        }
+    }
+    if stem.len() > 3 && stem.ends_with('e') {
+        stem.pop();
+    }
+    stem
+}
+
+// The number of single character insertions, deletions and substitutions between two words.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::Flashcard;
+
+    fn card(study: &mut Study, question: &str, answer: &str) -> FlashcardKey {
+        study.insert_card(Flashcard {
+            question: question.to_string(),
+            answer: answer.to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn stems_words() {
+        assert_eq!(stem("cells"), "cell");
+        assert_eq!(stem("running"), stem("run"));
+        assert_eq!(stem("moving"), stem("move"));
+        assert_eq!(stem("classes"), stem("class"));
+        assert_eq!(stem("mitosis"), "mitosis");
+        assert_eq!(
+            tokenize("**Mitochondria**, the cell's"),
+            ["mitochondria", "the", "cell", "s"]
+        );
+    }
+
+    #[test]
+    fn finds_and_ranks_cards() {
+        let mut study = Study::default();
+        let powerhouse = card(
+            &mut study,
+            "What is the powerhouse of the cell?",
+            "Mitochondria",
+        );
+        let membrane = card(&mut study, "What surrounds cells?", "The cell membrane");
+        let capital = card(&mut study, "Capital of France?", "Paris");
+        let biology = study.topic_by_name_or_insert("Biology");
+        study.link(capital, biology);
+        let mut index = SearchIndex::new(&study);
+
+        let cards = |hits: Vec<Hit>| hits.into_iter().map(|hit| hit.card).collect::<Vec<_>>();
+        // "cells" is stemmed, and the card with the word twice ranks first.
+        assert_eq!(cards(index.search("Cells")), [membrane, powerhouse]);
+        assert_eq!(cards(index.search("mito")), [powerhouse]);
+        assert_eq!(cards(index.search("mitochondira")), [powerhouse]);
+        assert_eq!(cards(index.search("cell paris")), []);
+        assert_eq!(cards(index.search("biology")), [capital]);
+        assert!(index.search("  ").is_empty());
+
+        // Only changed cards are indexed again.
+        study.cards[capital].answer = "Paris, on the Seine".to_string();
+        study.remove_card(membrane);
+        assert_eq!(index.refresh(&study), 2);
+        assert_eq!(index.refresh(&study), 0);
+        assert_eq!(cards(index.search("seine")), [capital]);
+        assert_eq!(cards(index.search("membrane")), []);
+
+        // Single cards can be brought up to date without looking at the others.
+        study.cards[powerhouse].answer = "The mitochondrion".to_string();
+        assert!(index.update_card(&study, powerhouse));
+        assert!(!index.update_card(&study, powerhouse));
+        assert_eq!(cards(index.search("mitochondrion")), [powerhouse]);
+        study.remove_card(powerhouse);
+        assert!(index.update_card(&study, powerhouse));
+        assert_eq!(cards(index.search("mitochondrion")), []);
+    }
+}
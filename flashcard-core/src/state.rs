@@ -0,0 +1,232 @@
+//! Card states that keep cards out of quizzes or mark them: suspended, buried and flagged.
+//!
+//! A suspended card is never quizzed until it is unsuspended. A buried card is left out until the
+//! end of the day, in UTC like the days of the statistics. A flag marks a card with a colour, for
+//! example to find the cards that need fixing later; it does not keep the card out of quizzes.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::quiz::{Color, Flashcard, FlashcardKey, Study, SECONDS_PER_DAY};
+
+/// A coloured flag on a card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Flag {
+    Red,
+    Orange,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl Flag {
+    pub const ALL: [Flag; 5] = [
+        Flag::Red,
+        Flag::Orange,
+        Flag::Green,
+        Flag::Blue,
+        Flag::Purple,
+    ];
+
+    /// Returns the colour the flag is drawn in.
+    pub fn color(self) -> Color {
+        match self {
+            Flag::Red => Color::from_rgb(0.9, 0.2, 0.2),
+            Flag::Orange => Color::from_rgb(1.0, 0.6, 0.1),
+            Flag::Green => Color::from_rgb(0.3, 0.75, 0.3),
+            Flag::Blue => Color::from_rgb(0.25, 0.5, 0.95),
+            Flag::Purple => Color::from_rgb(0.65, 0.35, 0.85),
+        }
+    }
+}
+
+impl fmt::Display for Flag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Flag::Red => "red",
+            Flag::Orange => "orange",
+            Flag::Green => "green",
+            Flag::Blue => "blue",
+            Flag::Purple => "purple",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Flag {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Flag::ALL
+            .into_iter()
+            .find(|flag| flag.to_string().eq_ignore_ascii_case(text.trim()))
+            .ok_or_else(|| {
+                format!("unknown flag {text}, expected one of red, orange, green, blue or purple")
+            })
+    }
+}
+
+/// Which cards to list, by their state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Suspended,
+    Buried,
+    Leech,
+    /// Cards with any flag.
+    Flagged,
+    /// Cards with this flag.
+    Flag(Flag),
+}
+
+impl Filter {
+    /// Returns the filters in the order they are offered.
+    pub fn all() -> Vec<Filter> {
+        [
+            Filter::Suspended,
+            Filter::Buried,
+            Filter::Leech,
+            Filter::Flagged,
+        ]
+        .into_iter()
+        .chain(Flag::ALL.map(Filter::Flag))
+        .collect()
+    }
+
+    /// Returns whether a card passes the filter at the unix time `now`.
+    pub fn matches(self, card: &Flashcard, now: u64) -> bool {
+        match self {
+            Filter::Suspended => card.suspended,
+            Filter::Buried => card.is_buried(now),
+            Filter::Leech => card.leech,
+            Filter::Flagged => card.flag.is_some(),
+            Filter::Flag(flag) => card.flag == Some(flag),
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::Suspended => write!(f, "suspended"),
+            Filter::Buried => write!(f, "buried"),
+            Filter::Leech => write!(f, "leech"),
+            Filter::Flagged => write!(f, "flagged"),
+            Filter::Flag(flag) => write!(f, "{flag}"),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Filter::all()
+            .into_iter()
+            .find(|filter| filter.to_string().eq_ignore_ascii_case(text.trim()))
+            .ok_or_else(|| {
+                format!(
+                    "unknown filter {text}, expected suspended, buried, leech, flagged or a flag \
+                     colour"
+                )
+            })
+    }
+}
+
+impl Flashcard {
+    /// Buries the card until the end of the day of the unix time `now`.
+    pub fn bury(&mut self, now: u64) {
+        self.buried_until = Some((now / SECONDS_PER_DAY + 1) * SECONDS_PER_DAY);
+    }
+
+    /// Returns whether the card is buried at the unix time `now`.
+    pub fn is_buried(&self, now: u64) -> bool {
+        self.buried_until.is_some_and(|until| now < until)
+    }
+
+    /// Returns whether the card can be quizzed at the unix time `now`: it is neither suspended
+    /// nor buried.
+    pub fn quizzable(&self, now: u64) -> bool {
+        !self.suspended && !self.is_buried(now)
+    }
+}
+
+impl Study {
+    /// Returns the cards passing `filter` at the unix time `now`.
+    pub fn filter_cards(&self, filter: Filter, now: u64) -> Vec<FlashcardKey> {
+        self.cards
+            .iter()
+            .filter(|(_, card)| filter.matches(card, now))
+            .map(|(key, _)| key)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::{unix_now, Quiz, Topic};
+
+    #[test]
+    fn states_keep_cards_out_of_quizzes() {
+        let mut study = Study::default();
+        let math = study.insert_topic(Topic {
+            content: "Math".to_string(),
+            enabled: true,
+            ..Default::default()
+        });
+        let [suspended, buried, flagged] = ["1+1?", "2+2?", "3+3?"].map(|question| {
+            study.insert_card(Flashcard {
+                question: question.to_string(),
+                topics: vec![math],
+                ..Default::default()
+            })
+        });
+        let now = unix_now();
+        study.cards[suspended].suspended = true;
+        study.cards[buried].bury(now);
+        study.cards[flagged].flag = Some(Flag::Red);
+
+        let mut quiz = Quiz::default();
+        quiz.start_quiz(&study);
+        assert_eq!(quiz.cards, [flagged]);
+        let card = study.cards[buried].clone();
+        quiz.submit_card_to_quiz(card, buried, &study);
+        assert_eq!(quiz.cards, [flagged]);
+
+        // Buried cards come back the next day.
+        let tomorrow = (now / SECONDS_PER_DAY + 1) * SECONDS_PER_DAY;
+        assert!(study.cards[buried].is_buried(tomorrow - 1));
+        assert!(study.cards[buried].quizzable(tomorrow));
+
+        assert_eq!(study.filter_cards(Filter::Suspended, now), [suspended]);
+        assert_eq!(study.filter_cards(Filter::Buried, now), [buried]);
+        assert_eq!(study.filter_cards(Filter::Flagged, now), [flagged]);
+        assert_eq!("Red".parse(), Ok(Filter::Flag(Flag::Red)));
+        assert!(study.filter_cards("blue".parse().unwrap(), now).is_empty());
+        assert!("pink".parse::<Flag>().is_err());
+    }
+
+    #[test]
+    fn topic_quizzes_leave_out_suspended_and_buried_cards() {
+        let mut study = Study::default();
+        let math = study.insert_topic(Topic {
+            content: "Math".to_string(),
+            ..Default::default()
+        });
+        let [suspended, buried, asked] = ["1+1?", "2+2?", "3+3?"].map(|question| {
+            study.insert_card(Flashcard {
+                question: question.to_string(),
+                topics: vec![math],
+                ..Default::default()
+            })
+        });
+        study.cards[suspended].suspended = true;
+        study.cards[buried].bury(unix_now());
+
+        let mut quiz = Quiz::default();
+        quiz.start_quiz_on(&study, &[math]);
+        assert_eq!(quiz.cards, [asked]);
+        assert_eq!(quiz.qna_queue.len(), 1);
+    }
+}
@@ -0,0 +1,217 @@
+//! Statistics over the review log, for seeing whether studying is working.
+//!
+//! Days are counted in UTC from the unix epoch, so a day runs from midnight to midnight UTC.
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::quiz::{FlashcardKey, Study, TopicKey, SECONDS_PER_DAY};
+use crate::review::Review;
+
+/// The reviews done on one day.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Day {
+    /// Days since the unix epoch.
+    pub day: u64,
+    pub reviews: usize,
+    pub correct: usize,
+}
+
+impl Day {
+    /// Returns the share of correct answers, or None without reviews.
+    pub fn accuracy(&self) -> Option<f32> {
+        (self.reviews > 0).then(|| self.correct as f32 / self.reviews as f32)
+    }
+}
+
+/// How well the cards of a topic are remembered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicRetention {
+    pub topic: TopicKey,
+    pub reviews: usize,
+    pub correct: usize,
+}
+
+impl TopicRetention {
+    /// Returns the share of correct answers.
+    pub fn retention(&self) -> f32 {
+        self.correct as f32 / self.reviews as f32
+    }
+}
+
+/// The answers given to one card.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardRecord {
+    pub card: FlashcardKey,
+    pub reviews: usize,
+    pub wrong: usize,
+}
+
+/// Returns the reviews of each of the last `days` days, ending with the day of the unix time
+/// `now`. Days without reviews are included.
+pub fn daily(study: &Study, now: u64, days: usize) -> Vec<Day> {
+    let today = now / SECONDS_PER_DAY;
+    let first = (today + 1).saturating_sub(days as u64);
+    let mut result: Vec<Day> = (first..=today)
+        .map(|day| Day {
+            day,
+            ..Default::default()
+        })
+        .collect();
+    for review in &study.reviews {
+        let day = review.time / SECONDS_PER_DAY;
+        if let Some(entry) = day
+            .checked_sub(first)
+            .and_then(|index| result.get_mut(index as usize))
+        {
+            entry.reviews += 1;
+            entry.correct += usize::from(review.correct());
+        }
+    }
+    result
+}
+
+/// Returns the retention of every topic whose cards were reviewed, in tree order. A review counts
+/// for every topic the card is in now.
+pub fn topic_retention(study: &Study) -> Vec<TopicRetention> {
+    let by_card = reviews_by_card(study);
+    study
+        .topic_tree()
+        .into_iter()
+        .filter_map(|(topic, _)| {
+            let mut retention = TopicRetention {
+                topic,
+                reviews: 0,
+                correct: 0,
+            };
+            for card in &study.topics[topic].qna {
+                let Some(reviews) = study
+                    .cards
+                    .get(*card)
+                    .and_then(|card| by_card.get(&card.id))
+                else {
+                    continue;
+                };
+                retention.reviews += reviews.len();
+                retention.correct += reviews.iter().filter(|review| review.correct()).count();
+            }
+            (retention.reviews > 0).then_some(retention)
+        })
+        .collect()
+}
+
+/// Returns up to `count` cards that were answered wrong at least once, the most often wrong
+/// first, then the most often wrong in proportion.
+pub fn hardest_cards(study: &Study, count: usize) -> Vec<CardRecord> {
+    let by_card = reviews_by_card(study);
+    let mut records: Vec<CardRecord> = study
+        .cards
+        .iter()
+        .filter_map(|(key, card)| {
+            let reviews = by_card.get(&card.id)?;
+            let wrong = reviews.iter().filter(|review| !review.correct()).count();
+            (wrong > 0).then_some(CardRecord {
+                card: key,
+                reviews: reviews.len(),
+                wrong,
+            })
+        })
+        .collect();
+    records.sort_by(|a, b| {
+        b.wrong
+            .cmp(&a.wrong)
+            .then((b.wrong * a.reviews).cmp(&(a.wrong * b.reviews)))
+    });
+    records.truncate(count);
+    records
+}
+
+/// Returns the date of a day since the unix epoch as year-month-day.
+pub fn date(day: u64) -> String {
+    // The civil from days algorithm of Howard Hinnant, with years starting in March so the leap
+    // day comes last.
+    let days = day as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day_of_month:02}")
+}
+
+fn reviews_by_card(study: &Study) -> HashMap<Uuid, Vec<&Review>> {
+    let mut by_card: HashMap<Uuid, Vec<&Review>> = HashMap::new();
+    for review in &study.reviews {
+        by_card.entry(review.card).or_default().push(review);
+    }
+    by_card
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quiz::Flashcard;
+    use crate::review::Grade;
+
+    #[test]
+    fn summarises_reviews() {
+        let mut study = Study::default();
+        let math = study.topic_by_name_or_insert("Math");
+        let history = study.topic_by_name_or_insert("History");
+        let easy = study.insert_card(Flashcard {
+            topics: vec![math],
+            ..Default::default()
+        });
+        let hard = study.insert_card(Flashcard {
+            topics: vec![math, history],
+            ..Default::default()
+        });
+        let now = 10 * SECONDS_PER_DAY + 5;
+        let log = [
+            (easy, 9, Grade::Good),
+            (hard, 9, Grade::Again),
+            (hard, 10, Grade::Again),
+        ];
+        for (card, day, grade) in log {
+            study.reviews.push(Review {
+                card: study.cards[card].id,
+                time: day * SECONDS_PER_DAY + 1,
+                answer: String::new(),
+                grade,
+                response_ms: 0,
+            });
+        }
+
+        let days = daily(&study, now, 3);
+        assert_eq!(
+            days.iter().map(|day| day.day).collect::<Vec<_>>(),
+            [8, 9, 10]
+        );
+        assert_eq!(days[1].accuracy(), Some(0.5));
+        assert_eq!((days[2].reviews, days[0].accuracy()), (1, None));
+
+        let retention = topic_retention(&study);
+        assert_eq!(retention.len(), 2);
+        assert_eq!(
+            (retention[0].topic, retention[0].retention()),
+            (math, 1.0 / 3.0)
+        );
+        assert_eq!((retention[1].reviews, retention[1].correct), (2, 0));
+
+        let hardest = hardest_cards(&study, 5);
+        assert_eq!(hardest.len(), 1);
+        assert_eq!((hardest[0].card, hardest[0].wrong), (hard, 2));
+
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(11_016), "2000-02-29");
+        assert_eq!(date(20_745), "2026-10-19");
+    }
+}
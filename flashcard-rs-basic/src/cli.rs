@@ -0,0 +1,1332 @@
+//! Non-interactive subcommands for scripting deck maintenance.
+//!
+//! Every command loads the deck, does its work, saves the deck again if it changed and exits with
+//! one of the codes below. Output meant for other programs goes to stdout, messages for people go
+//! to stderr.
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use flashcard_core::import::{self, ImportError};
+use flashcard_core::order::Order;
+use flashcard_core::query::{Query, QueryError};
+use flashcard_core::quiz::{
+    unix_now, Color, Flashcard, FlashcardKey, Namespace, NamespaceKey, Question, Study, TopicKey,
+    TopicTreeError,
+};
+use flashcard_core::random::Rng;
+use flashcard_core::review::Grade;
+use flashcard_core::search::SearchIndex;
+use flashcard_core::state::{Filter, Flag};
+use flashcard_core::{bundle, deck, forecast, html, pdf, stats};
+use serde::Serialize;
+
+use crate::UserInput;
+
+/// Exit code when a named topic or namespace does not exist.
+pub const EXIT_NOT_FOUND: u8 = 3;
+
+#[derive(Parser, Debug)]
+#[command(about = "Make flashcards and quiz yourself on them")]
+pub struct Cli {
+    /// The deck file to work on. Defaults to the deck shared with the GUI.
+    #[arg(long, global = true)]
+    pub deck: Option<PathBuf>,
+    /// Open the full-screen terminal UI instead of the menu.
+    #[arg(long)]
+    pub tui: bool,
+    /// Without a command an interactive menu is started.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Add a card and print its id.
+    Add {
+        #[arg(long)]
+        question: String,
+        #[arg(long)]
+        answer: String,
+        /// A topic to put the card in, created if it does not exist. Can be repeated.
+        #[arg(long)]
+        topic: Vec<String>,
+    },
+    /// List cards, one per line as tab separated id, question, answer and topics.
+    List {
+        /// Only list cards in this topic. Can be repeated.
+        #[arg(long)]
+        topic: Vec<String>,
+        /// Only list cards that are suspended, buried, leeches, flagged, or have the flag of
+        /// this colour.
+        #[arg(long)]
+        filter: Option<Filter>,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Search the questions, answers and topics of the cards and list the matches like `list`,
+    /// best match first. Words are matched by their stem, as a prefix or with a typo.
+    Search {
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// List at most this many cards.
+        #[arg(long)]
+        limit: Option<usize>,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Add, remove, rename, move or list topics.
+    #[command(subcommand)]
+    Topic(TopicCommand),
+    /// Add, remove, rename, recolour or reorder namespaces, and put topics in them.
+    #[command(subcommand)]
+    Namespace(NamespaceCommand),
+    /// Suspend, bury or flag cards given by id, or clear those states.
+    #[command(subcommand)]
+    Card(CardCommand),
+    /// List leeches, the cards forgotten too often, and choose when cards become leeches.
+    #[command(subcommand)]
+    Leech(LeechCommand),
+    /// Quiz yourself on the cards of some topics, reading answers from stdin. Suspended and buried
+    /// cards are left out.
+    Quiz {
+        /// A topic to quiz on. Can be repeated.
+        #[arg(long, required = true)]
+        topic: Vec<String>,
+        /// Also quiz on the sub-topics of the topics.
+        #[arg(long)]
+        subtopics: bool,
+        /// Show each answer after Enter and read a grade instead of a typed answer: again, hard,
+        /// good or easy, or 1 to 4. An empty grade means good.
+        #[arg(long)]
+        flip: bool,
+        /// The order to ask the cards in: created, shuffled, interleaved (taking turns between
+        /// the topics), hardest or oldest (longest since the last review).
+        #[arg(long, default_value_t = Order::Created)]
+        order: Order,
+        /// The seed to shuffle with, to ask the same order again. Without it a seed is picked and
+        /// shown.
+        #[arg(long)]
+        seed: Option<u64>,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Forecast how many reviews will be due each day, assuming every answer is right.
+    Forecast {
+        /// How many days to look ahead, e.g. 30, 90 or 365.
+        #[arg(long, default_value_t = 30)]
+        days: usize,
+        /// Simulate starting this many new cards every day.
+        #[arg(long, default_value_t = 0)]
+        new_per_day: usize,
+        /// Add the days up by week.
+        #[arg(long)]
+        weekly: bool,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Import a deck, deck bundle, or Mnemosyne or SuperMemo export.
+    Import { path: PathBuf },
+    /// Export cards as a printable PDF, an HTML page, a deck bundle or a deck file.
+    Export(ExportArgs),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TopicCommand {
+    /// Add a topic. Adding a topic that exists already does nothing.
+    Add {
+        name: String,
+        /// The topic to put the new topic under.
+        #[arg(long)]
+        parent: Option<String>,
+        /// Make a smart topic holding the cards that match this query, such as
+        /// `topic:Biology AND lapses>3 AND created:<30d`.
+        #[arg(long)]
+        query: Option<String>,
+    },
+    /// Remove a topic. Its cards are kept and its sub-topics move up a level.
+    Rm { name: String },
+    /// Rename a topic.
+    Rename { name: String, new_name: String },
+    /// Move a topic under another topic, or to the top level without --parent.
+    Move {
+        name: String,
+        #[arg(long)]
+        parent: Option<String>,
+    },
+    /// List the topics as a tree, indenting sub-topics by two spaces. Smart topics are followed
+    /// by a tab and their query.
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CardCommand {
+    /// Leave cards out of quizzes until they are unsuspended.
+    Suspend {
+        #[arg(required = true)]
+        id: Vec<String>,
+    },
+    /// Let suspended cards be quizzed again.
+    Unsuspend {
+        #[arg(required = true)]
+        id: Vec<String>,
+    },
+    /// Leave cards out of quizzes for the rest of the day.
+    Bury {
+        #[arg(required = true)]
+        id: Vec<String>,
+    },
+    /// Let buried cards be quizzed again today.
+    Unbury {
+        #[arg(required = true)]
+        id: Vec<String>,
+    },
+    /// Flag cards red, orange, green, blue or purple.
+    Flag {
+        flag: Flag,
+        #[arg(required = true)]
+        id: Vec<String>,
+    },
+    /// Take the flags off cards.
+    Unflag {
+        #[arg(required = true)]
+        id: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LeechCommand {
+    /// List the leeches like `list`, the most often forgotten first.
+    List {
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Take the leech flag off a card after rewriting it, and unsuspend it.
+    Forgive { id: String },
+    /// Print how many lapses a card may have before it becomes a leech and whether leeches are
+    /// suspended, changing them first when given.
+    Config {
+        #[arg(long)]
+        threshold: Option<u32>,
+        #[arg(long)]
+        suspend: Option<bool>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum NamespaceCommand {
+    /// List namespaces in order, one per line as tab separated name, colour and topics.
+    List,
+    /// Add a namespace. Adding a namespace that exists already does nothing.
+    Add {
+        name: String,
+        /// The colour of the namespace as #rrggbb.
+        #[arg(long)]
+        color: Option<String>,
+    },
+    /// Remove a namespace. Its topics are kept.
+    Rm { name: String },
+    /// Rename a namespace.
+    Rename { name: String, new_name: String },
+    /// Change the colour of a namespace, given as #rrggbb.
+    Color { name: String, color: String },
+    /// Move a namespace to a position in the list, counting from 0.
+    Move { name: String, position: usize },
+    /// Put topics in a namespace. Their other namespaces are kept.
+    Assign {
+        name: String,
+        #[arg(required = true)]
+        topic: Vec<String>,
+    },
+    /// Take topics out of a namespace.
+    Unassign {
+        name: String,
+        #[arg(required = true)]
+        topic: Vec<String>,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    #[arg(long, value_enum)]
+    format: ExportFormat,
+    /// A topic to export. Can be repeated; all cards are exported when left out, though only
+    /// deck files have room for cards without a topic.
+    #[arg(long)]
+    topic: Vec<String>,
+    /// The file to write.
+    output: PathBuf,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Pdf,
+    Html,
+    Bundle,
+    Deck,
+}
+
+/// Why a command failed.
+#[derive(Debug)]
+pub enum CliError {
+    /// A named topic or namespace does not exist.
+    NotFound(String),
+    /// The command cannot be carried out as asked.
+    Invalid(String),
+    Io(io::Error),
+    Import(ImportError),
+}
+
+impl CliError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            CliError::NotFound(_) => ExitCode::from(EXIT_NOT_FOUND),
+            _ => ExitCode::FAILURE,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::NotFound(what) => write!(f, "{what}"),
+            CliError::Invalid(reason) => write!(f, "{reason}"),
+            CliError::Io(error) => write!(f, "{error}"),
+            CliError::Import(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        CliError::Io(error)
+    }
+}
+
+impl From<ImportError> for CliError {
+    fn from(error: ImportError) -> Self {
+        CliError::Import(error)
+    }
+}
+
+impl From<TopicTreeError> for CliError {
+    fn from(error: TopicTreeError) -> Self {
+        CliError::Invalid(error.to_string())
+    }
+}
+
+impl From<QueryError> for CliError {
+    fn from(error: QueryError) -> Self {
+        CliError::Invalid(format!("invalid query, {error}"))
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(error: serde_json::Error) -> Self {
+        CliError::Io(error.into())
+    }
+}
+
+/// Reads answers from stdin, without the blank lines the interactive menu prints.
+pub struct StdinInput;
+
+impl UserInput for StdinInput {
+    fn ask_question(&mut self) -> String {
+        let mut answer = String::new();
+        if let Err(error) = io::stdin().read_line(&mut answer) {
+            eprintln!("Error: {error}");
+        }
+        answer.trim().to_string()
+    }
+}
+
+/// A card as printed by `list --format json`.
+#[derive(Serialize)]
+struct CardOutput<'a> {
+    id: String,
+    question: &'a str,
+    answer: &'a str,
+    topics: Vec<&'a str>,
+}
+
+/// The result of `quiz --format json`.
+#[derive(Serialize, Debug, PartialEq)]
+struct QuizOutput {
+    correct: usize,
+    total: usize,
+}
+
+/// A row of `forecast --format json`, for a day or for the week starting on the date.
+#[derive(Serialize, Debug, PartialEq)]
+struct ForecastRow {
+    date: String,
+    due: usize,
+    new: usize,
+}
+
+/// Runs `command` on the deck at `deck_path` and returns the exit code.
+pub fn run(command: Command, deck_path: &Path, input: &mut impl UserInput) -> ExitCode {
+    let result = deck::load_or_default(deck_path)
+        .map_err(CliError::from)
+        .and_then(|mut study| {
+            let changed = execute(command, &mut study, input, &mut io::stdout().lock())?;
+            if changed {
+                deck::save_deck(&study, deck_path)?;
+            }
+            Ok(())
+        });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {error}");
+            error.exit_code()
+        }
+    }
+}
+
+/// Runs `command` on `study`, writing its output to `out`, and returns whether the deck changed.
+pub fn execute(
+    command: Command,
+    study: &mut Study,
+    input: &mut impl UserInput,
+    out: &mut impl Write,
+) -> Result<bool, CliError> {
+    match command {
+        Command::Add {
+            question,
+            answer,
+            topic,
+        } => {
+            let topics = topic
+                .iter()
+                .map(|name| study.topic_by_name_or_insert(name))
+                .collect();
+            let key = study.insert_card(Flashcard {
+                question,
+                answer,
+                topics,
+                ..Default::default()
+            });
+            writeln!(out, "{}", study.cards[key].id)?;
+            Ok(true)
+        }
+        Command::List {
+            topic,
+            filter,
+            format,
+        } => {
+            let mut keys: Vec<FlashcardKey> = if topic.is_empty() {
+                study.cards.keys().collect()
+            } else {
+                study.cards_in_topics(&find_topics(study, &topic)?)
+            };
+            if let Some(filter) = filter {
+                let now = unix_now();
+                keys.retain(|&key| filter.matches(&study.cards[key], now));
+            }
+            write_cards(study, keys, format, out)?;
+            Ok(false)
+        }
+        Command::Search {
+            query,
+            limit,
+            format,
+        } => {
+            let hits = SearchIndex::new(study).search(&query.join(" "));
+            let keys = hits
+                .into_iter()
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|hit| hit.card)
+                .collect();
+            write_cards(study, keys, format, out)?;
+            Ok(false)
+        }
+        Command::Topic(TopicCommand::Add {
+            name,
+            parent,
+            query,
+        }) => {
+            if study.topic_by_name(&name).is_some() {
+                return Ok(false);
+            }
+            let parent = parent
+                .map(|parent| find_topic(study, &parent))
+                .transpose()?;
+            if let Some(query) = &query {
+                Query::parse(query)?;
+            }
+            let key = study.topic_by_name_or_insert(&name);
+            study.topics[key].parent = parent;
+            study.topics[key].query = query;
+            Ok(true)
+        }
+        Command::Topic(TopicCommand::Rm { name }) => {
+            let key = find_topic(study, &name)?;
+            study.remove_topic(key);
+            Ok(true)
+        }
+        Command::Topic(TopicCommand::Rename { name, new_name }) => {
+            let key = find_topic(study, &name)?;
+            if study
+                .topic_by_name(&new_name)
+                .is_some_and(|other| other != key)
+            {
+                return Err(CliError::Invalid(format!(
+                    "a topic called {new_name} exists already"
+                )));
+            }
+            study.topics[key].content = new_name.trim().to_string();
+            Ok(true)
+        }
+        Command::Namespace(command) => execute_namespace(command, study, out),
+        Command::Topic(TopicCommand::Move { name, parent }) => {
+            let key = find_topic(study, &name)?;
+            let parent = parent
+                .map(|parent| find_topic(study, &parent))
+                .transpose()?;
+            study.set_parent(key, parent)?;
+            Ok(true)
+        }
+        Command::Topic(TopicCommand::List) => {
+            for (key, depth) in study.topic_tree() {
+                let topic = &study.topics[key];
+                write!(out, "{}{}", "  ".repeat(depth), topic.content)?;
+                match &topic.query {
+                    Some(query) => writeln!(out, "\t{query}")?,
+                    None => writeln!(out)?,
+                }
+            }
+            Ok(false)
+        }
+        Command::Card(command) => {
+            let now = unix_now();
+            let (CardCommand::Suspend { id }
+            | CardCommand::Unsuspend { id }
+            | CardCommand::Bury { id }
+            | CardCommand::Unbury { id }
+            | CardCommand::Flag { id, .. }
+            | CardCommand::Unflag { id }) = &command;
+            let keys = id
+                .iter()
+                .map(|id| find_card(study, id))
+                .collect::<Result<Vec<_>, _>>()?;
+            for key in keys {
+                let card = &mut study.cards[key];
+                match &command {
+                    CardCommand::Suspend { .. } => card.suspended = true,
+                    CardCommand::Unsuspend { .. } => card.suspended = false,
+                    CardCommand::Bury { .. } => card.bury(now),
+                    CardCommand::Unbury { .. } => card.buried_until = None,
+                    CardCommand::Flag { flag, .. } => card.flag = Some(*flag),
+                    CardCommand::Unflag { .. } => card.flag = None,
+                }
+            }
+            Ok(true)
+        }
+        Command::Leech(LeechCommand::List { format }) => {
+            write_cards(study, study.leeches(), format, out)?;
+            Ok(false)
+        }
+        Command::Leech(LeechCommand::Forgive { id }) => {
+            let card = find_card(study, &id)?;
+            study.forgive_leech(card);
+            Ok(true)
+        }
+        Command::Leech(LeechCommand::Config { threshold, suspend }) => {
+            let settings = &mut study.leech_settings;
+            if let Some(threshold) = threshold {
+                settings.threshold = threshold;
+            }
+            if let Some(suspend) = suspend {
+                settings.suspend = suspend;
+            }
+            writeln!(
+                out,
+                "threshold {}\tsuspend {}",
+                settings.threshold, settings.suspend
+            )?;
+            Ok(threshold.is_some() || suspend.is_some())
+        }
+        Command::Quiz {
+            topic,
+            subtopics,
+            flip,
+            order,
+            seed,
+            format,
+        } => {
+            let mut topics = find_topics(study, &topic)?;
+            if subtopics {
+                topics = study.with_descendants(&topics);
+            }
+            let mut keys = study.cards_in_topics(&topics);
+            let now = unix_now();
+            keys.retain(|&key| study.cards[key].quizzable(now));
+            let seed = seed.unwrap_or_else(|| {
+                let seed = Rng::time_seed();
+                if order == Order::Shuffled {
+                    eprintln!("Shuffled with seed {seed}");
+                }
+                seed
+            });
+            let keys = study.order_cards(keys, order, seed);
+            let mut result = QuizOutput {
+                correct: 0,
+                total: keys.len(),
+            };
+            // Every answer is logged, so the deck is saved when there was a question.
+            for key in keys {
+                let card = &study.cards[key];
+                eprintln!("{}", card.question);
+                let shown = Instant::now();
+                let answer = input.ask_question();
+                let response_ms = shown.elapsed().as_millis() as u64;
+                let (answer, grade) = if flip {
+                    eprintln!("{}", card.answer);
+                    (String::new(), ask_grade(input))
+                } else {
+                    let correct = Question::from(card).check(&answer);
+                    if correct {
+                        eprintln!("Correct!");
+                    } else {
+                        eprintln!("Wrong, the answer is {}", card.answer);
+                    }
+                    (answer, Grade::from_correct(correct))
+                };
+                if grade != Grade::Again {
+                    result.correct += 1;
+                }
+                if study.record_review(key, answer, grade, response_ms) {
+                    eprintln!("This card is now a leech, rewrite it");
+                }
+            }
+            match format {
+                Format::Json => writeln!(out, "{}", serde_json::to_string(&result)?)?,
+                Format::Text => writeln!(out, "{}/{}", result.correct, result.total)?,
+            }
+            Ok(result.total > 0)
+        }
+        Command::Forecast {
+            days,
+            new_per_day,
+            weekly,
+            format,
+        } => {
+            let forecast = forecast::forecast(study, unix_now(), days, new_per_day);
+            let rows: Vec<ForecastRow> = forecast
+                .chunks(if weekly { 7 } else { 1 })
+                .map(|chunk| ForecastRow {
+                    date: stats::date(chunk[0].day),
+                    due: chunk.iter().map(|day| day.due).sum(),
+                    new: chunk.iter().map(|day| day.new).sum(),
+                })
+                .collect();
+            match format {
+                Format::Json => writeln!(out, "{}", serde_json::to_string(&rows)?)?,
+                Format::Text => {
+                    writeln!(
+                        out,
+                        "{:<10} {:>6} {:>6} {:>6}",
+                        "date", "due", "new", "total"
+                    )?;
+                    for row in &rows {
+                        writeln!(
+                            out,
+                            "{:<10} {:>6} {:>6} {:>6}",
+                            row.date,
+                            row.due,
+                            row.new,
+                            row.due + row.new
+                        )?;
+                    }
+                    let total: usize = rows.iter().map(|row| row.due + row.new).sum();
+                    writeln!(out, "{total} reviews in {days} days")?;
+                }
+            }
+            Ok(false)
+        }
+        Command::Import { path } => {
+            let summary = import::import_file(study, &path)?;
+            writeln!(out, "{summary}")?;
+            Ok(summary.cards > 0 || summary.topics > 0)
+        }
+        Command::Export(ExportArgs {
+            format,
+            topic,
+            output,
+        }) => {
+            let everything = topic.is_empty();
+            let topics = if everything {
+                study.topics.keys().collect()
+            } else {
+                find_topics(study, &topic)?
+            };
+            match format {
+                ExportFormat::Pdf => pdf::write_pdf(study, &topics, &output)?,
+                ExportFormat::Html => html::write_html(study, &topics, &output)?,
+                ExportFormat::Bundle => bundle::write_bundle(study, &topics, &output)?,
+                ExportFormat::Deck => {
+                    let cards = if everything {
+                        study.cards.keys().collect()
+                    } else {
+                        study.cards_in_topics(&topics)
+                    };
+                    let mut exported = Study::default();
+                    exported.merge_from(study, &topics, &cards);
+                    deck::save_deck(&exported, &output)?;
+                }
+            }
+            let left_out = study
+                .cards
+                .values()
+                .filter(|card| card.topics.is_empty())
+                .count();
+            if everything && format != ExportFormat::Deck && left_out > 0 {
+                eprintln!("Left out {left_out} cards without a topic");
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// Runs a `namespace` subcommand and returns whether the deck changed.
+fn execute_namespace(
+    command: NamespaceCommand,
+    study: &mut Study,
+    out: &mut impl Write,
+) -> Result<bool, CliError> {
+    match command {
+        NamespaceCommand::List => {
+            for key in study.ordered_namespaces() {
+                let namespace = &study.namespaces[key];
+                let topics: Vec<&str> = study
+                    .topics_in_namespace(key)
+                    .into_iter()
+                    .map(|topic| study.topics[topic].content.as_str())
+                    .collect();
+                writeln!(
+                    out,
+                    "{}\t{}\t{}",
+                    namespace.name,
+                    namespace.color.to_hex(),
+                    topics.join(",")
+                )?;
+            }
+            Ok(false)
+        }
+        NamespaceCommand::Add { name, color } => {
+            if study.namespace_by_name(&name).is_some() {
+                return Ok(false);
+            }
+            let color = match color {
+                Some(color) => parse_color(&color)?,
+                None => Color::WHITE,
+            };
+            study.insert_namespace(Namespace {
+                name: name.trim().to_string(),
+                color,
+                ..Default::default()
+            });
+            Ok(true)
+        }
+        NamespaceCommand::Rm { name } => {
+            let key = find_namespace(study, &name)?;
+            study.remove_namespace(key);
+            Ok(true)
+        }
+        NamespaceCommand::Rename { name, new_name } => {
+            let key = find_namespace(study, &name)?;
+            if study
+                .namespace_by_name(&new_name)
+                .is_some_and(|other| other != key)
+            {
+                return Err(CliError::Invalid(format!(
+                    "a namespace called {new_name} exists already"
+                )));
+            }
+            study.namespaces[key].name = new_name.trim().to_string();
+            Ok(true)
+        }
+        NamespaceCommand::Color { name, color } => {
+            let key = find_namespace(study, &name)?;
+            study.namespaces[key].color = parse_color(&color)?;
+            Ok(true)
+        }
+        NamespaceCommand::Move { name, position } => {
+            let key = find_namespace(study, &name)?;
+            study.move_namespace(key, position);
+            Ok(true)
+        }
+        NamespaceCommand::Assign { name, topic } => {
+            let key = find_namespace(study, &name)?;
+            for topic in find_topics(study, &topic)? {
+                study.add_to_namespace(topic, key);
+            }
+            Ok(true)
+        }
+        NamespaceCommand::Unassign { name, topic } => {
+            let key = find_namespace(study, &name)?;
+            for topic in find_topics(study, &topic)? {
+                study.remove_from_namespace(topic, key);
+            }
+            Ok(true)
+        }
+    }
+}
+
+// Writes cards as `list` prints them.
+fn write_cards(
+    study: &Study,
+    keys: Vec<FlashcardKey>,
+    format: Format,
+    out: &mut impl Write,
+) -> Result<(), CliError> {
+    let cards = keys.into_iter().map(|key| {
+        let card = &study.cards[key];
+        CardOutput {
+            id: card.id.to_string(),
+            question: &card.question,
+            answer: &card.answer,
+            topics: card
+                .topics
+                .iter()
+                .filter_map(|&topic| study.topics.get(topic))
+                .map(|topic| topic.content.as_str())
+                .collect(),
+        }
+    });
+    match format {
+        Format::Json => {
+            let cards: Vec<CardOutput> = cards.collect();
+            serde_json::to_writer_pretty(&mut *out, &cards)?;
+            writeln!(out)?;
+        }
+        Format::Text => {
+            for card in cards {
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}",
+                    card.id,
+                    one_line(card.question),
+                    one_line(card.answer),
+                    card.topics.join(",")
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn find_topic(study: &Study, name: &str) -> Result<TopicKey, CliError> {
+    study
+        .topic_by_name(name)
+        .ok_or_else(|| CliError::NotFound(format!("no topic called {name}")))
+}
+
+fn find_namespace(study: &Study, name: &str) -> Result<NamespaceKey, CliError> {
+    study
+        .namespace_by_name(name)
+        .ok_or_else(|| CliError::NotFound(format!("no namespace called {name}")))
+}
+
+fn parse_color(text: &str) -> Result<Color, CliError> {
+    Color::from_hex(text)
+        .ok_or_else(|| CliError::Invalid(format!("{text} is not a colour like #rrggbb")))
+}
+
+// Asks for a grade until one is given.
+fn ask_grade(input: &mut impl UserInput) -> Grade {
+    loop {
+        eprintln!("Again, hard, good or easy? (1-4, empty for good)");
+        let answer = input.ask_question();
+        if answer.trim().is_empty() {
+            return Grade::Good;
+        }
+        match answer.parse() {
+            Ok(grade) => return grade,
+            Err(error) => eprintln!("{error}"),
+        }
+    }
+}
+
+fn find_card(study: &Study, id: &str) -> Result<FlashcardKey, CliError> {
+    study
+        .cards
+        .iter()
+        .find(|(_, card)| card.id.to_string() == id.trim())
+        .map(|(key, _)| key)
+        .ok_or_else(|| CliError::NotFound(format!("no card with id {id}")))
+}
+
+fn find_topics(study: &Study, names: &[String]) -> Result<Vec<TopicKey>, CliError> {
+    names.iter().map(|name| find_topic(study, name)).collect()
+}
+
+// Keeps multi-line card text on one line of tab separated output.
+fn one_line(text: &str) -> String {
+    text.replace(['\t', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::MockInput;
+
+    fn execute_args(study: &mut Study, args: &[&str], answers: Vec<&str>) -> (bool, String) {
+        let cli = Cli::try_parse_from(["flashcard-rs-basic"].iter().chain(args)).unwrap();
+        let mut out = vec![];
+        let changed = execute(
+            cli.command.unwrap(),
+            study,
+            &mut MockInput::new(answers),
+            &mut out,
+        )
+        .unwrap();
+        (changed, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn adds_and_lists_cards() {
+        let mut study = Study::default();
+        let args = [
+            "add",
+            "--question",
+            "2+2?",
+            "--answer",
+            "4",
+            "--topic",
+            "Math",
+        ];
+        let (changed, id) = execute_args(&mut study, &args, vec![]);
+        assert!(changed);
+
+        let (changed, json) = execute_args(
+            &mut study,
+            &["list", "--topic", "math", "--format", "json"],
+            vec![],
+        );
+        assert!(!changed);
+        let cards: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(cards[0]["id"], id.trim());
+        assert_eq!(cards[0]["topics"][0], "Math");
+
+        let (_, text) = execute_args(&mut study, &["list"], vec![]);
+        assert_eq!(text, format!("{}\t2+2?\t4\tMath\n", id.trim()));
+    }
+
+    #[test]
+    fn searches_cards() {
+        let mut study = Study::default();
+        for (question, answer) in [
+            ("What is the powerhouse of the cell?", "Mitochondria"),
+            ("What surrounds cells?", "The cell membrane"),
+            ("Capital of France?", "Paris"),
+        ] {
+            let args = ["add", "--question", question, "--answer", answer];
+            execute_args(&mut study, &args, vec![]);
+        }
+        let (changed, text) = execute_args(&mut study, &["search", "Cells"], vec![]);
+        assert!(!changed);
+        let questions: Vec<&str> = text
+            .lines()
+            .map(|line| line.split('\t').nth(1).unwrap())
+            .collect();
+        assert_eq!(
+            questions,
+            [
+                "What surrounds cells?",
+                "What is the powerhouse of the cell?"
+            ]
+        );
+        let (_, text) = execute_args(&mut study, &["search", "cell", "--limit", "1"], vec![]);
+        assert_eq!(text.lines().count(), 1);
+        let (_, text) = execute_args(&mut study, &["search", "pari"], vec![]);
+        assert!(text.contains("Capital of France?"));
+    }
+
+    #[test]
+    fn manages_topics() {
+        let mut study = Study::default();
+        execute_args(
+            &mut study,
+            &["add", "--question", "Q", "--answer", "A", "--topic", "Old"],
+            vec![],
+        );
+        execute_args(&mut study, &["topic", "rename", "old", "New"], vec![]);
+        assert!(study.topic_by_name("New").is_some());
+
+        let cli = Cli::try_parse_from(["flashcard-rs-basic", "topic", "rm", "Old"]).unwrap();
+        let error = execute(
+            cli.command.unwrap(),
+            &mut study,
+            &mut MockInput::new(vec![]),
+            &mut vec![],
+        )
+        .unwrap_err();
+        assert!(matches!(error, CliError::NotFound(_)));
+
+        execute_args(&mut study, &["topic", "rm", "New"], vec![]);
+        assert!(study.topics.is_empty());
+        assert!(study.cards.values().all(|card| card.topics.is_empty()));
+    }
+
+    #[test]
+    fn manages_the_topic_tree() {
+        let mut study = Study::default();
+        execute_args(&mut study, &["topic", "add", "Biology"], vec![]);
+        execute_args(
+            &mut study,
+            &["topic", "add", "Cell", "--parent", "biology"],
+            vec![],
+        );
+        execute_args(&mut study, &["topic", "add", "Organelles"], vec![]);
+        execute_args(
+            &mut study,
+            &["topic", "move", "Organelles", "--parent", "Cell"],
+            vec![],
+        );
+        let (_, tree) = execute_args(&mut study, &["topic", "list"], vec![]);
+        assert_eq!(tree, "Biology\n  Cell\n    Organelles\n");
+
+        let cli = Cli::try_parse_from([
+            "flashcard-rs-basic",
+            "topic",
+            "move",
+            "Biology",
+            "--parent",
+            "Organelles",
+        ])
+        .unwrap();
+        let error = execute(
+            cli.command.unwrap(),
+            &mut study,
+            &mut MockInput::new(vec![]),
+            &mut vec![],
+        )
+        .unwrap_err();
+        assert!(matches!(error, CliError::Invalid(_)));
+
+        execute_args(
+            &mut study,
+            &["add", "--question", "Q", "--answer", "A", "--topic", "Cell"],
+            vec![],
+        );
+        let (_, output) = execute_args(
+            &mut study,
+            &["quiz", "--topic", "Biology", "--subtopics"],
+            vec!["A"],
+        );
+        assert_eq!(output, "1/1\n");
+    }
+
+    #[test]
+    fn makes_smart_topics() {
+        let mut study = Study::default();
+        for (question, topic) in [("Q1", "Biology"), ("Q2", "Biology"), ("Q3", "Done")] {
+            let args = [
+                "add",
+                "--question",
+                question,
+                "--answer",
+                "A",
+                "--topic",
+                topic,
+            ];
+            execute_args(&mut study, &args, vec![]);
+        }
+        let done = study.topic_by_name("Done").unwrap();
+        let q2 = study.cards.iter().find(|(_, card)| card.question == "Q2");
+        study.link(q2.unwrap().0, done);
+
+        let query = "topic:Biology AND NOT topic:Done";
+        let args = ["topic", "add", "Left", "--query", query];
+        let (changed, _) = execute_args(&mut study, &args, vec![]);
+        assert!(changed);
+        let (_, tree) = execute_args(&mut study, &["topic", "list"], vec![]);
+        assert!(tree.contains(&format!("Left\t{query}\n")));
+        let (_, list) = execute_args(&mut study, &["list", "--topic", "left"], vec![]);
+        assert_eq!(list.lines().count(), 1);
+        assert!(list.contains("\tQ1\t"));
+
+        let cli = Cli::try_parse_from([
+            "flashcard-rs-basic",
+            "topic",
+            "add",
+            "Broken",
+            "--query",
+            "(topic:Biology",
+        ])
+        .unwrap();
+        let error = execute(
+            cli.command.unwrap(),
+            &mut study,
+            &mut MockInput::new(vec![]),
+            &mut vec![],
+        )
+        .unwrap_err();
+        assert!(matches!(error, CliError::Invalid(_)));
+    }
+
+    #[test]
+    fn manages_namespaces() {
+        let mut study = Study::default();
+        for topic in ["Algebra", "Biology"] {
+            execute_args(&mut study, &["topic", "add", topic], vec![]);
+        }
+        execute_args(&mut study, &["namespace", "add", "Semester 1"], vec![]);
+        execute_args(
+            &mut study,
+            &["namespace", "add", "Exam prep", "--color", "#336699"],
+            vec![],
+        );
+        execute_args(&mut study, &["namespace", "move", "exam prep", "0"], vec![]);
+        execute_args(
+            &mut study,
+            &["namespace", "assign", "Semester 1", "Algebra", "Biology"],
+            vec![],
+        );
+        execute_args(
+            &mut study,
+            &["namespace", "assign", "Exam prep", "Algebra"],
+            vec![],
+        );
+        execute_args(
+            &mut study,
+            &["namespace", "unassign", "Semester 1", "Algebra"],
+            vec![],
+        );
+
+        let (changed, text) = execute_args(&mut study, &["namespace", "list"], vec![]);
+        assert!(!changed);
+        assert_eq!(
+            text,
+            "Exam prep\t#336699\tAlgebra\nSemester 1\t#ffffff\tBiology\n"
+        );
+
+        let cli = Cli::try_parse_from(["flashcard-rs-basic", "namespace", "rm", "Other"]).unwrap();
+        let error = execute(
+            cli.command.unwrap(),
+            &mut study,
+            &mut MockInput::new(vec![]),
+            &mut vec![],
+        )
+        .unwrap_err();
+        assert!(matches!(error, CliError::NotFound(_)));
+    }
+
+    #[test]
+    fn quizzes_on_a_topic() {
+        let mut study = Study::default();
+        for (question, answer) in [("1+1?", "2"), ("2+2?", "4")] {
+            execute_args(
+                &mut study,
+                &[
+                    "add",
+                    "--question",
+                    question,
+                    "--answer",
+                    answer,
+                    "--topic",
+                    "Math",
+                ],
+                vec![],
+            );
+        }
+        let (changed, output) = execute_args(
+            &mut study,
+            &["quiz", "--topic", "Math", "--format", "json"],
+            vec!["2", "5"],
+        );
+        assert_eq!(output, "{\"correct\":1,\"total\":2}\n");
+        // The answers are logged, so the deck has to be saved.
+        assert!(changed);
+        let answers: Vec<(&str, bool)> = study
+            .reviews
+            .iter()
+            .map(|review| (review.answer.as_str(), review.correct()))
+            .collect();
+        assert_eq!(answers, [("2", true), ("5", false)]);
+
+        // Flipped cards are graded by hand, with Enter to reveal the answer.
+        let (_, output) = execute_args(
+            &mut study,
+            &["quiz", "--topic", "Math", "--flip"],
+            vec!["", "ok", "easy", "", "1"],
+        );
+        assert_eq!(output, "1/2\n");
+        let grades: Vec<Grade> = study.reviews[2..]
+            .iter()
+            .map(|review| review.grade)
+            .collect();
+        assert_eq!(grades, [Grade::Easy, Grade::Again]);
+
+        // 2+2 was forgotten twice, so it is the hardest.
+        let (_, output) = execute_args(
+            &mut study,
+            &["quiz", "--topic", "Math", "--order", "hardest"],
+            vec!["4", "2"],
+        );
+        assert_eq!(output, "2/2\n");
+    }
+
+    #[test]
+    fn suspends_buries_and_flags_cards() {
+        let mut study = Study::default();
+        for question in ["1+1?", "2+2?", "3+3?"] {
+            execute_args(
+                &mut study,
+                &[
+                    "add",
+                    "--question",
+                    question,
+                    "--answer",
+                    "2",
+                    "--topic",
+                    "Math",
+                ],
+                vec![],
+            );
+        }
+        let (_, output) = execute_args(&mut study, &["list"], vec![]);
+        let ids: Vec<String> = output
+            .lines()
+            .map(|line| line.split('\t').next().unwrap().to_string())
+            .collect();
+        execute_args(&mut study, &["card", "suspend", &ids[0]], vec![]);
+        execute_args(&mut study, &["card", "bury", &ids[1]], vec![]);
+        execute_args(
+            &mut study,
+            &["card", "flag", "red", &ids[1], &ids[2]],
+            vec![],
+        );
+
+        let (_, output) = execute_args(&mut study, &["quiz", "--topic", "Math"], vec!["2"]);
+        assert_eq!(output, "1/1\n");
+        let (_, output) = execute_args(&mut study, &["list", "--filter", "red"], vec![]);
+        assert_eq!(output.lines().count(), 2);
+        let (_, output) = execute_args(&mut study, &["list", "--filter", "suspended"], vec![]);
+        assert!(output.starts_with(&ids[0]));
+
+        execute_args(&mut study, &["card", "unsuspend", &ids[0]], vec![]);
+        execute_args(&mut study, &["card", "unbury", &ids[1]], vec![]);
+        execute_args(&mut study, &["card", "unflag", &ids[1]], vec![]);
+        let (_, output) = execute_args(&mut study, &["list", "--filter", "flagged"], vec![]);
+        assert!(output.starts_with(&ids[2]) && output.lines().count() == 1);
+        let cli = Cli::try_parse_from(["flashcard-rs-basic", "card", "flag", "pink", &ids[0]]);
+        assert!(cli.is_err());
+    }
+
+    #[test]
+    fn lists_and_forgives_leeches() {
+        let mut study = Study::default();
+        execute_args(
+            &mut study,
+            &["leech", "config", "--threshold", "1", "--suspend", "true"],
+            vec![],
+        );
+        for question in ["1+1?", "2+2?"] {
+            execute_args(
+                &mut study,
+                &[
+                    "add",
+                    "--question",
+                    question,
+                    "--answer",
+                    "2",
+                    "--topic",
+                    "Math",
+                ],
+                vec![],
+            );
+        }
+        // A card becomes a leech when it is forgotten more often than the threshold.
+        execute_args(&mut study, &["quiz", "--topic", "Math"], vec!["2", "5"]);
+        let (_, output) = execute_args(&mut study, &["leech", "list"], vec![]);
+        assert!(output.is_empty());
+        let (_, output) = execute_args(
+            &mut study,
+            &["quiz", "--topic", "Math", "--format", "json"],
+            vec!["2", "5"],
+        );
+        assert_eq!(output, "{\"correct\":1,\"total\":2}\n");
+        let (_, output) = execute_args(&mut study, &["leech", "list"], vec![]);
+        let id = output.split('\t').next().unwrap().to_string();
+        assert!(output.contains("2+2?") && !output.contains("1+1?"));
+
+        // The suspended leech is left out of the next quiz.
+        let (_, output) = execute_args(&mut study, &["quiz", "--topic", "Math"], vec!["2"]);
+        assert_eq!(output, "1/1\n");
+
+        let (changed, _) = execute_args(&mut study, &["leech", "forgive", &id], vec![]);
+        assert!(changed);
+        let (_, output) = execute_args(&mut study, &["leech", "list"], vec![]);
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn forecasts_reviews() {
+        let mut study = Study::default();
+        let (_, output) = execute_args(
+            &mut study,
+            &["forecast", "--days", "14", "--new-per-day", "1"],
+            vec![],
+        );
+        assert_eq!(output.lines().count(), 16);
+        assert!(output.ends_with("34 reviews in 14 days\n"));
+
+        // A new card comes back the next day, so six of the first week's cards are due again in
+        // the same week.
+        let (changed, output) = execute_args(
+            &mut study,
+            &[
+                "forecast",
+                "--days",
+                "7",
+                "--new-per-day",
+                "1",
+                "--weekly",
+                "--format",
+                "json",
+            ],
+            vec![],
+        );
+        assert!(!changed);
+        assert!(output.ends_with("\"due\":6,\"new\":7}]\n"));
+    }
+
+    #[test]
+    fn exports_cards_without_a_topic_to_decks() {
+        let mut study = Study::default();
+        let args = ["add", "--question", "1+1?", "--answer", "2"];
+        execute_args(&mut study, &args, vec![]);
+        let args = [
+            "add",
+            "--question",
+            "2+2?",
+            "--answer",
+            "4",
+            "--topic",
+            "Math",
+        ];
+        execute_args(&mut study, &args, vec![]);
+        let path =
+            std::env::temp_dir().join(format!("flashcard-export-{}.json", std::process::id()));
+        let output = path.to_str().unwrap();
+
+        execute_args(&mut study, &["export", "--format", "deck", output], vec![]);
+        assert_eq!(deck::load_deck(&path).unwrap().cards.len(), 2);
+        let args = ["export", "--format", "deck", "--topic", "Math", output];
+        execute_args(&mut study, &args, vec![]);
+        assert_eq!(deck::load_deck(&path).unwrap().cards.len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
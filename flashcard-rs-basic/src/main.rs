@@ -1,28 +1,30 @@
-mod quiz;
-use std::{collections::VecDeque, fmt::Error, io::{stdin, stdout, Write}, iter::FilterMap};
-use quiz::{Flashcard, FlashcardKey, Quiz, Topic, TopicKey, TopicTag};
-use slotmap::{new_key_type, SlotMap};
-use strum::IntoEnumIterator;
-// use strum_macros::EnumIter;
-// use quiz::QuestionKey'
-
-fn ask_question() -> String {
-    let mut awnser = String::new();
-    stdout().flush().unwrap();
-    if let Err(error) = stdin().read_line(&mut awnser) {
-        println!("Error: {error}");
-    }
-    println!("\n");
-
-    awnser.trim().to_string()
-}
+mod cli;
+mod tui;
 
+use std::io::{stdin, stdout, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Instant;
 
+use clap::Parser;
+use cli::Cli;
+use flashcard_core::auto::{self, Rule, Suggestion};
+use flashcard_core::exam::{self, Exam, TimeLimit};
+use flashcard_core::history::{History, Snapshot};
+use flashcard_core::order::Order;
+use flashcard_core::quiz::{
+    unix_now, Color, Flashcard, FlashcardKey, Namespace, NamespaceKey, NamespaceOperation,
+    Question, Quiz, Study, Topic, TopicKey,
+};
+use flashcard_core::random::Rng;
+use flashcard_core::review::Grade;
+use flashcard_core::{bundle, deck, html, pdf};
 
 #[derive(PartialEq, Debug)]
 enum Operations {
     NamespaceOperate,
     NamepaceChange,
+    Reparent,
     Make,
     Edit,
     List,
@@ -31,8 +33,29 @@ enum Operations {
     Delete,
     Add,
     Test,
-    None
+    Undo,
+    Redo,
+    None,
+}
+
+impl Operations {
+    // The name an operation is recorded under in the undo history.
+    fn label(&self) -> &'static str {
+        match self {
+            Operations::NamespaceOperate => "namespace operation",
+            Operations::NamepaceChange => "namespace change",
+            Operations::Reparent => "topic move",
+            Operations::Make => "new flashcard",
+            Operations::Edit => "flashcard edit",
+            Operations::Topic => "new topic",
+            Operations::Auto => "topic assignment",
+            Operations::Delete => "topic deletion",
+            Operations::Add => "test topics",
+            _ => "change",
+        }
+    }
 }
+
 trait UserInput {
     fn ask_question(&mut self) -> String;
 }
@@ -52,322 +75,687 @@ impl UserInput for RealInput {
     }
 }
 
+// The cards and topics live in a study session shared with the GUI. When a deck file is set the
+// session is saved to it after every operation.
 struct App<I: UserInput> {
     input: I,
-    cards: SlotMap<FlashcardKey, Flashcard>,
-    topics: SlotMap<TopicKey, Topic>,
-    current_topics: Vec<TopicKey>,
-    quiz: Quiz
+    study: Study,
+    quiz: Quiz,
+    deck: Option<PathBuf>,
+    history: History,
 }
-impl Default for App<RealInput> { 
-    fn default() -> Self {
+
+impl App<RealInput> {
+    // Opens the deck at `deck`, starting with an empty one if it does not exist yet.
+    fn new(deck: PathBuf) -> Self {
+        let study = deck::load_or_default(&deck).unwrap_or_else(|error| {
+            println!("Could not open {}: {error}", deck.display());
+            Study::default()
+        });
         Self {
             input: RealInput,
-            ..Default::default()
+            study,
+            quiz: Quiz::default(),
+            deck: Some(deck),
+            history: History::default(),
         }
     }
 }
 
-impl App<RealInput> { 
-fn new() -> Self {
-    Self {
-        input: RealInput,
-        cards: SlotMap::with_key(),
-        topics: SlotMap::with_key(),
-        quiz: Quiz::default(),
-        current_topics: vec![],
-        
+impl<I: UserInput> App<I> {
+    // Returns the keys of the topics named in a comma separated list.
+    fn topics_from_list(&self, topic_string: &str) -> Vec<TopicKey> {
+        let mut final_topic_keys: Vec<TopicKey> = vec![];
+        for listed_topic in topic_string.split(",") {
+            for (key, topic) in &self.study.topics {
+                if topic.content.trim().to_lowercase() == listed_topic.trim().to_lowercase() {
+                    final_topic_keys.push(key);
+                }
+            }
+        }
+        final_topic_keys
     }
-}
-}
 
-impl<I: UserInput> App<I> {
-    fn operate(&mut self, operation: Operations){
+    // The changes below are shared by the menu operations and the terminal UI. They do not print,
+    // so they can run while the terminal UI owns the screen.
+
+    // Adds a card to the topics named in a comma separated list.
+    fn make_card(&mut self, question: String, answer: String, topic_string: &str) -> FlashcardKey {
+        let topics = self.topics_from_list(topic_string);
+        self.study.insert_card(Flashcard {
+            question,
+            answer,
+            topics,
+            ..Default::default()
+        })
+    }
+
+    // Adds a card to the topics named in a comma separated list, on top of its current topics.
+    fn add_card_topics(&mut self, card_key: FlashcardKey, topic_string: &str) {
+        for key in self.topics_from_list(topic_string) {
+            self.study.link(card_key, key);
+        }
+    }
+
+    // Takes a card out of the topic called `name`.
+    fn remove_card_topic(&mut self, card_key: FlashcardKey, name: &str) {
+        if let Some(topic_key) = self.study.topic_by_name(name) {
+            self.study.unlink(card_key, topic_key);
+        }
+    }
+
+    // Moves a card to exactly the topics named in a comma separated list.
+    fn set_card_topics(&mut self, card_key: FlashcardKey, topic_string: &str) {
+        let topics = self.topics_from_list(topic_string);
+        self.study.set_card_topics(card_key, &topics);
+    }
+
+    fn create_topic(&mut self, name: String) -> TopicKey {
+        self.study.insert_topic(Topic {
+            content: name,
+            ..Default::default()
+        })
+    }
+
+    fn delete_topic(&mut self, name: &str) {
+        if let Some(key) = self.study.topic_by_name(name) {
+            self.study.remove_topic(key);
+        }
+    }
+
+    // Adds the cards of the topics named in a comma separated list to the test, with the cards of
+    // their sub-topics when those are included.
+    fn add_topics_to_test(&mut self, topic_string: &str) {
+        let mut topics = self.topics_from_list(topic_string);
+        if self.quiz.include_subtopics {
+            topics = self.study.with_descendants(&topics);
+        }
+        // The cards of smart topics are found by their query, and a card in several of the
+        // topics, or in the test already, is only asked once.
+        let now = unix_now();
+        for card_key in self.study.cards_in_topics(&topics) {
+            if self.study.cards[card_key].quizzable(now) && !self.quiz.cards.contains(&card_key) {
+                self.quiz.cards.push(card_key);
+            }
+        }
+    }
+
+    // Asks for the order of the test and, for a shuffle, its seed. Returns false when the answer
+    // was not an order.
+    fn ask_order(&mut self) -> bool {
+        println!(
+            "In what order? (created, shuffled, interleaved, hardest or oldest, leave empty for \
+             created)"
+        );
+        let order = self.input.ask_question();
+        self.quiz.order = if order.is_empty() {
+            Order::Created
+        } else {
+            match order.parse() {
+                Ok(order) => order,
+                Err(error) => {
+                    println!("{error}");
+                    return false;
+                }
+            }
+        };
+        if self.quiz.order == Order::Shuffled {
+            println!("Which seed? (leave empty for a new one)");
+            self.quiz.seed = match self.input.ask_question().parse() {
+                Ok(seed) => seed,
+                Err(_) => Rng::time_seed(),
+            };
+            println!("Shuffled with seed {}", self.quiz.seed);
+        }
+        true
+    }
+
+    // Takes a timed exam on `count` of the cards added to the test. Nothing is said about the
+    // answers until the end, when the marks are printed. The time left is shown before each
+    // question, and an answer given after it ran out does not count.
+    fn take_exam(&mut self, count: usize) {
+        println!("What is the time limit? (e.g. 20m for the whole exam or 30s each)");
+        let limit: TimeLimit = match self.input.ask_question().parse() {
+            Ok(limit) => limit,
+            Err(error) => {
+                println!("{error}");
+                return;
+            }
+        };
+        let questions = self
+            .quiz
+            .cards
+            .iter()
+            .map(|&key| {
+                let card = &self.study.cards[key];
+                Question {
+                    question: card.question.clone(),
+                    answer: card.answer.clone(),
+                    id: card.id,
+                }
+            })
+            .collect();
+        let now = Instant::now();
+        let exam = self
+            .quiz
+            .exam
+            .insert(Exam::new(questions, count, limit, Rng::time_seed(), now));
+        let total = exam.questions.len();
+        while let Some(question) = exam.current() {
+            println!(
+                "Question {} of {}, {} left",
+                exam.answers.len() + 1,
+                total,
+                exam::clock(exam.remaining(Instant::now()))
+            );
+            println!("{}", question.question);
+            let answer = self.input.ask_question();
+            if !exam.answer(answer, Instant::now()) {
+                println!("Out of time.");
+            }
+        }
+        let Some(report) = self.quiz.finish_exam(&mut self.study, Instant::now()) else {
+            return;
+        };
+        for result in &report.results {
+            let mark = if result.correct { "right" } else { "wrong" };
+            println!(
+                "{}: {} ({mark}, the answer is {})",
+                result.question.question,
+                result.answer.as_deref().unwrap_or("no answer"),
+                result.question.answer
+            );
+        }
+        println!("{report}");
+    }
+
+    // Prints the topics as a tree, indenting sub-topics below their parent.
+    fn print_topic_tree(&self) {
+        for (key, depth) in self.study.topic_tree() {
+            println!("{}{}", "  ".repeat(depth), self.study.topics[key].content);
+        }
+    }
+
+    // Asks for the topic to put another topic under. An empty answer means the top level.
+    fn ask_parent(&mut self) -> Result<Option<TopicKey>, String> {
+        println!("Which topic should it go under? (leave empty for the top level)");
+        let name = self.input.ask_question();
+        if name.trim().is_empty() {
+            return Ok(None);
+        }
+        match self.study.topic_by_name(&name) {
+            Some(key) => Ok(Some(key)),
+            None => Err(format!("There is no topic called {name}")),
+        }
+    }
+
+    // Asks for a format and a file and exports the cards of `topics` to it.
+    fn export_topics(&mut self, topics: &[TopicKey]) {
+        println!("What format do you want? (pdf, html or bundle)");
+        let format = self.input.ask_question().to_lowercase();
+        let write = match format.as_str() {
+            "pdf" => pdf::write_pdf,
+            "html" => html::write_html,
+            "bundle" => bundle::write_bundle,
+            _ => {
+                println!("Invalid format");
+                return;
+            }
+        };
+        println!("Where do you want to save it?");
+        let path = PathBuf::from(self.input.ask_question());
+        match write(&self.study, topics, &path) {
+            Ok(()) => println!("Exported {} topics to {}", topics.len(), path.display()),
+            Err(error) => println!("Export failed: {error}"),
+        }
+    }
+
+    // Asks for a namespace by number, or by name to create a new one.
+    fn ask_namespace(&mut self) -> Option<NamespaceKey> {
+        println!("Namespaces:");
+        let namespaces = self.study.ordered_namespaces();
+        for (i, &key) in namespaces.iter().enumerate() {
+            let namespace = &self.study.namespaces[key];
+            println!("{}: {} ({})", i, namespace.name, namespace.color.to_hex());
+        }
+
+        println!("Select a namespace, or type a name to create one:");
+        let answer = self.input.ask_question();
+        if let Ok(index) = answer.parse::<usize>() {
+            let key = namespaces.get(index).copied();
+            if key.is_none() {
+                println!("Invalid input");
+            }
+            return key;
+        }
+        if answer.trim().is_empty() {
+            println!("Invalid input");
+            return None;
+        }
+        let key = self.study.namespace_by_name(&answer).unwrap_or_else(|| {
+            println!("Created namespace {}", answer.trim());
+            self.study.insert_namespace(Namespace {
+                name: answer.trim().to_string(),
+                ..Default::default()
+            })
+        });
+        Some(key)
+    }
+
+    // Names the namespaces of a topic, comma separated.
+    fn namespace_names(&self, topic: &Topic) -> String {
+        let names: Vec<&str> = topic
+            .namespaces
+            .iter()
+            .filter_map(|&key| self.study.namespaces.get(key))
+            .map(|namespace| namespace.name.as_str())
+            .collect();
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join(", ")
+        }
+    }
+
+    // Runs an operation and records what it changed, so it can be undone.
+    fn perform(&mut self, operation: Operations) {
+        // Answering cards in a test is not an edit, and undoing an edit keeps the reviews since.
+        if matches!(
+            operation,
+            Operations::Undo | Operations::Redo | Operations::Test
+        ) {
+            return self.operate(operation);
+        }
+        let label = operation.label();
+        let before = Snapshot::take(&self.study);
+        self.operate(operation);
+        self.history.commit(label, before, &self.study);
+    }
+
+    fn operate(&mut self, operation: Operations) {
         match operation {
+            Operations::Undo => match self.history.undo(&mut self.study) {
+                Some(label) => println!("Undid the {label}."),
+                None => println!("There is nothing to undo."),
+            },
+            Operations::Redo => match self.history.redo(&mut self.study) {
+                Some(label) => println!("Redid the {label}."),
+                None => println!("There is nothing to redo."),
+            },
             Operations::Make => {
-                        println!("What is your question?");
-                        let question = self.input.ask_question();
-    
-                        println!("What is your awnser?");
-                        let awnser = self.input.ask_question();
-    
-                        for (_, value) in &self.topics {
-                            println!("{}", value.content);
+                println!("What is your question?");
+                let question = self.input.ask_question();
+
+                println!("What is your answer?");
+                let answer = self.input.ask_question();
+
+                for value in self.study.topics.values() {
+                    println!("{}", value.content);
+                }
+
+                println!("What topics do you want it to be in? (split it from comma)");
+                let topic_string = self.input.ask_question();
+                self.make_card(question, answer, &topic_string);
+            }
+            Operations::Edit => loop {
+                let mut identifyable_cards: Vec<FlashcardKey> = vec![];
+                for (i, (key, card)) in self.study.cards.iter().enumerate() {
+                    println!(
+                        "\n{}: question: {}, answer: {}",
+                        i, card.question, card.answer
+                    );
+                    identifyable_cards.push(key);
+                }
+                println!("What card do you want to edit?");
+                let card_to_edit: Result<usize, _> = self.input.ask_question().parse();
+                let Some(&card_key) = card_to_edit
+                    .ok()
+                    .and_then(|index| identifyable_cards.get(index))
+                else {
+                    println!("Invalid input");
+                    continue;
+                };
+
+                println!("Type 1 to edit question");
+                println!("Type 2 to edit answer");
+                println!("Type 3 to edit topics");
+                println!("Type 4 to exit");
+
+                let edit_operation: Result<usize, _> = self.input.ask_question().parse();
+                let Some(edit_operation) = edit_operation.ok().filter(|operation| *operation <= 4)
+                else {
+                    println!("Invalid input");
+                    continue;
+                };
+
+                match edit_operation {
+                    1 => {
+                        println!(
+                            "Current question is {}, what would you like to change it to?",
+                            self.study.cards[card_key].question
+                        );
+                        let new_question = self.input.ask_question();
+                        self.study.cards[card_key].question = new_question;
+                    }
+                    2 => {
+                        println!(
+                            "Current answer is {}, what would you like to change it to?",
+                            self.study.cards[card_key].answer
+                        );
+                        let new_answer = self.input.ask_question();
+                        self.study.cards[card_key].answer = new_answer;
+                    }
+                    3 => {
+                        println!("Current topics are:");
+                        for key in self.study.cards[card_key].topics.clone() {
+                            println!("{}", self.study.topics.get(key).unwrap().content);
                         }
-    
-                        println!("What topics do you want it to be in? (split it from comma)");
-                        let topic_string = self.input.ask_question();
-                        let topics: Vec<&str> = topic_string.split(",").collect();
-                
-                        let added_card_key = self.cards.insert(Flashcard { question, topics: vec![], awnser });
-    
-                        let mut final_topic_keys: Vec<TopicKey> = vec![];
-                        for listed_topic in topics {
-                            for (key, topic) in self.topics.iter_mut() {
-                                if topic.content.trim().to_lowercase() == listed_topic.trim().to_lowercase() {
-                                    topic.qna.push(added_card_key);
-                                    final_topic_keys.push(key);
-                                }
+
+                        println!("\nPress 1 to remove a topic");
+                        println!("Press 2 to add a topic");
+                        println!("Press 3 to exit");
+                        let topic_operation: Result<usize, _> = self.input.ask_question().parse();
+                        let Some(topic_operation) =
+                            topic_operation.ok().filter(|operation| *operation <= 3)
+                        else {
+                            println!("Invalid input");
+                            continue;
+                        };
+                        match topic_operation {
+                            1 => {
+                                println!("What topic do you want to delete?");
+                                let topic_to_delete = self.input.ask_question();
+                                self.remove_card_topic(card_key, &topic_to_delete);
                             }
-                        }
-            
-    
-                        self.cards.get_mut(added_card_key).unwrap().topics.extend(final_topic_keys);
-                    },
-            Operations::Edit => {
-                        loop {
-                            let mut identifyable_cards: Vec<(FlashcardKey, Flashcard)> = vec![];
-                            for (i, (key, card)) in self.cards.iter().enumerate() {
+                            2 => {
+                                for topic in self.study.topics.values() {
+                                    println!("{}", topic.content);
+                                }
+
                                 println!(
-                                    "\n{}: question: {}, answer: {}",
-                                    i, card.question, card.awnser
+                                    "What topics do you want it to be in? (split it from comma)"
                                 );
-                                identifyable_cards.push((key, card.clone()));
-                    
-                            }
-                            println!("What card do you want to edit?");
-                            let card_to_edit: Result<usize, _> = self.input.ask_question().parse();
-                            if card_to_edit.is_err() {
-                                println!("Invalid input");
-                                continue;
-                            }
-                            let (card_key, card) = &mut identifyable_cards[card_to_edit.unwrap()];
-                    
-                            println!("Type 1 to edit question");
-                            println!("Type 2 to edit answer");
-                            println!("Type 3 to edit topics");
-                            println!("Type 4 to exit");
-            
-                            let edit_operation: Result<usize, _> = self.input.ask_question().parse();
-                            if edit_operation.clone().is_err() || edit_operation.clone().unwrap() > 4 || edit_operation.clone().unwrap() < 0  {
-                                println!("Invalid input");
-                                continue;
-                            }
-            
-                            match edit_operation.unwrap() {
-                                1 => {
-                                    println!("Current question is {}, what would you like to change it to?", card.question);
-                                    let new_question = self.input.ask_question();
-                                    card.question = new_question; 
-                                }
-                                2 => {
-                                    println!("Current answer is {}, what would you like to change it to?", card.awnser);
-                                    let new_awnser = self.input.ask_question();
-                                    card.awnser = new_awnser; 
-                                }
-                                3 => {
-                                    println!("Current topics are:");
-                                    for key in card.topics.clone() {
-                                        println!("{}", self.topics.get(key).unwrap().content);
-                                    }
-            
-                                    println!("\nPress 1 to remove a topic");
-                                    println!("Press 2 to add a topic");
-                                    println!("Press 3 to exit");
-                                    let topic_operation: Result<usize, _> = self.input.ask_question().parse();
-                                    if topic_operation.clone().is_err() || topic_operation.clone().unwrap() > 3 || topic_operation.clone().unwrap() < 0  {
-                                        println!("Invalid input");
-                                        continue;
-                                    }
-                                    match topic_operation.unwrap() {
-                                        1 => {
-                                            println!("What topic do you want to delete?");
-                                            let topic_to_delete = self.input.ask_question().to_lowercase();
-            
-                                    
-                                            card.topics.retain(|&key| {
-                                                let topic = self.topics.get(key).unwrap();
-                                                topic.content.to_lowercase() != topic_to_delete
-                                            });
-                                        }
-                                        2 => {
-                                            for (_, topic) in &self.topics {
-                                                println!("{}", topic.content);
-                                            }
-    
-                                            println!("What topics do you want it to be in? (split it from comma)");
-                                            let topic_string = self.input.ask_question();
-                                            let topics: Vec<&str> = topic_string.split(",").collect();
-                                    
-                                            let mut final_topic_keys: Vec<TopicKey> = vec![];
-                                            for listed_topic in topics {
-                                                for (key, topic) in &mut self.topics {
-                                                    if topic.content.trim().to_lowercase() == listed_topic.to_lowercase().to_string(){
-                                                        topic.qna.push(*card_key);
-                                                        final_topic_keys.push(key);
-                                                    }
-                                                }
-                                            }
-                        
-                                            self.cards.get_mut(*card_key).unwrap().topics.extend(final_topic_keys);
-                                    
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                                4 => {
-                                    break; 
-                                }
-                                _ => {}
+                                let topic_string = self.input.ask_question();
+                                self.add_card_topics(card_key, &topic_string);
                             }
-            
-                            break;
+                            _ => {}
                         }
                     }
+                    4 => {
+                        break;
+                    }
+                    _ => {}
+                }
+
+                break;
+            },
             Operations::List => {
-                        for (i, (_, card)) in self.cards.iter().enumerate() {
-                            let mut all_topics = String::new();
-                            for key in card.topics.clone() {
-                                all_topics.push_str(&self.topics.get(key).unwrap().content);
-                                all_topics.push_str(" ");
-                            }
-                            println!(
-                                "\n{}: question: {}, answer: {}, topics: {}",
-                                i, card.question, card.awnser, all_topics
-                            );
-                        }
-                
-                    },
+                for (i, card) in self.study.cards.values().enumerate() {
+                    let mut all_topics = String::new();
+                    for key in card.topics.clone() {
+                        all_topics.push_str(&self.study.topic_path(key));
+                        all_topics.push(' ');
+                    }
+                    println!(
+                        "\n{}: question: {}, answer: {}, topics: {}",
+                        i, card.question, card.answer, all_topics
+                    );
+                }
+            }
             Operations::Topic => {
-                        println!("What topic do you want to add?");
-                        let topic_to_add = self.input.ask_question();
-                        self.topics.insert(Topic { content: topic_to_add, enabled: false, qna: vec![], topic_tag: TopicTag::None });
-                    },
+                println!("What topic do you want to add?");
+                let topic_to_add = self.input.ask_question();
+                let parent = match self.ask_parent() {
+                    Ok(parent) => parent,
+                    Err(error) => {
+                        println!("{error}");
+                        return;
+                    }
+                };
+                let key = self.create_topic(topic_to_add);
+                self.study.topics[key].parent = parent;
+            }
+            Operations::Reparent => {
+                self.print_topic_tree();
+                println!("What topic do you want to move?");
+                let name = self.input.ask_question();
+                let Some(topic) = self.study.topic_by_name(&name) else {
+                    println!("There is no topic called {name}");
+                    return;
+                };
+                let moved = self.ask_parent().and_then(|parent| {
+                    self.study
+                        .set_parent(topic, parent)
+                        .map_err(|error| error.to_string())
+                });
+                match moved {
+                    Ok(()) => println!("Moved to {}", self.study.topic_path(topic)),
+                    Err(error) => println!("{error}"),
+                }
+            }
             Operations::Test => {
-                        for key in &self.quiz.cards {
-                            println!("{}", self.cards.get(*key).unwrap().question);
-                            if &ask_question() == &self.cards.get(*key).unwrap().awnser {
-                                println!("Correct!")
-                            }
-                        }
-                    },
-            Operations::None => {},
+                println!("How many questions for a timed exam? (leave empty for a normal test)");
+                let count = self.input.ask_question();
+                if !count.is_empty() {
+                    match count.parse() {
+                        Ok(count) => self.take_exam(count),
+                        Err(_) => println!("{count} is not a number"),
+                    }
+                    return;
+                }
+                if !self.ask_order() {
+                    return;
+                }
+                let keys = self.study.order_cards(
+                    self.quiz.cards.clone(),
+                    self.quiz.order,
+                    self.quiz.seed,
+                );
+                for key in keys {
+                    let card = &self.study.cards[key];
+                    println!("{}", card.question);
+                    let question = Question::from(card);
+                    let shown = Instant::now();
+                    let answer = self.input.ask_question();
+                    let response_ms = shown.elapsed().as_millis() as u64;
+                    let correct = question.check(&answer);
+                    if correct {
+                        println!("Correct!")
+                    }
+                    let grade = Grade::from_correct(correct);
+                    if self.study.record_review(key, answer, grade, response_ms) {
+                        println!("This card is now a leech, rewrite it");
+                    }
+                }
+            }
+            Operations::None => {}
             Operations::Delete => {
-                        for (_, value) in &self.topics {
-                            println!("\ntopic: {}", value.content);
-                        }
-                        println!("\n");
-    
-                        println!("What topic do you want to delete?");
-                        let topic_to_delete = self.input.ask_question().to_lowercase().to_string();
-                        self.topics.retain(|_, value| value.content.to_lowercase().to_string() != topic_to_delete);
-            
-                    },
+                for value in self.study.topics.values() {
+                    println!("\ntopic: {}", value.content);
+                }
+                println!("\n");
+
+                println!("What topic do you want to delete?");
+                let topic_to_delete = self.input.ask_question();
+                self.delete_topic(&topic_to_delete);
+            }
             Operations::Add => {
-                        for (_, topic) in &self.topics {
-                            println!("{}", topic.content)
-                        }
-                        print!("\nwhat topics do you want to add to the test? (split by comma)");
-                        let topic_string = self.input.ask_question();
-                        let topics: Vec<&str> = topic_string.split(",").collect();
-    
-    
-                        // let mut final_topic_keys: Vec<TopicKey> = vec![];
-                        for listed_topic in topics {
-                            for (key, topic) in &self.topics {
-                                println!("A");
-                                if topic.content.trim().to_lowercase() == listed_topic.to_lowercase().to_string(){
-                                    println!("B");
-                                    for key in &topic.qna {
-                                        println!("C");
-                                        self.quiz.cards.push(*key);
-                                        println!("{:?}", self.quiz.cards)
-                                    }
-                                }
+                self.print_topic_tree();
+                print!("\nwhat topics do you want to add to the test? (split by comma)");
+                let topic_string = self.input.ask_question();
+                println!("Include their sub-topics? (y/n)");
+                self.quiz.include_subtopics = self.input.ask_question().eq_ignore_ascii_case("y");
+                self.add_topics_to_test(&topic_string);
+                println!("{:?}", self.quiz.cards)
+            }
+            Operations::Auto => {
+                println!("Topic rules:");
+                for topic in self.study.topics.values() {
+                    let rules: Vec<String> = topic.rules.iter().map(Rule::to_string).collect();
+                    println!("{}: {}", topic.content, rules.join(", "));
+                }
+
+                println!("Add a rule to which topic? (leave empty to skip)");
+                let topic_name = self.input.ask_question();
+                if !topic_name.is_empty() {
+                    match self.study.topic_by_name(&topic_name) {
+                        Some(topic_key) => {
+                            println!("What keyword or /regex/ should match?");
+                            match Rule::parse(&self.input.ask_question()) {
+                                Ok(rule) => self.study.topics[topic_key].rules.push(rule),
+                                Err(error) => println!("Invalid rule: {error}"),
                             }
                         }
-    
-    
-                    },
-            Operations::Auto => {
-                
-                    },
+                        None => println!("Topic not found"),
+                    }
+                }
+
+                let suggestions = auto::suggest(&self.study);
+                if suggestions.is_empty() {
+                    println!("No suggestions");
+                    return;
+                }
+                for (i, suggestion) in suggestions.iter().enumerate() {
+                    println!("{}: {}", i, suggestion.describe(&self.study));
+                }
+                println!(
+                    "Which suggestions do you want to apply? (all, none or numbers split by comma)"
+                );
+                let accepted: Vec<Suggestion> = match self.input.ask_question().as_str() {
+                    "all" => suggestions,
+                    selection => selection
+                        .split(",")
+                        .filter_map(|index| index.trim().parse::<usize>().ok())
+                        .filter_map(|index| suggestions.get(index).cloned())
+                        .collect(),
+                };
+                auto::apply(&mut self.study, &accepted);
+                println!("Applied {} suggestions", accepted.len());
+            }
             Operations::NamespaceOperate => {
-                loop {
-                    println!("Namespaces:");
-                    let namespace_arr: Vec<_> = TopicTag::iter().collect();
-                    for namespace in namespace_arr {
-                        println!("{}: {}", namespace.clone() as i32, namespace);
+                let namespace = loop {
+                    if let Some(namespace) = self.ask_namespace() {
+                        break namespace;
                     }
-                    
-                    println!("Select a namespace:");
-                    let mut namespace_to_choose: Result<usize, _> = self.input.ask_question().parse();
-                    if namespace_to_choose.is_err() {
-                        println!("Invalid input");
-                        continue;
+                };
+
+                println!("Type 1 to enable all topics");
+                println!("Type 2 to disable all topics");
+                println!("Type 3 to start a test on all topics");
+                println!("Type 4 to export all topics");
+                println!("Type 5 to move all topics to another namespace");
+                println!("Type 6 to delete all topics");
+                println!("Type 7 to rename the namespace");
+                println!("Type 8 to change the colour of the namespace");
+                println!("Type 9 to change the position of the namespace");
+                println!("Type 10 to delete the namespace but keep its topics");
+                println!("Type 11 to exit");
+                let operation: Result<usize, _> = self.input.ask_question().parse();
+                let operation = match operation {
+                    Ok(1) => NamespaceOperation::Enable,
+                    Ok(2) => NamespaceOperation::Disable,
+                    Ok(3) => NamespaceOperation::Quiz,
+                    Ok(4) => NamespaceOperation::Export,
+                    Ok(5) => loop {
+                        if let Some(target) = self.ask_namespace() {
+                            break NamespaceOperation::Move(target);
+                        }
+                    },
+                    Ok(6) => NamespaceOperation::Delete,
+                    Ok(7) => {
+                        println!("What is the new name?");
+                        let name = self.input.ask_question().trim().to_string();
+                        if name.is_empty() {
+                            println!("The name cannot be empty");
+                        } else if self
+                            .study
+                            .namespace_by_name(&name)
+                            .is_some_and(|other| other != namespace)
+                        {
+                            println!("A namespace called {name} exists already");
+                        } else {
+                            self.study.namespaces[namespace].name = name;
+                        }
+                        return;
                     }
-                    let unwrapped_namespace = namespace_to_choose.unwrap();
-
-                    let finalnamespace = match unwrapped_namespace {
-                        0 => TopicTag::Quiz,
-                        1 => TopicTag::Configure,
-                        2 => TopicTag::Default,
-                        3 => TopicTag::None,
-                        _ => {
-                            println!("Invalid value, defaulting to Default");
-                            TopicTag::Default 
+                    Ok(8) => {
+                        println!("What is the new colour? (#rrggbb)");
+                        match Color::from_hex(&self.input.ask_question()) {
+                            Some(color) => self.study.namespaces[namespace].color = color,
+                            None => println!("Invalid colour"),
                         }
-                    };
-
-                    println!("Final namespace choosen is {}", finalnamespace);
-
-                    
-                }
-            },
-            Operations::NamepaceChange => {
-                loop {
-                    println!("Select a topic:");
-                    let mut identifyable_topics: Vec<(TopicKey, Topic)> = vec![];
-                    for (i, (key, topic)) in self.topics.iter().enumerate() {
-                        println!("{}: {}", i, topic.content);
-                        identifyable_topics.push((key, topic.clone()));
-            
+                        return;
                     }
-                    println!("What topic do you want to edit?");
-                    let topic_to_edit: Result<usize, _> = self.input.ask_question().parse();
-                    if topic_to_edit.is_err() {
-                        println!("Invalid input");
-                        continue;
+                    Ok(9) => {
+                        println!("What is the new position?");
+                        match self.input.ask_question().parse() {
+                            Ok(position) => self.study.move_namespace(namespace, position),
+                            Err(_) => println!("Invalid input"),
+                        }
+                        return;
                     }
-                    let (topic_key, topic) = &mut identifyable_topics[topic_to_edit.unwrap()];
-                    println!("Current topic is {} and namespace is {}", topic.content, topic.topic_tag);
-
-                    println!("Namespaces:");
-                    let namespace_arr: Vec<_> = TopicTag::iter().collect();
-                    for namespace in namespace_arr {
-                        println!("{}: {}", namespace.clone() as i32, namespace);
+                    Ok(10) => {
+                        self.study.remove_namespace(namespace);
+                        return;
                     }
-                    
-                    println!("Select a namespace:");
-                    let mut namespace_to_choose: Result<usize, _> = self.input.ask_question().parse();
-                    if namespace_to_choose.is_err() {
+                    Ok(11) => return,
+                    _ => {
                         println!("Invalid input");
-                        continue;
+                        return;
                     }
-                    let unwrapped_namespace = namespace_to_choose.unwrap();
-
-                    let finalnamespace = match unwrapped_namespace {
-                        0 => TopicTag::Quiz,
-                        1 => TopicTag::Configure,
-                        2 => TopicTag::Default,
-                        3 => TopicTag::None,
-                        _ => {
-                            println!("Invalid value, defaulting to Default");
-                            TopicTag::Default 
-                        }
-                    };
-
-                    println!("Final namespace choosen is {}", finalnamespace);
+                };
 
-                    topic.topic_tag = finalnamespace;
-                    break;
+                let topics =
+                    self.quiz
+                        .operation_for_namespace(&mut self.study, namespace, &operation);
+                match operation {
+                    NamespaceOperation::Quiz => self.operate(Operations::Test),
+                    NamespaceOperation::Export => self.export_topics(&topics),
+                    _ => {}
                 }
+            }
+            Operations::NamepaceChange => loop {
+                println!("Select a topic:");
+                let mut identifyable_topics: Vec<TopicKey> = vec![];
+                for (i, (key, topic)) in self.study.topics.iter().enumerate() {
+                    println!("{}: {}", i, topic.content);
+                    identifyable_topics.push(key);
+                }
+                println!("What topic do you want to edit?");
+                let topic_to_edit: Result<usize, _> = self.input.ask_question().parse();
+                let Some(&topic_key) = topic_to_edit
+                    .ok()
+                    .and_then(|index| identifyable_topics.get(index))
+                else {
+                    println!("Invalid input");
+                    continue;
+                };
+                let topic = &self.study.topics[topic_key];
+                println!(
+                    "Current topic is {} and its namespaces are {}",
+                    topic.content,
+                    self.namespace_names(topic)
+                );
+
+                let Some(namespace) = self.ask_namespace() else {
+                    continue;
+                };
+                self.quiz
+                    .toggle_namespace(&mut self.study, topic_key, namespace);
+                break;
             },
-                    }
+        }
     }
+
+    fn save(&self) {
+        if let Some(path) = &self.deck {
+            if let Err(error) = deck::save_deck(&self.study, path) {
+                println!("Could not save {}: {error}", path.display());
+            }
+        }
+    }
+
     fn start(mut self) {
-        loop {
-        let mut operation =  Operations::None;
         loop {
             println!("Operations:");
             println!("(M): Make a flashcard");
@@ -375,13 +763,16 @@ impl<I: UserInput> App<I> {
             println!("(L): List flashcards");
             println!("(P): Create a topic");
             println!("(D): Delete a topic");
+            println!("(R): Move a topic under another topic");
             println!("(E): Edit or delete a flashcard");
             println!("(Q): Add topics to a test");
-            println!("(T): Start a test");
+            println!("(T): Start a test or a timed exam");
             println!("(N): Add or remove topic to/from namespace");
             println!("(O): Namespace operation");
+            println!("(U): Undo the last change");
+            println!("(Y): Redo the last undone change");
             println!("What operation would you like to do?");
-            operation = match self.input.ask_question().trim().to_lowercase().as_str() {
+            let operation = match self.input.ask_question().trim().to_lowercase().as_str() {
                 "t" => Operations::Test,
                 "p" => Operations::Topic,
                 "l" => Operations::List,
@@ -391,38 +782,55 @@ impl<I: UserInput> App<I> {
                 "d" => Operations::Delete,
                 "m" => Operations::Make,
                 "n" => Operations::NamepaceChange,
+                "r" => Operations::Reparent,
                 "o" => Operations::NamespaceOperate,
+                "u" => Operations::Undo,
+                "y" => Operations::Redo,
                 _ => Operations::None,
             };
-            if operation == Operations::None {
-                break
-            }
-            self.operate(operation);
+            self.perform(operation);
+            self.save();
         }
     }
-    }
- }
+}
 
-fn main(){
-    App::new().start();
+// Runs a subcommand, or the interactive menu when none is given. Unless another deck file is
+// given the deck shared with the GUI is used.
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let deck = cli.deck.unwrap_or_else(deck::default_path);
+    match cli.command {
+        Some(command) => cli::run(command, &deck, &mut cli::StdinInput),
+        None if cli.tui => match tui::run(App::new(deck)) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("Error: {error}");
+                ExitCode::FAILURE
+            }
+        },
+        None => {
+            App::new(deck).start();
+            ExitCode::SUCCESS
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    struct MockInput {
+    pub(crate) struct MockInput {
         responses: Vec<String>,
     }
-    
+
     impl MockInput {
-        fn new(responses: Vec<&str>) -> Self {
+        pub(crate) fn new(responses: Vec<&str>) -> Self {
             Self {
                 responses: responses.into_iter().map(|s| s.to_string()).collect(),
             }
         }
     }
-    
+
     impl UserInput for MockInput {
         fn ask_question(&mut self) -> String {
             let response = self.responses.remove(0);
@@ -430,33 +838,206 @@ mod tests {
             response
         }
     }
-    impl Default for App<MockInput> { 
-        fn default() -> Self {
-            Self {
-                input: MockInput { responses: vec![] },
-                ..Default::default()
-            }
+
+    pub(crate) fn app(responses: Vec<&str>) -> App<MockInput> {
+        App {
+            input: MockInput::new(responses),
+            study: Study::default(),
+            quiz: Quiz::default(),
+            deck: None,
+            history: History::default(),
         }
     }
 
     #[test]
-    fn edit_topic(){
-
-        let binding = MockInput::new(["0", "3", "2", "topic"].to_vec());
-        let mut app = App { input: binding, cards: SlotMap::with_key(), topics: SlotMap::with_key(), quiz: Quiz::default(), current_topics: vec![]  };
-        let key = app.cards.insert(Flashcard { question: "Question".to_string(), awnser: "Awnser".to_string(), topics: vec![] });
-        app.topics.insert(Topic { content: "topic".to_string(), enabled: false, qna: vec![], topic_tag: TopicTag::None });
+    fn edit_topic() {
+        let mut app = app(["0", "3", "2", "topic"].to_vec());
+        let key = app.study.insert_card(Flashcard {
+            question: "Question".to_string(),
+            answer: "Answer".to_string(),
+            ..Default::default()
+        });
+        app.study.insert_topic(Topic {
+            content: "topic".to_string(),
+            ..Default::default()
+        });
         app.operate(Operations::Edit);
-        assert_eq!(app.topics.get(*app.cards.get(key).unwrap().topics.get(0).unwrap()).unwrap().content, "topic")
-        //assert_eq!()
-    }
-    // #[test]
-    // fn start_test(){
-    //     let binding = MockInput::new(["topic",].to_vec());
-    //     let mut app = App { input: binding, cards: SlotMap::with_key(), topics: SlotMap::with_key(), quiz: Quiz::default()  };
-    //     let topic = app.topics.insert(Topic { content: "topic".to_string(), enabled: false, qna: vec![] });
-    //     let card_key = app.cards.insert(Flashcard { question: "Question".to_string(), awnser: "Awnser".to_string(), topics: vec![topic] });
-    //     app.operate(Operations::Add);
-
-    // }
-}
\ No newline at end of file
+        assert_eq!(
+            app.study.topics[app.study.cards[key].topics[0]].content,
+            "topic"
+        )
+    }
+
+    #[test]
+    fn auto_assigns_topics() {
+        let mut app = app(["math", r"/\d\+\d/", "0"].to_vec());
+        let math = app.study.topic_by_name_or_insert("Math");
+        let sum = app.make_card("1+1?".to_string(), "2".to_string(), "");
+        app.make_card("2+3?".to_string(), "5".to_string(), "");
+        app.operate(Operations::Auto);
+        assert_eq!(app.study.cards[sum].topics, [math]);
+        assert_eq!(app.study.topics[math].qna, [sum]);
+    }
+
+    #[test]
+    fn namespace_operate() {
+        let responses = [
+            "0",
+            "Semester 1",
+            "0",
+            "5",
+            "Exam prep",
+            "1",
+            "3",
+            "",
+            "",
+            "4",
+            "1",
+            "8",
+            "#ff0000",
+        ];
+        let mut app = app(responses.to_vec());
+        let topic = app.create_topic("topic".to_string());
+        app.make_card("2+2?".to_string(), "4".to_string(), "topic");
+
+        // Putting the topic in a new namespace, moving that namespace's topics to another new
+        // namespace, then testing on it and recolouring it.
+        app.operate(Operations::NamepaceChange);
+        let semester = app.study.namespace_by_name("semester 1").unwrap();
+        assert_eq!(app.study.topics[topic].namespaces, [semester]);
+        app.operate(Operations::NamespaceOperate);
+        let exam = app.study.namespace_by_name("Exam prep").unwrap();
+        assert_eq!(app.study.topics[topic].namespaces, [exam]);
+        app.operate(Operations::NamespaceOperate);
+        assert_eq!(app.quiz.cards.len(), 1);
+        app.operate(Operations::NamespaceOperate);
+        assert_eq!(app.study.namespaces[exam].color.to_hex(), "#ff0000");
+        assert!(app.input.responses.is_empty());
+    }
+
+    #[test]
+    fn renames_namespaces_to_trimmed_names() {
+        let mut app = app(["Exam", "7", "  ", "0", "7", " Finals "].to_vec());
+        app.operate(Operations::NamespaceOperate);
+        let exam = app.study.namespace_by_name("Exam").unwrap();
+        app.operate(Operations::NamespaceOperate);
+        assert_eq!(app.study.namespaces[exam].name, "Finals");
+    }
+
+    #[test]
+    fn topic_tree() {
+        let responses = [
+            "Cell", "Biology", "Biology", "cell", "Cell", "", "Biology", "y",
+        ];
+        let mut app = app(responses.to_vec());
+        let biology = app.create_topic("Biology".to_string());
+        let card = app.make_card("Powerhouse?".to_string(), "Mitochondria".to_string(), "");
+
+        app.operate(Operations::Topic);
+        let cell = app.study.topic_by_name("Cell").unwrap();
+        assert_eq!(app.study.topic_path(cell), "Biology > Cell");
+        app.add_card_topics(card, "Cell");
+
+        // Moving Biology under its own sub-topic is refused.
+        app.operate(Operations::Reparent);
+        assert_eq!(app.study.topics[biology].parent, None);
+        app.operate(Operations::Reparent);
+        assert_eq!(app.study.topics[cell].parent, None);
+        app.study.set_parent(cell, Some(biology)).unwrap();
+
+        app.operate(Operations::Add);
+        assert_eq!(app.quiz.cards, [card]);
+        assert!(app.input.responses.is_empty());
+    }
+
+    #[test]
+    fn takes_timed_exams() {
+        let mut app = app(["2", "20m", "4", "wrong"].to_vec());
+        app.create_topic("Math".to_string());
+        for n in 1..=3 {
+            app.make_card(format!("{n}+{n}?"), (n + n).to_string(), "Math");
+        }
+        app.add_topics_to_test("Math");
+        app.perform(Operations::Test);
+        assert!(app.quiz.exam.is_none());
+        assert_eq!(app.study.reviews.len(), 2);
+        assert!(app.input.responses.is_empty());
+    }
+
+    #[test]
+    fn tests_in_order() {
+        let mut app = app(["", "interleaved", "2", "Cat", "4"].to_vec());
+        app.create_topic("Math".to_string());
+        app.create_topic("Words".to_string());
+        app.make_card("1+1?".to_string(), "2".to_string(), "Math");
+        app.make_card("2+2?".to_string(), "4".to_string(), "Math");
+        app.make_card("Meow?".to_string(), "Cat".to_string(), "Words");
+        app.add_topics_to_test("Math, Words");
+        app.perform(Operations::Test);
+        let answers: Vec<&str> = app
+            .study
+            .reviews
+            .iter()
+            .map(|review| review.answer.as_str())
+            .collect();
+        assert_eq!(answers, ["2", "Cat", "4"]);
+        assert!(app.study.reviews.iter().all(|review| review.correct()));
+    }
+
+    #[test]
+    fn tests_on_smart_topics_ask_each_card_once() {
+        let mut app = app(vec![]);
+        app.create_topic("Math".to_string());
+        let sum = app.make_card("1+1?".to_string(), "2".to_string(), "Math");
+        let cell = app.make_card("Cell?".to_string(), "Unit".to_string(), "");
+        let smart = app.create_topic("Questions".to_string());
+        app.study.topics[smart].query = Some("cell".to_string());
+
+        app.add_topics_to_test("Math, Questions");
+        app.add_topics_to_test("Math");
+        assert_eq!(app.quiz.cards, [sum, cell]);
+    }
+
+    #[test]
+    fn deleting_a_topic_keeps_links_intact() {
+        let mut app = app(["topic"].to_vec());
+        app.create_topic("topic".to_string());
+        let card = app.make_card("Q".to_string(), "A".to_string(), "topic");
+        app.operate(Operations::Delete);
+        assert!(app.study.cards[card].topics.is_empty());
+        assert!(flashcard_core::integrity::check(&app.study).is_empty());
+        app.operate(Operations::List);
+    }
+
+    #[test]
+    fn make_card() {
+        let mut app = app(["Question", "Answer", "topic"].to_vec());
+        let topic = app.study.topic_by_name_or_insert("topic");
+        app.operate(Operations::Make);
+        let (key, card) = app.study.cards.iter().next().unwrap();
+        assert_eq!(card.answer, "Answer");
+        assert_eq!(card.topics, [topic]);
+        assert_eq!(app.study.topics[topic].qna, [key]);
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut app = app(["Question", "Answer", "topic", "Cell", ""].to_vec());
+        app.study.topic_by_name_or_insert("topic");
+        app.perform(Operations::Make);
+        app.perform(Operations::Topic);
+        assert_eq!((app.study.cards.len(), app.study.topics.len()), (1, 2));
+
+        app.perform(Operations::Undo);
+        assert!(app.study.topic_by_name("Cell").is_none());
+        app.perform(Operations::Undo);
+        assert!(app.study.cards.is_empty());
+        // Only changes are recorded, so there is nothing left to undo.
+        app.perform(Operations::Undo);
+        assert_eq!(app.study.topics.len(), 1);
+
+        app.perform(Operations::Redo);
+        let (_, card) = app.study.cards.iter().next().unwrap();
+        assert_eq!(app.study.topics[card.topics[0]].content, "topic");
+    }
+}
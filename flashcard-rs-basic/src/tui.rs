@@ -0,0 +1,880 @@
+//! Full-screen terminal UI.
+//!
+//! The browser shows the topics in a sidebar next to the cards of the selected topic, which can
+//! be searched, edited and quizzed on without leaving the keyboard. Changes go through the same
+//! [`App`] methods as the menu operations and are saved to the deck right away.
+use std::collections::{HashSet, VecDeque};
+use std::io;
+use std::time::Instant;
+
+use flashcard_core::deck;
+use flashcard_core::history::Snapshot;
+use flashcard_core::order::Order;
+use flashcard_core::quiz::{unix_now, FlashcardKey, Question, TopicKey};
+use flashcard_core::random::Rng;
+use flashcard_core::review::Grade;
+use flashcard_core::search::SearchIndex;
+use flashcard_core::state::Flag;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::{App, UserInput};
+
+const EDITOR_FIELDS: [&str; 3] = ["Question", "Answer", "Topics (split by comma)"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Focus {
+    Topics,
+    Cards,
+}
+
+// A card being written or edited. `card` is `None` for a new card.
+#[derive(Debug, Default)]
+struct Editor {
+    card: Option<FlashcardKey>,
+    fields: [String; 3],
+    field: usize,
+}
+
+// A quiz on a list of cards. Cards answered wrongly go to the back of the queue; only cards known
+// at the first try count as correct.
+#[derive(Debug, Default)]
+struct QuizScreen {
+    queue: VecDeque<FlashcardKey>,
+    typed: String,
+    flipped: bool,
+    verdict: Option<bool>,
+    // When the card was shown, and how long it took to flip it.
+    shown: Option<Instant>,
+    response_ms: u64,
+    missed: HashSet<FlashcardKey>,
+    correct: usize,
+    total: usize,
+}
+
+enum Screen {
+    Browse,
+    Search,
+    Editor(Editor),
+    NewTopic(String),
+    Quiz(QuizScreen),
+}
+
+pub struct Tui<I: UserInput> {
+    app: App<I>,
+    screen: Screen,
+    focus: Focus,
+    search: String,
+    index: SearchIndex,
+    // Index 0 of the sidebar is "All cards", the topics follow.
+    topic_state: ListState,
+    card_state: ListState,
+    status: String,
+    quit: bool,
+}
+
+/// Runs the terminal UI until the user quits.
+pub fn run<I: UserInput>(app: App<I>) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = Tui::new(app).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl<I: UserInput> Tui<I> {
+    pub fn new(app: App<I>) -> Self {
+        Self {
+            screen: Screen::Browse,
+            focus: Focus::Cards,
+            search: String::new(),
+            index: SearchIndex::new(&app.study),
+            topic_state: ListState::default().with_selected(Some(0)),
+            card_state: ListState::default().with_selected(Some(0)),
+            status: String::new(),
+            quit: false,
+            app,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The topics in tree order, each parent followed by its sub-topics.
+    fn topics(&self) -> Vec<TopicKey> {
+        self.app
+            .study
+            .topic_tree()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    fn selected_topic(&self) -> Option<TopicKey> {
+        let index = self.topic_state.selected()?.checked_sub(1)?;
+        self.topics().get(index).copied()
+    }
+
+    // The cards of the selected topic and its sub-topics, in the order they were made, or the
+    // ones matching the search with the best match first.
+    fn visible_cards(&self) -> Vec<FlashcardKey> {
+        let keys = match self.selected_topic() {
+            Some(topic) => self
+                .app
+                .study
+                .cards_in_topics(&self.app.study.with_descendants(&[topic])),
+            None => self.app.study.cards.keys().collect(),
+        };
+        if self.search.trim().is_empty() {
+            return keys;
+        }
+        let keys: HashSet<FlashcardKey> = keys.into_iter().collect();
+        self.index
+            .search(&self.search)
+            .into_iter()
+            .map(|hit| hit.card)
+            .filter(|key| keys.contains(key))
+            .collect()
+    }
+
+    fn selected_card(&self) -> Option<FlashcardKey> {
+        self.visible_cards()
+            .get(self.card_state.selected()?)
+            .copied()
+    }
+
+    fn save(&mut self) {
+        if let Some(path) = &self.app.deck {
+            self.status = match deck::save_deck(&self.app.study, path) {
+                Ok(()) => format!("Saved to {}", path.display()),
+                Err(error) => format!("Could not save {}: {error}", path.display()),
+            };
+        }
+    }
+
+    // Runs an edit and records what it changed under `label`, so Ctrl+Z and Ctrl+Shift+Z on the
+    // card browser can undo and redo it. Grading a card in a quiz is not an edit, and undoing an
+    // edit keeps the reviews given since.
+    fn record<T>(&mut self, label: &str, edit: impl FnOnce(&mut Self) -> T) -> T {
+        let before = Snapshot::take(&self.app.study);
+        let result = edit(self);
+        for key in self.app.history.commit(label, before, &self.app.study) {
+            self.index.update_card(&self.app.study, key);
+        }
+        result
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        let undo_key = matches!(self.screen, Screen::Browse)
+            && key.modifiers.contains(KeyModifiers::CONTROL)
+            && matches!(key.code, KeyCode::Char('z' | 'Z'));
+        if undo_key {
+            self.status = if key.modifiers.contains(KeyModifiers::SHIFT) {
+                match self.app.history.redo(&mut self.app.study) {
+                    Some(label) => format!("Redid {label}"),
+                    None => "Nothing to redo".to_string(),
+                }
+            } else {
+                match self.app.history.undo(&mut self.app.study) {
+                    Some(label) => format!("Undid {label}"),
+                    None => "Nothing to undo".to_string(),
+                }
+            };
+            self.topic_state.select(Some(0));
+            self.card_state.select(Some(0));
+            self.save();
+            self.index.refresh(&self.app.study);
+            return;
+        }
+        self.dispatch_key(key);
+    }
+
+    fn dispatch_key(&mut self, key: KeyEvent) {
+        match std::mem::replace(&mut self.screen, Screen::Browse) {
+            Screen::Browse => self.browse_key(key),
+            Screen::Search => self.search_key(key),
+            Screen::Editor(editor) => self.editor_key(editor, key),
+            Screen::NewTopic(name) => self.new_topic_key(name, key),
+            Screen::Quiz(quiz) => self.quiz_key(quiz, key),
+        }
+    }
+
+    fn browse_key(&mut self, key: KeyEvent) {
+        let len = match self.focus {
+            Focus::Topics => self.app.study.topics.len() + 1,
+            Focus::Cards => self.visible_cards().len(),
+        };
+        let state = match self.focus {
+            Focus::Topics => &mut self.topic_state,
+            Focus::Cards => &mut self.card_state,
+        };
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Focus::Topics => Focus::Cards,
+                    Focus::Cards => Focus::Topics,
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let next = state.selected().map_or(0, |index| index + 1);
+                state.select(Some(next.min(len.saturating_sub(1))));
+                if self.focus == Focus::Topics {
+                    self.card_state.select(Some(0));
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let previous = state.selected().unwrap_or(0).saturating_sub(1);
+                state.select(Some(previous));
+                if self.focus == Focus::Topics {
+                    self.card_state.select(Some(0));
+                }
+            }
+            KeyCode::Char('/') => {
+                self.card_state.select(Some(0));
+                self.screen = Screen::Search;
+            }
+            KeyCode::Char('n') => {
+                let topic = self
+                    .selected_topic()
+                    .map(|key| self.app.study.topics[key].content.clone())
+                    .unwrap_or_default();
+                self.screen = Screen::Editor(Editor {
+                    fields: [String::new(), String::new(), topic],
+                    ..Default::default()
+                });
+            }
+            KeyCode::Char('e') | KeyCode::Enter if self.focus == Focus::Cards => {
+                if let Some(card_key) = self.selected_card() {
+                    let card = &self.app.study.cards[card_key];
+                    let topics: Vec<&str> = card
+                        .topics
+                        .iter()
+                        .filter_map(|&topic| self.app.study.topics.get(topic))
+                        .map(|topic| topic.content.as_str())
+                        .collect();
+                    self.screen = Screen::Editor(Editor {
+                        card: Some(card_key),
+                        fields: [
+                            card.question.clone(),
+                            card.answer.clone(),
+                            topics.join(", "),
+                        ],
+                        field: 0,
+                    });
+                }
+            }
+            KeyCode::Char('d') => match self.focus {
+                Focus::Cards => {
+                    if let Some(card_key) = self.selected_card() {
+                        self.record("delete card", |tui| tui.app.study.remove_card(card_key));
+                        self.save();
+                    }
+                }
+                Focus::Topics => {
+                    if let Some(topic_key) = self.selected_topic() {
+                        let name = self.app.study.topics[topic_key].content.clone();
+                        self.record("delete topic", |tui| tui.app.delete_topic(&name));
+                        self.topic_state.select(Some(0));
+                        self.save();
+                    }
+                }
+            },
+            // Suspending, burying and flagging toggle, and flags cycle through the colours.
+            KeyCode::Char('x' | 'b' | 'f') if self.focus == Focus::Cards => {
+                if let Some(card_key) = self.selected_card() {
+                    let label = match key.code {
+                        KeyCode::Char('x') => "suspend card",
+                        KeyCode::Char('b') => "bury card",
+                        _ => "flag card",
+                    };
+                    self.record(label, |tui| {
+                        let now = unix_now();
+                        let card = &mut tui.app.study.cards[card_key];
+                        match key.code {
+                            KeyCode::Char('x') => card.suspended = !card.suspended,
+                            KeyCode::Char('b') if card.is_buried(now) => card.buried_until = None,
+                            KeyCode::Char('b') => card.bury(now),
+                            _ => {
+                                let next = match card.flag {
+                                    None => Some(0),
+                                    Some(flag) => {
+                                        Flag::ALL.iter().position(|&f| f == flag).map(|i| i + 1)
+                                    }
+                                };
+                                card.flag = next.and_then(|i| Flag::ALL.get(i).copied());
+                            }
+                        }
+                    });
+                    self.save();
+                }
+            }
+            KeyCode::Char('t') => self.screen = Screen::NewTopic(String::new()),
+            // The order of the next quiz cycles, with a new seed for every shuffle.
+            KeyCode::Char('o') => {
+                let quiz = &mut self.app.quiz;
+                let next = Order::ALL
+                    .iter()
+                    .position(|&order| order == quiz.order)
+                    .unwrap_or(0)
+                    + 1;
+                quiz.order = Order::ALL[next % Order::ALL.len()];
+                self.status = format!("Quiz order: {}", quiz.order.describe());
+                if quiz.order == Order::Shuffled {
+                    quiz.seed = Rng::time_seed();
+                    self.status += &format!(" with seed {}", quiz.seed);
+                }
+            }
+            KeyCode::Char('s') => {
+                let study = &self.app.study;
+                let now = unix_now();
+                let mut keys = self.visible_cards();
+                keys.retain(|&key| study.cards[key].quizzable(now));
+                let queue: VecDeque<FlashcardKey> = study
+                    .order_cards(keys, self.app.quiz.order, self.app.quiz.seed)
+                    .into();
+                if queue.is_empty() {
+                    self.status = "There are no cards to quiz on".to_string();
+                } else {
+                    self.screen = Screen::Quiz(QuizScreen {
+                        total: queue.len(),
+                        queue,
+                        shown: Some(Instant::now()),
+                        ..Default::default()
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => self.search.clear(),
+            KeyCode::Enter => {}
+            KeyCode::Backspace => {
+                self.search.pop();
+                self.screen = Screen::Search;
+            }
+            KeyCode::Char(character) => {
+                self.search.push(character);
+                self.card_state.select(Some(0));
+                self.screen = Screen::Search;
+            }
+            _ => self.screen = Screen::Search,
+        }
+    }
+
+    fn editor_key(&mut self, mut editor: Editor, key: KeyEvent) {
+        let save = key.code == KeyCode::Char('s') && key.modifiers.contains(KeyModifiers::CONTROL)
+            || key.code == KeyCode::Enter && editor.field == EDITOR_FIELDS.len() - 1;
+        if save {
+            let [question, answer, topics] = editor.fields;
+            match editor.card {
+                Some(card_key) => self.record("edit card", |tui| {
+                    let card = &mut tui.app.study.cards[card_key];
+                    card.question = question;
+                    card.answer = answer;
+                    tui.app.set_card_topics(card_key, &topics);
+                }),
+                None => self.record("add card", |tui| {
+                    tui.app.make_card(question, answer, &topics);
+                }),
+            }
+            self.save();
+            return;
+        }
+        match key.code {
+            KeyCode::Esc => return,
+            KeyCode::Tab | KeyCode::Down | KeyCode::Enter => {
+                editor.field = (editor.field + 1) % EDITOR_FIELDS.len();
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                editor.field = (editor.field + EDITOR_FIELDS.len() - 1) % EDITOR_FIELDS.len();
+            }
+            KeyCode::Backspace => {
+                editor.fields[editor.field].pop();
+            }
+            KeyCode::Char(character) => editor.fields[editor.field].push(character),
+            _ => {}
+        }
+        self.screen = Screen::Editor(editor);
+    }
+
+    fn new_topic_key(&mut self, mut name: String, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => return,
+            KeyCode::Enter => {
+                if !name.trim().is_empty() && self.app.study.topic_by_name(&name).is_none() {
+                    self.record("add topic", |tui| {
+                        tui.app.create_topic(name.trim().to_string())
+                    });
+                    self.save();
+                }
+                return;
+            }
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Char(character) => name.push(character),
+            _ => {}
+        }
+        self.screen = Screen::NewTopic(name);
+    }
+
+    fn quiz_key(&mut self, mut quiz: QuizScreen, key: KeyEvent) {
+        if key.code == KeyCode::Esc {
+            return;
+        }
+        let Some(&card_key) = quiz.queue.front() else {
+            // Any key leaves the results.
+            return;
+        };
+        if !quiz.flipped {
+            let response_ms = quiz
+                .shown
+                .map_or(0, |shown| shown.elapsed().as_millis() as u64);
+            match key.code {
+                KeyCode::Enter => {
+                    let question = Question::from(&self.app.study.cards[card_key]);
+                    quiz.verdict = Some(question.check(&quiz.typed));
+                    quiz.flipped = true;
+                    quiz.response_ms = response_ms;
+                }
+                KeyCode::Tab => {
+                    quiz.flipped = true;
+                    quiz.response_ms = response_ms;
+                }
+                KeyCode::Backspace => {
+                    quiz.typed.pop();
+                }
+                KeyCode::Char(character) => quiz.typed.push(character),
+                _ => {}
+            }
+        } else {
+            // After flipping the card is graded: Enter accepts the verdict on the typed answer,
+            // `g` and `a` override it, and 1 to 4 grade it from Again to Easy.
+            let grade = match key.code {
+                KeyCode::Enter => quiz.verdict.map(Grade::from_correct),
+                KeyCode::Char('g') => Some(Grade::Good),
+                KeyCode::Char('a') => Some(Grade::Again),
+                KeyCode::Char(digit @ '1'..='4') => digit.to_string().parse().ok(),
+                _ => None,
+            };
+            if let Some(grade) = grade {
+                quiz.queue.pop_front();
+                let leech = self.app.study.record_review(
+                    card_key,
+                    std::mem::take(&mut quiz.typed),
+                    grade,
+                    quiz.response_ms,
+                );
+                self.save();
+                if leech {
+                    self.status = if self.app.study.cards[card_key].suspended {
+                        "This card is now a leech and was suspended, rewrite it".to_string()
+                    } else {
+                        "This card is now a leech, rewrite it".to_string()
+                    };
+                }
+                if grade != Grade::Again {
+                    if !quiz.missed.contains(&card_key) {
+                        quiz.correct += 1;
+                    }
+                } else {
+                    quiz.missed.insert(card_key);
+                    // Leeches are not asked again, so they cannot keep the quiz going.
+                    if !self.app.study.cards[card_key].leech {
+                        quiz.queue.push_back(card_key);
+                    }
+                }
+                quiz.flipped = false;
+                quiz.verdict = None;
+                quiz.shown = Some(Instant::now());
+            }
+        }
+        self.screen = Screen::Quiz(quiz);
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [body, help] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        self.draw_browser(frame, body);
+
+        let help_text = match &self.screen {
+            Screen::Browse | Screen::Search => {
+                "/ search  tab switch  n new card  e edit  d delete  x suspend  b bury  f flag  \
+                 t new topic  s quiz  o quiz order  ctrl+z undo  q quit"
+            }
+            Screen::Editor(_) => "tab next field  enter on topics or ctrl+s save  esc cancel",
+            Screen::NewTopic(_) => "enter add topic  esc cancel",
+            Screen::Quiz(quiz) if quiz.queue.is_empty() => "any key back to the cards",
+            Screen::Quiz(quiz) if quiz.flipped => {
+                "enter accept  g got it  a again  1-4 again/hard/good/easy  esc stop"
+            }
+            Screen::Quiz(_) => "type your answer, enter check  tab flip  esc stop",
+        };
+        let help_line = if self.status.is_empty() {
+            help_text.to_string()
+        } else {
+            format!("{}  |  {help_text}", self.status)
+        };
+        frame.render_widget(
+            Paragraph::new(help_line).style(Style::new().add_modifier(Modifier::DIM)),
+            help,
+        );
+
+        let popup = centered(frame.area(), 70, 70);
+        match &self.screen {
+            Screen::Editor(editor) => draw_editor(frame, popup, editor),
+            Screen::NewTopic(name) => {
+                let area = centered(frame.area(), 50, 20);
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    Paragraph::new(format!("{name}_")).block(Block::bordered().title("New topic")),
+                    area,
+                );
+            }
+            Screen::Quiz(quiz) => self.draw_quiz(frame, popup, quiz),
+            Screen::Browse | Screen::Search => {}
+        }
+    }
+
+    fn draw_browser(&mut self, frame: &mut Frame, area: Rect) {
+        let [sidebar, main] =
+            Layout::horizontal([Constraint::Length(24), Constraint::Min(0)]).areas(area);
+        let [search, list, preview] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(8),
+        ])
+        .areas(main);
+
+        let focused = |focus: Focus| {
+            if self.focus == focus {
+                Style::new().fg(Color::Yellow)
+            } else {
+                Style::new()
+            }
+        };
+        let highlight = Style::new().add_modifier(Modifier::REVERSED);
+
+        let topics: Vec<ListItem> = std::iter::once(ListItem::new("All cards"))
+            .chain(self.app.study.topic_tree().into_iter().map(|(key, depth)| {
+                let name = &self.app.study.topics[key].content;
+                ListItem::new(format!("{}{name}", "  ".repeat(depth)))
+            }))
+            .collect();
+        frame.render_stateful_widget(
+            List::new(topics)
+                .block(
+                    Block::bordered()
+                        .title("Topics")
+                        .border_style(focused(Focus::Topics)),
+                )
+                .highlight_style(highlight),
+            sidebar,
+            &mut self.topic_state,
+        );
+
+        let searching = matches!(self.screen, Screen::Search);
+        frame.render_widget(
+            Paragraph::new(if searching {
+                format!("{}_", self.search)
+            } else {
+                self.search.clone()
+            })
+            .block(
+                Block::bordered()
+                    .title("Search")
+                    .border_style(if searching {
+                        Style::new().fg(Color::Yellow)
+                    } else {
+                        Style::new()
+                    }),
+            ),
+            search,
+        );
+
+        let now = unix_now();
+        let cards: Vec<ListItem> = self
+            .visible_cards()
+            .into_iter()
+            .map(|key| {
+                let card = &self.app.study.cards[key];
+                let mut marks = String::new();
+                if card.suspended {
+                    marks.push_str("[suspended] ");
+                }
+                if card.is_buried(now) {
+                    marks.push_str("[buried] ");
+                }
+                if let Some(flag) = card.flag {
+                    marks.push_str(&format!("[{flag}] "));
+                }
+                ListItem::new(format!("{marks}{}", card.question.replace('\n', " ")))
+            })
+            .collect();
+        frame.render_stateful_widget(
+            List::new(cards)
+                .block(
+                    Block::bordered()
+                        .title("Cards")
+                        .border_style(focused(Focus::Cards)),
+                )
+                .highlight_style(highlight),
+            list,
+            &mut self.card_state,
+        );
+
+        let preview_text = match self.selected_card() {
+            Some(key) => {
+                let card = &self.app.study.cards[key];
+                Text::from(vec![
+                    Line::from(format!("Q: {}", card.question)),
+                    Line::from(format!("A: {}", card.answer)),
+                ])
+            }
+            None => Text::from("No card selected"),
+        };
+        frame.render_widget(
+            Paragraph::new(preview_text)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title("Card")),
+            preview,
+        );
+    }
+
+    fn draw_quiz(&self, frame: &mut Frame, area: Rect, quiz: &QuizScreen) {
+        frame.render_widget(Clear, area);
+        let title = format!(
+            "Quiz  {} left, {}/{} known",
+            quiz.queue.len(),
+            quiz.correct,
+            quiz.total
+        );
+        let Some(&card_key) = quiz.queue.front() else {
+            frame.render_widget(
+                Paragraph::new(format!(
+                    "Finished! You knew {} of {} cards at the first try.",
+                    quiz.correct, quiz.total
+                ))
+                .block(Block::bordered().title(title)),
+                area,
+            );
+            return;
+        };
+        let card = &self.app.study.cards[card_key];
+        let [card_area, answer_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(area);
+
+        let mut lines = vec![Line::from(card.question.clone()), Line::from("")];
+        if quiz.flipped {
+            let color = match quiz.verdict {
+                Some(true) => Color::Green,
+                Some(false) => Color::Red,
+                None => Color::Reset,
+            };
+            lines.push(Line::styled(card.answer.clone(), Style::new().fg(color)));
+        }
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(title)),
+            card_area,
+        );
+        frame.render_widget(
+            Paragraph::new(format!("{}_", quiz.typed))
+                .block(Block::bordered().title("Your answer")),
+            answer_area,
+        );
+    }
+}
+
+fn draw_editor(frame: &mut Frame, area: Rect, editor: &Editor) {
+    frame.render_widget(Clear, area);
+    let title = if editor.card.is_some() {
+        "Edit card"
+    } else {
+        "New card"
+    };
+    let block = Block::bordered().title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let areas = Layout::vertical([
+        Constraint::Min(3),
+        Constraint::Min(3),
+        Constraint::Length(3),
+    ])
+    .split(inner);
+    for (index, (name, value)) in EDITOR_FIELDS.iter().zip(&editor.fields).enumerate() {
+        let (text, style) = if index == editor.field {
+            (format!("{value}_"), Style::new().fg(Color::Yellow))
+        } else {
+            (value.clone(), Style::new())
+        };
+        frame.render_widget(
+            Paragraph::new(text)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(*name).border_style(style)),
+            areas[index],
+        );
+    }
+}
+
+// A rectangle in the middle of `area` taking the given percentages of its size.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [_, middle, _] = Layout::vertical([
+        Constraint::Percentage((100 - height) / 2),
+        Constraint::Percentage(height),
+        Constraint::Percentage((100 - height) / 2),
+    ])
+    .areas(area);
+    let [_, center, _] = Layout::horizontal([
+        Constraint::Percentage((100 - width) / 2),
+        Constraint::Percentage(width),
+        Constraint::Percentage((100 - width) / 2),
+    ])
+    .areas(middle);
+    center
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{app, MockInput};
+    use flashcard_core::leech::LeechSettings;
+
+    fn press(tui: &mut Tui<MockInput>, code: KeyCode) {
+        tui.handle_key(KeyEvent::from(code));
+    }
+
+    fn type_text(tui: &mut Tui<MockInput>, text: &str) {
+        for character in text.chars() {
+            press(tui, KeyCode::Char(character));
+        }
+    }
+
+    #[test]
+    fn writes_and_searches_cards() {
+        let mut tui = Tui::new(app(vec![]));
+        tui.app.create_topic("Math".to_string());
+        for (question, answer) in [("2+2?", "4"), ("Capital of France?", "Paris")] {
+            press(&mut tui, KeyCode::Char('n'));
+            type_text(&mut tui, question);
+            press(&mut tui, KeyCode::Tab);
+            type_text(&mut tui, answer);
+            press(&mut tui, KeyCode::Tab);
+            type_text(&mut tui, "math");
+            press(&mut tui, KeyCode::Enter);
+        }
+        assert_eq!(tui.app.study.cards.len(), 2);
+        let math = tui.app.study.topic_by_name("Math").unwrap();
+        assert_eq!(tui.app.study.topics[math].qna.len(), 2);
+
+        press(&mut tui, KeyCode::Char('/'));
+        type_text(&mut tui, "paris");
+        press(&mut tui, KeyCode::Enter);
+        let visible = tui.visible_cards();
+        assert_eq!(visible.len(), 1);
+        assert_eq!(
+            tui.app.study.cards[visible[0]].question,
+            "Capital of France?"
+        );
+
+        let backend = ratatui::backend::TestBackend::new(80, 24);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        terminal.draw(|frame| tui.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Q: Capital of France?"));
+    }
+
+    #[test]
+    fn quiz_requeues_missed_cards() {
+        let mut tui = Tui::new(app(vec![]));
+        tui.app.make_card("1+1?".to_string(), "2".to_string(), "");
+        tui.app.make_card("2+2?".to_string(), "4".to_string(), "");
+
+        press(&mut tui, KeyCode::Char('s'));
+        type_text(&mut tui, "2");
+        press(&mut tui, KeyCode::Enter);
+        press(&mut tui, KeyCode::Enter);
+        type_text(&mut tui, "5");
+        press(&mut tui, KeyCode::Enter);
+        press(&mut tui, KeyCode::Enter);
+        // The missed card comes back; flipping it and grading it as known finishes the quiz.
+        press(&mut tui, KeyCode::Tab);
+        press(&mut tui, KeyCode::Char('g'));
+
+        let Screen::Quiz(quiz) = &tui.screen else {
+            panic!("the quiz should show its results");
+        };
+        assert!(quiz.queue.is_empty());
+        assert_eq!((quiz.correct, quiz.total), (1, 2));
+    }
+
+    #[test]
+    fn tells_when_a_card_becomes_a_leech() {
+        let mut tui = Tui::new(app(vec![]));
+        tui.app.study.leech_settings = LeechSettings {
+            threshold: 1,
+            suspend: true,
+        };
+        tui.app.make_card("1+1?".to_string(), "2".to_string(), "");
+
+        press(&mut tui, KeyCode::Char('s'));
+        for _ in 0..2 {
+            type_text(&mut tui, "3");
+            press(&mut tui, KeyCode::Enter);
+            press(&mut tui, KeyCode::Enter);
+        }
+        assert_eq!(
+            tui.status,
+            "This card is now a leech and was suspended, rewrite it"
+        );
+        let Screen::Quiz(quiz) = &tui.screen else {
+            panic!("the quiz should show its results");
+        };
+        assert!(quiz.queue.is_empty());
+    }
+
+    #[test]
+    fn undoes_edits_by_name() {
+        let mut tui = Tui::new(app(vec![]));
+        press(&mut tui, KeyCode::Char('n'));
+        type_text(&mut tui, "2+2?");
+        // Typing is not recorded, only saving the card.
+        assert!(!tui.app.history.can_undo());
+        press(&mut tui, KeyCode::Tab);
+        press(&mut tui, KeyCode::Tab);
+        press(&mut tui, KeyCode::Enter);
+        press(&mut tui, KeyCode::Char('x'));
+        assert!(tui.app.study.cards.values().all(|card| card.suspended));
+
+        let undo = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        tui.handle_key(undo);
+        assert_eq!(tui.status, "Undid suspend card");
+        tui.handle_key(undo);
+        assert_eq!(tui.status, "Undid add card");
+        assert!(tui.app.study.cards.is_empty());
+    }
+}
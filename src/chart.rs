@@ -0,0 +1,155 @@
+//! Charts of the review log, drawn on a canvas for the statistics popup.
+use flashcard_core::forecast::ForecastDay;
+use flashcard_core::stats::Day;
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke};
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme};
+
+const BAR_COLOR: Color = Color::from_rgb(0.35, 0.6, 0.95);
+const FORECAST_COLOR: Color = Color::from_rgb(0.55, 0.8, 0.45);
+const LINE_COLOR: Color = Color::from_rgb(1.0, 0.75, 0.2);
+const EMPTY_COLOR: Color = Color::from_rgb(0.22, 0.22, 0.22);
+
+// The side of a heatmap cell and the gap between cells.
+const CELL: f32 = 11.0;
+const GAP: f32 = 3.0;
+
+/// Bars scaled to the highest bar, with an optional line of shares between 0 and 1 drawn over
+/// them. A point of the line that is None leaves a gap.
+pub struct BarChart {
+    pub bars: Vec<f32>,
+    pub line: Vec<Option<f32>>,
+    pub color: Color,
+}
+
+impl BarChart {
+    /// Charts the reviews of every day as bars and the accuracy of every day as a line.
+    pub fn activity(days: &[Day]) -> Self {
+        Self {
+            bars: days.iter().map(|day| day.reviews as f32).collect(),
+            line: days.iter().map(Day::accuracy).collect(),
+            color: BAR_COLOR,
+        }
+    }
+
+    /// Charts every review of every day of a forecast as bars.
+    pub fn forecast(days: &[ForecastDay]) -> Self {
+        Self {
+            bars: days.iter().map(|day| day.total() as f32).collect(),
+            line: vec![],
+            color: FORECAST_COLOR,
+        }
+    }
+}
+
+impl<Message> canvas::Program<Message> for BarChart {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        frame.fill_rectangle(Point::ORIGIN, bounds.size(), EMPTY_COLOR);
+        if self.bars.is_empty() {
+            return vec![frame.into_geometry()];
+        }
+        let slot = bounds.width / self.bars.len() as f32;
+        let highest = self.bars.iter().copied().fold(1.0, f32::max);
+        for (i, &bar) in self.bars.iter().enumerate() {
+            let height = bar / highest * (bounds.height - 4.0);
+            frame.fill_rectangle(
+                Point::new(i as f32 * slot + 1.0, bounds.height - height),
+                Size::new((slot - 2.0).max(1.0), height),
+                self.color,
+            );
+        }
+        // The line is broken wherever there is no point.
+        let line = Path::new(|builder| {
+            let mut drawing = false;
+            for (i, point) in self.line.iter().enumerate() {
+                let Some(share) = point else {
+                    drawing = false;
+                    continue;
+                };
+                let point = Point::new(
+                    (i as f32 + 0.5) * slot,
+                    bounds.height - 2.0 - share * (bounds.height - 4.0),
+                );
+                if drawing {
+                    builder.line_to(point);
+                } else {
+                    builder.move_to(point);
+                    drawing = true;
+                }
+            }
+        });
+        frame.stroke(
+            &line,
+            Stroke::default().with_color(LINE_COLOR).with_width(2.0),
+        );
+        vec![frame.into_geometry()]
+    }
+}
+
+/// A calendar of the given days, one column per week from Monday to Sunday, with each day
+/// shaded by how many reviews were done on it.
+pub struct Heatmap {
+    pub days: Vec<Day>,
+}
+
+impl Heatmap {
+    /// Returns the size the calendar needs.
+    pub fn size(days: usize) -> Size {
+        let weeks = days.div_ceil(7) + 1;
+        Size::new(weeks as f32 * (CELL + GAP), 7.0 * (CELL + GAP))
+    }
+}
+
+impl<Message> canvas::Program<Message> for Heatmap {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let Some(first) = self.days.first() else {
+            return vec![frame.into_geometry()];
+        };
+        let busiest = self
+            .days
+            .iter()
+            .map(|day| day.reviews)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        // The unix epoch was a Thursday, so this counts from Monday as 0.
+        let weekday = |day: u64| (day + 3) % 7;
+        let first_monday = first.day - weekday(first.day);
+        for day in &self.days {
+            let column = ((day.day - first_monday) / 7) as f32;
+            let row = weekday(day.day) as f32;
+            let color = if day.reviews == 0 {
+                EMPTY_COLOR
+            } else {
+                let strength = 0.25 + 0.75 * day.reviews as f32 / busiest as f32;
+                Color::from_rgb(0.1, 0.3 + 0.6 * strength, 0.2)
+            };
+            frame.fill_rectangle(
+                Point::new(column * (CELL + GAP), row * (CELL + GAP)),
+                Size::new(CELL, CELL),
+                color,
+            );
+        }
+        vec![frame.into_geometry()]
+    }
+}
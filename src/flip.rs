@@ -0,0 +1,396 @@
+//! A flip card widget shows one of two sides of a card and turns over when it is toggled.
+//!
+//! The turn is drawn as the card narrowing to nothing and widening again with the other side up,
+//! darkening as it turns away, which reads as a flip around the vertical axis. The card is coloured
+//! with the background colour of the flashcard: a strip along the top of both sides, and a light
+//! tint on the back.
+//!
+//! # Example
+//! ```ignore
+//! FlipCard::new(Text::new("Question"), Text::new("Answer"), self.revealed)
+//!     .bg_color(card_color)
+//!     .key(card.id.as_u128())
+//! ```
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::widget::{tree, Operation, Tree};
+use iced::advanced::{overlay, renderer, Clipboard, Shell, Widget};
+use iced::event::{self, Event};
+use iced::{
+    border, mouse, window, Background, Color, Element, Length, Point, Rectangle, Shadow, Size,
+    Vector,
+};
+
+// How long a flip takes.
+const FLIP_DURATION: Duration = Duration::from_millis(450);
+// The height of the coloured strip and the space around the content.
+const HEAD: f32 = 28.0;
+const PADDING: f32 = 12.0;
+
+/// A card with a front and a back that flips over when `flipped` changes.
+///
+/// The card only flips when it shows the same card as before; when its key changes it shows the
+/// new card straight away, so the answer of the next card is never seen during a flip.
+#[allow(missing_debug_implementations)]
+pub struct FlipCard<'a, Message, Theme = iced::Theme, Renderer = iced::Renderer>
+where
+    Renderer: iced::advanced::Renderer,
+{
+    front: Element<'a, Message, Theme, Renderer>,
+    back: Element<'a, Message, Theme, Renderer>,
+    flipped: bool,
+    width: f32,
+    height: f32,
+    background_color: Color,
+    border_radius: f32,
+    key: u64,
+}
+
+impl<'a, Message, Theme, Renderer> FlipCard<'a, Message, Theme, Renderer>
+where
+    Renderer: iced::advanced::Renderer,
+{
+    /// Creates a [`FlipCard`] showing `back` when `flipped` is set and `front` otherwise.
+    pub fn new(
+        front: impl Into<Element<'a, Message, Theme, Renderer>>,
+        back: impl Into<Element<'a, Message, Theme, Renderer>>,
+        flipped: bool,
+    ) -> Self {
+        Self {
+            front: front.into(),
+            back: back.into(),
+            flipped,
+            width: 400.0,
+            height: 220.0,
+            background_color: Color::from_rgb8(255, 0, 0),
+            border_radius: 10.0,
+            key: 0,
+        }
+    }
+
+    /// Sets the size of the [`FlipCard`].
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets the colour of the [`FlipCard`], usually the background colour of the flashcard.
+    pub fn bg_color(mut self, color: Color) -> Self {
+        self.background_color = color;
+        self
+    }
+
+    /// Sets the border radius of the [`FlipCard`].
+    pub fn border_radius(mut self, border_radius: f32) -> Self {
+        self.border_radius = border_radius;
+        self
+    }
+
+    /// Sets what card the [`FlipCard`] shows, such as the id of a flashcard.
+    pub fn key(mut self, key: impl Hash) -> Self {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.key = hasher.finish();
+        self
+    }
+
+    // The side that is up, and the index of its child.
+    fn side(&self) -> usize {
+        usize::from(self.flipped)
+    }
+
+    fn content(&self) -> &Element<'a, Message, Theme, Renderer> {
+        if self.flipped {
+            &self.back
+        } else {
+            &self.front
+        }
+    }
+
+    fn content_mut(&mut self) -> &mut Element<'a, Message, Theme, Renderer> {
+        if self.flipped {
+            &mut self.back
+        } else {
+            &mut self.front
+        }
+    }
+}
+
+// The side the card was last seen on, the card it showed, when it started turning and the time of
+// the last frame.
+struct State {
+    flipped: bool,
+    key: u64,
+    started: Option<Instant>,
+    now: Instant,
+}
+
+impl State {
+    // How far the flip has got, from 0 to 1. A card that is not turning is done.
+    fn progress(&self) -> f32 {
+        self.started.map_or(1.0, |started| {
+            (self.now.saturating_duration_since(started).as_secs_f32()
+                / FLIP_DURATION.as_secs_f32())
+            .min(1.0)
+        })
+    }
+}
+
+impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer>
+    for FlipCard<'_, Message, Theme, Renderer>
+where
+    Renderer: iced::advanced::Renderer,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State {
+            flipped: self.flipped,
+            key: self.key,
+            started: None,
+            now: Instant::now(),
+        })
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.front), Tree::new(&self.back)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(&[&self.front, &self.back]);
+    }
+
+    fn size(&self) -> Size<Length> {
+        Size {
+            width: Length::Fixed(self.width),
+            height: Length::Fixed(self.height),
+        }
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        _limits: &layout::Limits,
+    ) -> layout::Node {
+        let inner = Size::new(
+            self.width - 2.0 * PADDING,
+            self.height - HEAD - 2.0 * PADDING,
+        );
+        let limits = layout::Limits::new(Size::ZERO, inner);
+        let children = [&self.front, &self.back]
+            .into_iter()
+            .zip(&mut tree.children)
+            .map(|(side, tree)| {
+                side.as_widget()
+                    .layout(tree, renderer, &limits)
+                    .move_to(Point::new(PADDING, HEAD + PADDING))
+            })
+            .collect();
+        layout::Node::with_children(Size::new(self.width, self.height), children)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation,
+    ) {
+        let side = self.side();
+        self.content().as_widget().operate(
+            &mut tree.children[side],
+            layout.children().nth(side).unwrap(),
+            renderer,
+            operation,
+        );
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        if let Event::Window(window::Event::RedrawRequested(now)) = event {
+            let state = tree.state.downcast_mut::<State>();
+            state.now = now;
+            if state.key != self.key {
+                // Another card is shown, so there is nothing to turn over.
+                state.key = self.key;
+                state.flipped = self.flipped;
+                state.started = None;
+            } else if state.flipped != self.flipped {
+                state.flipped = self.flipped;
+                state.started = Some(now);
+            }
+            if state.progress() < 1.0 {
+                shell.request_redraw(window::RedrawRequest::NextFrame);
+            }
+        }
+        // The content cannot be used while it is turning.
+        if tree.state.downcast_ref::<State>().progress() < 1.0 {
+            return event::Status::Ignored;
+        }
+        let side = self.side();
+        self.content_mut().as_widget_mut().on_event(
+            &mut tree.children[side],
+            event,
+            layout.children().nth(side).unwrap(),
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let side = self.side();
+        self.content().as_widget().mouse_interaction(
+            &tree.children[side],
+            layout.children().nth(side).unwrap(),
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let progress = tree.state.downcast_ref::<State>().progress();
+        // The card turns a half turn; until it is edge on the side it turns away from is up.
+        let angle = progress * std::f32::consts::PI;
+        let (side, showing_back) = if progress < 0.5 {
+            (1 - self.side(), !self.flipped)
+        } else {
+            (self.side(), self.flipped)
+        };
+        let width = bounds.width * angle.cos().abs();
+        let face = Rectangle {
+            x: bounds.center_x() - width / 2.0,
+            width,
+            ..bounds
+        };
+        if face.width < 1.0 {
+            return;
+        }
+
+        let body = if showing_back {
+            mix(self.background_color, Color::WHITE, 0.85)
+        } else {
+            Color::WHITE
+        };
+        let quad = |bounds, radius: border::Radius| renderer::Quad {
+            bounds,
+            border: border::rounded(radius),
+            shadow: Shadow::default(),
+        };
+        let radius = self.border_radius;
+        renderer.fill_quad(quad(face, radius.into()), Background::Color(body));
+        renderer.fill_quad(
+            quad(
+                Rectangle {
+                    height: HEAD,
+                    ..face
+                },
+                border::Radius::new(0.0).top(radius),
+            ),
+            Background::Color(self.background_color),
+        );
+
+        let content = if side == 1 { &self.back } else { &self.front };
+        if let Some(clip) = face.intersection(viewport) {
+            renderer.with_layer(clip, |renderer| {
+                content.as_widget().draw(
+                    &tree.children[side],
+                    renderer,
+                    theme,
+                    style,
+                    layout.children().nth(side).unwrap(),
+                    cursor,
+                    &clip,
+                );
+            });
+        }
+
+        // The card darkens as it turns edge on.
+        let shade = angle.sin() * 0.45;
+        if shade > 0.0 {
+            renderer.with_layer(face, |renderer| {
+                renderer.fill_quad(
+                    quad(face, radius.into()),
+                    Background::Color(Color::BLACK.scale_alpha(shade)),
+                );
+            });
+        }
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        let side = self.side();
+        let content = if self.flipped {
+            &mut self.back
+        } else {
+            &mut self.front
+        };
+        content.as_widget_mut().overlay(
+            &mut tree.children[side],
+            layout.children().nth(side).unwrap(),
+            renderer,
+            translation,
+        )
+    }
+}
+
+// Mixes two colours, taking `amount` of the second.
+fn mix(a: Color, b: Color, amount: f32) -> Color {
+    Color::from_rgb(
+        a.r + (b.r - a.r) * amount,
+        a.g + (b.g - a.g) * amount,
+        a.b + (b.b - a.b) * amount,
+    )
+}
+
+impl<'a, Message, Theme, Renderer> From<FlipCard<'a, Message, Theme, Renderer>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Message: 'a,
+    Theme: 'a,
+    Renderer: iced::advanced::Renderer + 'a,
+{
+    fn from(card: FlipCard<'a, Message, Theme, Renderer>) -> Element<'a, Message, Theme, Renderer> {
+        Element::new(card)
+    }
+}
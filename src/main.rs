@@ -1,27 +1,44 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use iced::{
     advanced::graphics::core::Element,
     alignment,
     widget::{
-        button, center, column, container, mouse_area, opaque, row,
+        button, canvas, center, checkbox, column, container, mouse_area, opaque, pick_list, row,
         scrollable::{self, Rail, Scroller},
         stack, text_input, Button, Column, Container, Row, Scrollable, Space, Text,
     },
-    Alignment, Background, Border, Color,
+    keyboard, time, Alignment, Background, Border, Color, Subscription, Task,
     Length::{self}, Renderer, Theme,
 };
 
-use iced_aw::{card, color_picker, style};
-use crate::quiz::Question;
-use quiz::{Flashcard, FlashcardKey, Quiz, Topic, TopicKey, TopicTag, Study};
-use slotmap::new_key_type;
-use slotmap::SlotMap;
+use iced_aw::{card, color_picker};
+use flashcard_core::quiz::{
+    Flashcard, FlashcardKey, Namespace, NamespaceKey, NamespaceOperation, Question, Quiz, Study,
+    Topic, TopicKey, unix_now,
+};
+use flashcard_core::auto::{self, Rule, Suggestion};
+use flashcard_core::exam::{self, ExamReport, TimeLimit};
+use flashcard_core::history::{History, Snapshot};
+use flashcard_core::order::Order;
+use flashcard_core::query::Query;
+use flashcard_core::random::Rng;
+use flashcard_core::review::{Grade, GRADES};
+use flashcard_core::search::SearchIndex;
+use flashcard_core::state::{Filter, Flag};
+use flashcard_core::{forecast, stats};
+use flashcard_core::{bundle, deck, html, import, pdf};
 
+mod chart;
+mod flip;
+mod markup;
 mod pin;
-mod quiz;
 mod rectangle;
 
+use chart::{BarChart, Heatmap};
+use flip::FlipCard;
 use pin::Pin;
 use rectangle::RoundedRectangle;
 
@@ -30,7 +47,9 @@ use rectangle::RoundedRectangle;
 pub fn main() -> iced::Result {
 //ee;
 
-iced::run("Title", App::update, App::view)
+iced::application("Title", App::update, App::view)
+    .subscription(App::subscription)
+    .run_with(|| (App::new(), Task::none()))
 }
 
 #[derive(Debug, Default, Clone)]
@@ -39,9 +58,20 @@ enum Popups {
     Quiz,
     ColorPicker,
     Text,
+    Image,
     StartQuiz(VecDeque<Question>),
     Topics,
     Configure,
+    Import,
+    Export,
+    Suggest,
+    Namespaces,
+    Stats,
+    Leeches,
+    // A flip review shows its card in place of the new card, without a popup.
+    Review,
+    Exam,
+    ExamReport(ExamReport),
     #[default]
     None,
 }
@@ -49,7 +79,7 @@ enum Popups {
 #[derive(Default)]
 struct App {
     current_popup: Popups,
-    current_quiz: quiz::Quiz,
+    current_quiz: Quiz,
     current_card: Flashcard,
     study_session: Study,
     //cards: SlotMap<FlashcardKey, Flashcard>,
@@ -58,13 +88,245 @@ struct App {
     quiz: Quiz,
     //staging_topic: String,
 
-    expand_questions: bool,
-    expand_answers: bool,
+    // Whether the preview of the new card shows its answer.
+    preview_flipped: bool,
+
+    import_path: String,
+    import_status: String,
+    export_path: String,
+    export_status: String,
+    // The topics of the namespace being exported. The enabled topics are exported when it is not
+    // set.
+    export_topics: Option<Vec<TopicKey>>,
+
+    deck_path: PathBuf,
+
+    suggestions: Vec<(Suggestion, bool)>,
+    rule_topic: String,
+    rule_text: String,
+    suggest_status: String,
+
+    // The namespace the topic lists are filtered by, all topics are listed when it is None.
+    namespace: Option<NamespaceKey>,
+    move_target: Option<NamespaceKey>,
+    namespace_name: String,
+    // The namespace being renamed and its new name as typed, saved when Enter is pressed.
+    namespace_rename: Option<(NamespaceKey, String)>,
+    namespace_color_picker: Option<NamespaceKey>,
+
+    // New topics are put under the parent, and so is the topic picked to be moved.
+    topic_parent: Option<TopicKey>,
+    reparent_topic: Option<TopicKey>,
+    topic_status: String,
+    // The query of a smart topic being made; the topic is named by the staging topic.
+    smart_query: String,
+
+    // Every edit to the study session, so it can be undone with Ctrl+Z and redone with
+    // Ctrl+Shift+Z.
+    history: History,
+
+    // The card browser: the column the cards are sorted by, the ticked cards, the card being
+    // edited in place with its new question and answer, and the topic for bulk assignment.
+    card_sort: CardColumn,
+    card_sort_descending: bool,
+    selected_cards: HashSet<FlashcardKey>,
+    card_edit: Option<(FlashcardKey, String, String)>,
+    bulk_topic: Option<TopicKey>,
+    cards_color_picker: bool,
+    // The browser lists the matching cards, best match first, while there is a search.
+    card_search: String,
+    search_index: SearchIndex,
+    // Only the cards in this state are listed when it is set.
+    card_filter: Option<Filter>,
+
+    // The answer being typed to the question at the front of the quiz, when that question was
+    // shown, and whether the last answer was right.
+    quiz_answer: String,
+    question_shown: Option<Instant>,
+    quiz_feedback: String,
+    // Whether the answer of the card in a flip review is showing, and how long it took to show it.
+    answer_revealed: bool,
+    reveal_ms: u64,
+    // The question count and time limit of the next exam as typed, and the time of the last tick
+    // of the exam countdown.
+    exam_questions: String,
+    exam_limit: String,
+    exam_clock: Option<Instant>,
+    // The seed shuffled quizzes are asked with as typed. A new one is picked when it is empty.
+    quiz_seed: String,
+
+    // The days the forecast of the statistics popup looks ahead, and the new cards per day it
+    // simulates as typed.
+    forecast_days: usize,
+    new_per_day: String,
+
+    // The lapse threshold for leeches as typed.
+    leech_threshold: String,
+}
+
+// A column of the card browser.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum CardColumn {
+    #[default]
+    Question,
+    Answer,
+    Topics,
+    Colour,
+}
+
+// An entry of a namespace or topic pick list. Pick lists need their options to be displayable,
+// so the name is kept next to the key.
+#[derive(Debug, Clone, PartialEq)]
+struct Choice<K> {
+    key: Option<K>,
+    name: String,
+}
+
+impl<K> std::fmt::Display for Choice<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Returns the namespaces in order as pick list options, starting with an entry for all topics
+/// when `all` is set.
+fn namespace_choices(study: &Study, all: bool) -> Vec<Choice<NamespaceKey>> {
+    let all = all.then(|| Choice {
+        key: None,
+        name: "All topics".to_string(),
+    });
+    all.into_iter()
+        .chain(study.ordered_namespaces().into_iter().map(|key| Choice {
+            key: Some(key),
+            name: study.namespaces[key].name.clone(),
+        }))
+        .collect()
+}
+
+/// Returns the pick list option for `key`.
+fn namespace_choice(study: &Study, key: Option<NamespaceKey>) -> Option<Choice<NamespaceKey>> {
+    namespace_choices(study, true)
+        .into_iter()
+        .find(|choice| choice.key == key)
+}
+
+/// Returns the topics in tree order as pick list options named by their path, starting with an
+/// entry for no topic.
+fn topic_choices(study: &Study) -> Vec<Choice<TopicKey>> {
+    let none = Choice {
+        key: None,
+        name: "No topic".to_string(),
+    };
+    std::iter::once(none)
+        .chain(study.topic_tree().into_iter().map(|(key, _)| Choice {
+            key: Some(key),
+            name: study.topic_path(key),
+        }))
+        .collect()
+}
+
+/// Returns the names of the topics of a card, split by commas.
+fn card_topic_names(study: &Study, key: FlashcardKey) -> String {
+    let names: Vec<&str> = study.cards[key]
+        .topics
+        .iter()
+        .filter_map(|&topic| study.topics.get(topic))
+        .map(|topic| topic.content.as_str())
+        .collect();
+    names.join(", ")
+}
+
+/// Returns every card sorted by `column`, ignoring case. Cards without a colour come first.
+fn sorted_cards(study: &Study, column: CardColumn, descending: bool) -> Vec<FlashcardKey> {
+    let mut keys: Vec<FlashcardKey> = study.cards.keys().collect();
+    keys.sort_by_cached_key(|&key| {
+        let card = &study.cards[key];
+        match column {
+            CardColumn::Question => card.question.to_lowercase(),
+            CardColumn::Answer => card.answer.to_lowercase(),
+            CardColumn::Topics => card_topic_names(study, key).to_lowercase(),
+            CardColumn::Colour => card.bg_color.map(|color| color.to_hex()).unwrap_or_default(),
+        }
+    });
+    if descending {
+        keys.reverse();
+    }
+    keys
+}
+
+/// Returns the card states as pick list options, starting with an entry for all cards.
+fn filter_choices() -> Vec<Choice<Filter>> {
+    let all = Choice {
+        key: None,
+        name: "All cards".to_string(),
+    };
+    std::iter::once(all)
+        .chain(Filter::all().into_iter().map(|filter| Choice {
+            key: Some(filter),
+            name: filter.to_string(),
+        }))
+        .collect()
+}
+
+/// Returns the flags as pick list options, starting with an entry for no flag.
+fn flag_choices() -> Vec<Choice<Flag>> {
+    let none = Choice {
+        key: None,
+        name: "No flag".to_string(),
+    };
+    std::iter::once(none)
+        .chain(Flag::ALL.into_iter().map(|flag| Choice {
+            key: Some(flag),
+            name: flag.to_string(),
+        }))
+        .collect()
+}
+
+/// Returns the orders a quiz can ask its cards in as pick list options.
+fn order_choices() -> Vec<Choice<Order>> {
+    Order::ALL
+        .into_iter()
+        .map(|order| Choice {
+            key: Some(order),
+            name: order.describe().to_string(),
+        })
+        .collect()
+}
+
+/// Returns the states of a card as short text, such as "suspended, red".
+fn card_states(card: &Flashcard, now: u64) -> String {
+    let mut states = vec![];
+    if card.suspended {
+        states.push("suspended".to_string());
+    }
+    if card.is_buried(now) {
+        states.push("buried".to_string());
+    }
+    states.extend(card.flag.map(|flag| flag.to_string()));
+    states.join(", ")
+}
+
+/// Returns the text of the button grading a card.
+fn grade_label(grade: Grade) -> &'static str {
+    match grade {
+        Grade::Again => "Again",
+        Grade::Hard => "Hard",
+        Grade::Good => "Good",
+        Grade::Easy => "Easy",
+    }
+}
+
+/// Returns the pick list option for `key`.
+fn topic_choice(study: &Study, key: Option<TopicKey>) -> Option<Choice<TopicKey>> {
+    topic_choices(study)
+        .into_iter()
+        .find(|choice| choice.key == key)
 }
 
 
 
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 enum Message {
     Flashcards,
     Quiz,
@@ -85,21 +347,392 @@ enum Message {
     StartQuiz,
     AnswerChanged(String),
     QuestionChanged(String),
-    ExpandQuestions,
-    ExpandAnswers,
+    ImageChanged(String),
+    FlipPreview,
     Text,
+    Image,
     Topics,
+    Import,
+    ImportPathChanged(String),
+    SubmitImport,
+    Export,
+    ExportPathChanged(String),
+    ExportPdf,
+    ExportHtml,
+    ExportBundle,
+    Save,
+    SuggestTopics,
+    RuleTopicChanged(String),
+    RuleChanged(String),
+    AddRule,
+    ToggleSuggestion(usize, bool),
+    ApplySuggestions,
+    NamespaceSelected(Choice<NamespaceKey>),
+    MoveTargetSelected(Choice<NamespaceKey>),
+    NamespaceOperate(NamespaceOperation),
+    AddEnabledToNamespace,
+    Namespaces,
+    NamespaceNameChanged(String),
+    AddNamespace,
+    NamespaceRenameChanged(NamespaceKey, String),
+    RenameNamespace(NamespaceKey, String),
+    MoveNamespace(NamespaceKey, usize),
+    RemoveNamespace(NamespaceKey),
+    PickNamespaceColor(NamespaceKey),
+    CancelNamespaceColor,
+    SubmitNamespaceColor(Color),
+    TopicParentSelected(Choice<TopicKey>),
+    ReparentTopicSelected(Choice<TopicKey>),
+    ReparentTopic,
+    SmartQueryChanged(String),
+    AddSmartTopic,
+    IncludeSubtopics(bool),
+    Undo,
+    Redo,
+    CardSearchChanged(String),
+    SortCards(CardColumn),
+    ToggleCard(FlashcardKey, bool),
+    SelectAllCards(bool),
+    EditCard(FlashcardKey),
+    EditQuestionChanged(String),
+    EditAnswerChanged(String),
+    SaveCardEdit,
+    CancelCardEdit,
+    DeleteSelectedCards,
+    BulkTopicSelected(Choice<TopicKey>),
+    AddSelectedToTopic,
+    RemoveSelectedFromTopic,
+    PickCardsColor,
+    CancelCardsColor,
+    RecolorSelectedCards(Color),
+    CardFilterSelected(Choice<Filter>),
+    SuspendSelected(bool),
+    BurySelected(bool),
+    FlagSelected(Choice<Flag>),
+    QuizAnswerChanged(String),
+    SubmitQuizAnswer,
+    StartReview,
+    RevealAnswer,
+    GradeCard(Grade),
+    QuizOrderSelected(Choice<Order>),
+    QuizSeedChanged(String),
+    ExamQuestionsChanged(String),
+    ExamLimitChanged(String),
+    StartExam,
+    ExamTick(Instant),
+    SubmitExamAnswer,
+    FinishExam,
+    Stats,
+    ForecastDays(usize),
+    NewPerDayChanged(String),
+    Leeches,
+    LeechThresholdChanged(String),
+    SuspendLeeches(bool),
+    ForgiveLeech(FlashcardKey),
     None,
 }
 
+impl Message {
+    /// Returns the label the message is recorded under in the undo history, or None when the
+    /// message does not edit the study session. Answers in quizzes and exams are not edits, and
+    /// undoing an edit keeps the reviews given since.
+    fn edit_label(&self) -> Option<&'static str> {
+        Some(match self {
+            Message::SubmitCard(_) => "add card",
+            Message::SubmitTopic(_) => "add topic",
+            Message::SelectTopic(_) => "change card topics",
+            Message::SelectQuiz(_) => "toggle topic",
+            Message::SubmitColor(_) => "change colour",
+            Message::SubmitImport => "import",
+            Message::NamespaceOperate(_) => "namespace operation",
+            Message::AddEnabledToNamespace => "add topics to namespace",
+            Message::AddNamespace => "add namespace",
+            Message::RenameNamespace(..) => "rename namespace",
+            Message::MoveNamespace(..) => "move namespace",
+            Message::RemoveNamespace(_) => "delete namespace",
+            Message::SubmitNamespaceColor(_) => "change namespace colour",
+            Message::ReparentTopic => "move topic",
+            Message::AddSmartTopic => "add smart topic",
+            Message::AddRule => "add rule",
+            Message::ApplySuggestions => "apply suggestions",
+            Message::SaveCardEdit => "edit card",
+            Message::DeleteSelectedCards => "delete cards",
+            Message::AddSelectedToTopic => "add cards to topic",
+            Message::RemoveSelectedFromTopic => "remove cards from topic",
+            Message::RecolorSelectedCards(_) => "recolour cards",
+            Message::ForgiveLeech(_) => "forgive leech",
+            Message::SuspendSelected(_) => "suspend cards",
+            Message::BurySelected(_) => "bury cards",
+            Message::FlagSelected(_) => "flag cards",
+            _ => return None,
+        })
+    }
+}
+
 impl App {
-  
+    /// Opens the deck that is shared with the command line version.
+    fn new() -> Self {
+        let deck_path = deck::default_path();
+        let study_session = deck::load_or_default(&deck_path).unwrap_or_else(|error| {
+            println!("Could not open {}: {error}", deck_path.display());
+            Study::default()
+        });
+        Self {
+            search_index: SearchIndex::new(&study_session),
+            study_session,
+            deck_path,
+            forecast_days: 30,
+            exam_questions: "20".to_string(),
+            exam_limit: "20m".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Handles a message, recording it in the undo history when it edits the study session and
+    /// bringing the search index up to date with the edit.
     fn update(&mut self, message: Message) {
+        if matches!(message, Message::Undo | Message::Redo) {
+            self.handle(message);
+            self.search_index.refresh(&self.study_session);
+            return;
+        }
+        let Some(label) = message.edit_label() else {
+            return self.handle(message);
+        };
+        let before = Snapshot::take(&self.study_session);
+        self.handle(message);
+        for key in self.history.commit(label, before, &self.study_session) {
+            self.search_index.update_card(&self.study_session, key);
+        }
+    }
+
+    fn handle(&mut self, message: Message) {
         match message {
+            Message::Undo => {
+                if let Some(label) = self.history.undo(&mut self.study_session) {
+                    println!("Undid {label}.");
+                }
+                self.forget_missing_cards();
+            }
+            Message::Redo => {
+                if let Some(label) = self.history.redo(&mut self.study_session) {
+                    println!("Redid {label}.");
+                }
+                self.forget_missing_cards();
+            }
+            Message::CardSearchChanged(query) => self.card_search = query,
+            Message::SortCards(column) => {
+                // Sorting by the same column again flips the order.
+                self.card_sort_descending = self.card_sort == column && !self.card_sort_descending;
+                self.card_sort = column;
+            }
+            Message::ToggleCard(key, selected) => {
+                if selected {
+                    self.selected_cards.insert(key);
+                } else {
+                    self.selected_cards.remove(&key);
+                }
+            }
+            Message::SelectAllCards(selected) => {
+                self.selected_cards = if selected {
+                    self.study_session.cards.keys().collect()
+                } else {
+                    HashSet::new()
+                };
+            }
+            Message::EditCard(key) => {
+                let card = &self.study_session.cards[key];
+                self.card_edit = Some((key, card.question.clone(), card.answer.clone()));
+            }
+            Message::EditQuestionChanged(question) => {
+                if let Some((_, edited, _)) = &mut self.card_edit {
+                    *edited = question;
+                }
+            }
+            Message::EditAnswerChanged(answer) => {
+                if let Some((_, _, edited)) = &mut self.card_edit {
+                    *edited = answer;
+                }
+            }
+            Message::SaveCardEdit => {
+                if let Some((key, question, answer)) = self.card_edit.take() {
+                    if let Some(card) = self.study_session.cards.get_mut(key) {
+                        card.question = question;
+                        card.answer = answer;
+                    }
+                }
+            }
+            Message::CancelCardEdit => self.card_edit = None,
+            Message::DeleteSelectedCards => {
+                for key in self.selected_cards.drain() {
+                    self.study_session.remove_card(key);
+                }
+                println!("Deleted the selected cards.");
+                self.forget_missing_cards();
+            }
+            Message::CardFilterSelected(choice) => self.card_filter = choice.key,
+            Message::SuspendSelected(suspended) => {
+                for &key in &self.selected_cards {
+                    if let Some(card) = self.study_session.cards.get_mut(key) {
+                        card.suspended = suspended;
+                    }
+                }
+            }
+            Message::BurySelected(buried) => {
+                let now = unix_now();
+                for &key in &self.selected_cards {
+                    if let Some(card) = self.study_session.cards.get_mut(key) {
+                        if buried {
+                            card.bury(now);
+                        } else {
+                            card.buried_until = None;
+                        }
+                    }
+                }
+            }
+            Message::FlagSelected(choice) => {
+                for &key in &self.selected_cards {
+                    if let Some(card) = self.study_session.cards.get_mut(key) {
+                        card.flag = choice.key;
+                    }
+                }
+            }
+            Message::BulkTopicSelected(choice) => self.bulk_topic = choice.key,
+            Message::AddSelectedToTopic => {
+                if let Some(topic) = self.bulk_topic {
+                    for &key in &self.selected_cards {
+                        self.study_session.link(key, topic);
+                    }
+                }
+            }
+            Message::RemoveSelectedFromTopic => {
+                if let Some(topic) = self.bulk_topic {
+                    for &key in &self.selected_cards {
+                        self.study_session.unlink(key, topic);
+                    }
+                }
+            }
+            Message::PickCardsColor => self.cards_color_picker = true,
+            Message::CancelCardsColor => self.cards_color_picker = false,
+            Message::RecolorSelectedCards(color) => {
+                self.cards_color_picker = false;
+                for &key in &self.selected_cards {
+                    if let Some(card) = self.study_session.cards.get_mut(key) {
+                        card.bg_color = Some(color.into());
+                    }
+                }
+            }
+            Message::QuizOrderSelected(choice) => {
+                self.current_quiz.order = choice.key.unwrap_or_default();
+            }
+            Message::QuizSeedChanged(seed) => self.quiz_seed = seed,
             Message::StartQuiz => {
+                self.seed_quiz();
                 self.current_quiz.start_quiz(&self.study_session);
                 let questions = self.current_quiz.get_layout();
                 self.current_popup = Popups::StartQuiz(questions);
+                self.quiz_answer.clear();
+                self.quiz_feedback.clear();
+                self.question_shown = Some(Instant::now());
+            }
+            Message::StartReview => {
+                self.seed_quiz();
+                self.current_quiz.start_quiz(&self.study_session);
+                self.current_popup = Popups::Review;
+                self.answer_revealed = false;
+                self.question_shown = Some(Instant::now());
+            }
+            Message::RevealAnswer => {
+                self.answer_revealed = true;
+                self.reveal_ms = self
+                    .question_shown
+                    .map_or(0, |shown| shown.elapsed().as_millis() as u64);
+            }
+            Message::GradeCard(grade) => {
+                self.current_quiz
+                    .grade_current(&mut self.study_session, grade, self.reveal_ms);
+                self.answer_revealed = false;
+                self.question_shown = Some(Instant::now());
+            }
+            Message::ExamQuestionsChanged(count) => self.exam_questions = count,
+            Message::ExamLimitChanged(limit) => self.exam_limit = limit,
+            Message::StartExam => {
+                let (Ok(count), Ok(limit)) = (
+                    self.exam_questions.trim().parse(),
+                    self.exam_limit.parse::<TimeLimit>(),
+                ) else {
+                    return;
+                };
+                let now = Instant::now();
+                self.current_quiz.start_exam(
+                    &self.study_session,
+                    count,
+                    limit,
+                    Rng::time_seed(),
+                    now,
+                );
+                self.exam_clock = Some(now);
+                self.quiz_answer.clear();
+                self.current_popup = Popups::Exam;
+            }
+            Message::ExamTick(now) => {
+                self.exam_clock = Some(now);
+                let Some(exam) = &mut self.current_quiz.exam else {
+                    return;
+                };
+                // A question that ran out of time takes the answer being typed with it.
+                if exam.tick(now) {
+                    self.quiz_answer.clear();
+                }
+                if exam.is_finished() {
+                    self.update(Message::FinishExam);
+                }
+            }
+            Message::SubmitExamAnswer => {
+                let Some(exam) = &mut self.current_quiz.exam else {
+                    return;
+                };
+                exam.answer(std::mem::take(&mut self.quiz_answer), Instant::now());
+                if exam.is_finished() {
+                    self.update(Message::FinishExam);
+                }
+            }
+            Message::FinishExam => {
+                if let Some(report) = self
+                    .current_quiz
+                    .finish_exam(&mut self.study_session, Instant::now())
+                {
+                    self.current_popup = Popups::ExamReport(report);
+                }
+            }
+            Message::QuizAnswerChanged(answer) => self.quiz_answer = answer,
+            Message::SubmitQuizAnswer => {
+                let front = self.current_quiz.qna_queue.front();
+                let expected = front.map(|qna| qna.answer.clone());
+                let card = front.and_then(|qna| self.study_session.card_by_id(qna.id));
+                let response_ms = self
+                    .question_shown
+                    .map_or(0, |shown| shown.elapsed().as_millis() as u64);
+                let answer = std::mem::take(&mut self.quiz_answer);
+                self.quiz_feedback = match self.current_quiz.answer_current(
+                    &mut self.study_session,
+                    answer,
+                    response_ms,
+                ) {
+                    Some(true) => "Correct!".to_string(),
+                    Some(false)
+                        if card.is_some_and(|key| self.study_session.cards[key].leech) =>
+                    {
+                        format!(
+                            "Wrong, it was {}. This card is a leech, rewrite it.",
+                            expected.unwrap_or_default()
+                        )
+                    }
+                    Some(false) => format!("Wrong, it was {}.", expected.unwrap_or_default()),
+                    None => String::new(),
+                };
+                self.question_shown = Some(Instant::now());
+                self.current_popup = Popups::StartQuiz(self.current_quiz.qna_queue.clone());
             }
             Message::SelectTopic(topic_key) => {
                 self.current_quiz
@@ -110,9 +743,11 @@ impl App {
                     .select_topic_to_quiz(&mut self.study_session, topic_key);
             }
             Message::SubmitCard(card) => {
-                // Insert the card into the study session and mark it as current.
-                let card_key = self.study_session.cards.insert(card.clone());
+                // Insert the card into the study session and mark it as current. The session
+                // assigns the card its id, so the stored copy is the one submitted to the quiz.
+                let card_key = self.study_session.insert_card(card);
                 self.study_session.current_card = Some(card_key);
+                let card = self.study_session.cards[card_key].clone();
                 self.current_quiz
                     .submit_card_to_quiz(card, card_key, &self.study_session);
             }
@@ -124,6 +759,19 @@ impl App {
                 self.quiz
                     .submit_topic_to_list(&mut self.study_session, topic);
             }
+            Message::ImportPathChanged(path) => self.import_path = path,
+            Message::SubmitImport => {
+                let path = Path::new(self.import_path.trim());
+                self.import_status = match import::import_file(&mut self.study_session, path) {
+                    Ok(summary) => format!("Done, {summary}."),
+                    Err(error) => format!("Import failed, {error}."),
+                };
+                println!("{}", self.import_status);
+            }
+            Message::ExportPathChanged(path) => self.export_path = path,
+            Message::ExportPdf => self.export(pdf::write_pdf),
+            Message::ExportHtml => self.export(html::write_html),
+            Message::ExportBundle => self.export(bundle::write_bundle),
             Message::SetTopic(content) => {
                 self.quiz.set_topic(&mut self.study_session, content);
             }
@@ -135,25 +783,231 @@ impl App {
                 self.current_card.answer = content;
                 self.update(Message::UpdateTopic);
             }
-            Message::ExpandQuestions => self.expand_questions = !self.expand_questions,
-            Message::ExpandAnswers => self.expand_answers = !self.expand_answers,
+            Message::ImageChanged(path) => {
+                self.current_card.image = if path.trim().is_empty() {
+                    None
+                } else {
+                    Some(path.into())
+                };
+            }
+            Message::FlipPreview => self.preview_flipped = !self.preview_flipped,
             Message::SubmitColor(color) => {
                 self.current_card = Flashcard {
-                    bg_color: Some(Background::Color(color)),
+                    bg_color: Some(color.into()),
                     question: self.current_card.question.clone(),
                     answer: self.current_card.answer.clone(),
+                    image: self.current_card.image.clone(),
                     topics: self.current_card.topics.clone(),
                     ..Default::default()
                 }
             }
             // Popup state messages.
             Message::Text => self.current_popup = Popups::Text,
+            Message::Image => self.current_popup = Popups::Image,
             Message::Topics => self.current_popup = Popups::Topics,
             Message::Quiz => self.current_popup = Popups::Quiz,
             Message::Configure => self.current_popup = Popups::Configure,
             Message::NoPopup => self.current_popup = Popups::None,
             Message::ColorPicker => self.current_popup = Popups::ColorPicker,
             Message::Flashcards => self.current_popup = Popups::Flashcards,
+            Message::Stats => self.current_popup = Popups::Stats,
+            Message::ForecastDays(days) => self.forecast_days = days,
+            Message::NewPerDayChanged(count) => self.new_per_day = count,
+            Message::Leeches => {
+                self.leech_threshold = self.study_session.leech_settings.threshold.to_string();
+                self.current_popup = Popups::Leeches;
+            }
+            Message::LeechThresholdChanged(threshold) => {
+                if let Ok(threshold) = threshold.trim().parse() {
+                    self.study_session.leech_settings.threshold = threshold;
+                }
+                self.leech_threshold = threshold;
+            }
+            Message::SuspendLeeches(suspend) => {
+                self.study_session.leech_settings.suspend = suspend;
+            }
+            Message::ForgiveLeech(key) => self.study_session.forgive_leech(key),
+            Message::Import => {
+                self.import_status.clear();
+                self.current_popup = Popups::Import;
+            }
+            Message::Save => match deck::save_deck(&self.study_session, &self.deck_path) {
+                Ok(()) => println!("Saved deck to {}.", self.deck_path.display()),
+                Err(error) => println!("Could not save deck, {error}."),
+            },
+            Message::Export => {
+                self.export_status.clear();
+                self.export_topics = None;
+                self.current_popup = Popups::Export;
+            }
+            Message::NamespaceSelected(choice) => self.namespace = choice.key,
+            Message::MoveTargetSelected(choice) => self.move_target = choice.key,
+            Message::NamespaceOperate(operation) => {
+                let Some(namespace) = self.namespace else {
+                    return;
+                };
+                let topics = self.current_quiz.operation_for_namespace(
+                    &mut self.study_session,
+                    namespace,
+                    &operation,
+                );
+                match operation {
+                    NamespaceOperation::Quiz => {
+                        let questions = self.current_quiz.get_layout();
+                        self.current_popup = Popups::StartQuiz(questions);
+                    }
+                    // The namespace's topics are exported without touching which are enabled.
+                    NamespaceOperation::Export => {
+                        self.update(Message::Export);
+                        self.export_topics = Some(topics);
+                    }
+                    _ => {}
+                }
+            }
+            Message::AddEnabledToNamespace => {
+                let Some(target) = self.move_target else {
+                    return;
+                };
+                let enabled: Vec<TopicKey> = self
+                    .study_session
+                    .topics
+                    .iter()
+                    .filter(|(_, topic)| topic.enabled)
+                    .map(|(key, _)| key)
+                    .collect();
+                for topic in enabled {
+                    self.study_session.add_to_namespace(topic, target);
+                }
+            }
+            Message::Namespaces => self.current_popup = Popups::Namespaces,
+            Message::NamespaceNameChanged(name) => self.namespace_name = name,
+            Message::AddNamespace => {
+                let name = self.namespace_name.trim();
+                if name.is_empty() || self.study_session.namespace_by_name(name).is_some() {
+                    return;
+                }
+                self.study_session.insert_namespace(Namespace {
+                    name: name.to_string(),
+                    ..Default::default()
+                });
+                self.namespace_name.clear();
+            }
+            Message::NamespaceRenameChanged(key, name) => {
+                self.namespace_rename = Some((key, name));
+            }
+            Message::RenameNamespace(key, name) => {
+                self.namespace_rename = None;
+                let name = name.trim();
+                if name.is_empty()
+                    || self
+                        .study_session
+                        .namespace_by_name(name)
+                        .is_some_and(|other| other != key)
+                {
+                    return;
+                }
+                if let Some(namespace) = self.study_session.namespaces.get_mut(key) {
+                    namespace.name = name.to_string();
+                }
+            }
+            Message::MoveNamespace(key, index) => self.study_session.move_namespace(key, index),
+            Message::RemoveNamespace(key) => {
+                self.study_session.remove_namespace(key);
+                if self.namespace == Some(key) {
+                    self.namespace = None;
+                }
+                if self.move_target == Some(key) {
+                    self.move_target = None;
+                }
+            }
+            Message::PickNamespaceColor(key) => self.namespace_color_picker = Some(key),
+            Message::CancelNamespaceColor => self.namespace_color_picker = None,
+            Message::SubmitNamespaceColor(color) => {
+                if let Some(key) = self.namespace_color_picker.take() {
+                    if let Some(namespace) = self.study_session.namespaces.get_mut(key) {
+                        namespace.color = color.into();
+                    }
+                }
+            }
+            Message::TopicParentSelected(choice) => self.topic_parent = choice.key,
+            Message::ReparentTopicSelected(choice) => self.reparent_topic = choice.key,
+            Message::ReparentTopic => {
+                let Some(topic) = self.reparent_topic else {
+                    return;
+                };
+                self.topic_status = match self.study_session.set_parent(topic, self.topic_parent) {
+                    Ok(()) => format!("Moved to {}.", self.study_session.topic_path(topic)),
+                    Err(error) => format!("Could not move the topic, {error}."),
+                };
+                println!("{}", self.topic_status);
+            }
+            Message::SmartQueryChanged(query) => self.smart_query = query,
+            Message::AddSmartTopic => {
+                let name = self.study_session.staging_topic.trim().to_string();
+                self.topic_status = if name.is_empty() {
+                    "Type the name of the smart topic first.".to_string()
+                } else if self.study_session.topic_by_name(&name).is_some() {
+                    format!("There is a topic called {name} already.")
+                } else {
+                    match Query::parse(&self.smart_query) {
+                        Ok(query) => {
+                            let cards = query.cards(&self.study_session).len();
+                            self.study_session.insert_topic(Topic {
+                                content: name.clone(),
+                                query: Some(self.smart_query.trim().to_string()),
+                                namespaces: self.namespace.into_iter().collect(),
+                                parent: self.topic_parent,
+                                ..Default::default()
+                            });
+                            self.smart_query.clear();
+                            format!("Added smart topic {name}, it has {cards} cards now.")
+                        }
+                        Err(error) => format!("Invalid query, {error}."),
+                    }
+                };
+                println!("{}", self.topic_status);
+            }
+            Message::IncludeSubtopics(include) => self.current_quiz.include_subtopics = include,
+            Message::SuggestTopics => {
+                self.suggest_status.clear();
+                self.refresh_suggestions();
+                self.current_popup = Popups::Suggest;
+            }
+            Message::RuleTopicChanged(topic) => self.rule_topic = topic,
+            Message::RuleChanged(rule) => self.rule_text = rule,
+            Message::AddRule => {
+                self.suggest_status = match (
+                    self.study_session.topic_by_name(&self.rule_topic),
+                    Rule::parse(&self.rule_text),
+                ) {
+                    (None, _) => format!("There is no topic called {}.", self.rule_topic.trim()),
+                    (_, Err(error)) => format!("Invalid rule, {error}."),
+                    (Some(topic_key), Ok(rule)) => {
+                        let topic = &mut self.study_session.topics[topic_key];
+                        let status = format!("Added {rule} to {}.", topic.content);
+                        topic.rules.push(rule);
+                        self.rule_text.clear();
+                        status
+                    }
+                };
+                self.refresh_suggestions();
+            }
+            Message::ToggleSuggestion(index, accepted) => {
+                if let Some(suggestion) = self.suggestions.get_mut(index) {
+                    suggestion.1 = accepted;
+                }
+            }
+            Message::ApplySuggestions => {
+                let accepted: Vec<Suggestion> = self
+                    .suggestions
+                    .iter()
+                    .filter(|(_, accepted)| *accepted)
+                    .map(|(suggestion, _)| suggestion.clone())
+                    .collect();
+                auto::apply(&mut self.study_session, &accepted);
+                self.suggest_status = format!("Applied {} suggestions.", accepted.len());
+                self.refresh_suggestions();
+            }
             // Miscellaneous messages.
             Message::None | Message::CancelColor | Message::ChooseColor => {}
             Message::UpdateQuiz(qna_queue) => {
@@ -163,12 +1017,87 @@ impl App {
             Message::UpdateTopic => {
                 self.quiz.update_topic(&mut self.study_session);
             }
-            // AnswerChanged and QuestionChanged were already handled above.
-            _ => {}
         }
     }
 
-    fn view(&self) -> Container<Message> {
+    /// Suggests topics for the cards from the topic rules again. Suggestions start out accepted,
+    /// so applying them all is one click.
+    fn refresh_suggestions(&mut self) {
+        self.suggestions = auto::suggest(&self.study_session)
+            .into_iter()
+            .map(|suggestion| (suggestion, true))
+            .collect();
+    }
+
+    /// Seeds the quiz with the typed seed, or with a new one that is shown so the same shuffle
+    /// can be asked again.
+    fn seed_quiz(&mut self) {
+        self.current_quiz.seed = self.quiz_seed.trim().parse().unwrap_or_else(|_| {
+            let seed = Rng::time_seed();
+            if self.current_quiz.order == Order::Shuffled {
+                self.quiz_seed = seed.to_string();
+            }
+            seed
+        });
+    }
+
+    /// Drops the cards that no longer exist from the browser selection and the card being edited.
+    fn forget_missing_cards(&mut self) {
+        let cards = &self.study_session.cards;
+        self.selected_cards.retain(|&key| cards.contains_key(key));
+        self.card_edit.take_if(|(key, ..)| !cards.contains_key(*key));
+    }
+
+    /// Undoes with Ctrl+Z and redoes with Ctrl+Shift+Z, or the Command key on macOS. While an
+    /// exam is taken its countdown ticks a few times a second.
+    fn subscription(&self) -> Subscription<Message> {
+        let keys = keyboard::on_key_press(|key, modifiers| match key.as_ref() {
+            // Shift turns the key into an upper case Z on some platforms.
+            keyboard::Key::Character(c) if c.eq_ignore_ascii_case("z") && modifiers.command() => {
+                Some(if modifiers.shift() { Message::Redo } else { Message::Undo })
+            }
+            _ => None,
+        });
+        if self.current_quiz.exam.is_some() {
+            Subscription::batch([
+                keys,
+                time::every(Duration::from_millis(250)).map(Message::ExamTick),
+            ])
+        } else {
+            keys
+        }
+    }
+
+    /// Returns the message for an operation on the selected namespace, or None when all topics
+    /// are listed.
+    fn namespace_operation(&self, operation: NamespaceOperation) -> Option<Message> {
+        self.namespace.map(|_| Message::NamespaceOperate(operation))
+    }
+
+    /// Exports the cards of the topics currently toggled on, or of the namespace being exported,
+    /// to the path typed in the export popup, using the given writer.
+    fn export(&mut self, write: fn(&Study, &[TopicKey], &Path) -> std::io::Result<()>) {
+        let topics: Vec<TopicKey> = self.export_topics.clone().unwrap_or_else(|| {
+            self.study_session
+                .topics
+                .iter()
+                .filter(|(_, topic)| topic.enabled)
+                .map(|(key, _)| key)
+                .collect()
+        });
+        let path = Path::new(self.export_path.trim());
+        self.export_status = if topics.is_empty() {
+            "Select the topics to export first.".to_string()
+        } else {
+            match write(&self.study_session, &topics, path) {
+                Ok(()) => format!("Saved to {}.", path.display()),
+                Err(error) => format!("Export failed, {error}."),
+            }
+        };
+        println!("{}", self.export_status);
+    }
+
+    fn view(&self) -> Container<'_, Message> {
         let main_container = container(self.main_container());
         let background_rect: Element<'_, Message, Theme, Renderer> =
             RoundedRectangle::new(700.0, 340.0)
@@ -179,29 +1108,251 @@ impl App {
         dbg!(&self.current_popup);
         match &self.current_popup {
             Popups::Flashcards => {
-                let rect: Element<'_, Message, Theme, Renderer> =
-                    RoundedRectangle::new(200.0, 200.0)
-                        .bg_color(Color::WHITE)
-                        .into();
+                let white = |text: String| Text::new(text).color(Color::WHITE);
+                let header = |name: &str, column: CardColumn, width: f32| {
+                    let arrow = match (self.card_sort == column, self.card_sort_descending) {
+                        (false, _) => "",
+                        (true, false) => " ▲",
+                        (true, true) => " ▼",
+                    };
+                    Button::new(Text::new(format!("{name}{arrow}")))
+                        .on_press(Message::SortCards(column))
+                        .width(width)
+                };
+                let all_selected = !self.study_session.cards.is_empty()
+                    && self.selected_cards.len() == self.study_session.cards.len();
+                let header_row = row!(
+                    checkbox("", all_selected).on_toggle(Message::SelectAllCards),
+                    header("Question", CardColumn::Question, 150.0),
+                    header("Answer", CardColumn::Answer, 150.0),
+                    header("Topics", CardColumn::Topics, 110.0),
+                    header("Colour", CardColumn::Colour, 70.0),
+                    Text::new("State").color(Color::WHITE).width(90),
+                )
+                .spacing(5)
+                .align_y(Alignment::Center);
+
+                let now = unix_now();
+                let mut order = if self.card_search.trim().is_empty() {
+                    sorted_cards(&self.study_session, self.card_sort, self.card_sort_descending)
+                } else {
+                    let hits = self.search_index.search(&self.card_search);
+                    hits.into_iter().map(|hit| hit.card).collect()
+                };
+                if let Some(filter) = self.card_filter {
+                    order.retain(|&key| filter.matches(&self.study_session.cards[key], now));
+                }
+                let order_len = order.len();
+                let rows = order
+                    .into_iter()
+                    .map(|key| {
+                        let card = &self.study_session.cards[key];
+                        let swatch_color: Color =
+                            card.bg_color.map_or(Color::TRANSPARENT, Into::into);
+                        let swatch: Element<'_, Message, Theme, Renderer> =
+                            RoundedRectangle::new(20.0, 20.0)
+                                .bg_color(swatch_color)
+                                .border_radius(4.0)
+                                .into();
+                        // The card being edited shows text inputs in place of its question and
+                        // answer.
+                        let cells: [Element<'_, Message, Theme, Renderer>; 3] =
+                            match &self.card_edit {
+                                Some((edited, question, answer)) if *edited == key => [
+                                    text_input("Question", question)
+                                        .on_input(Message::EditQuestionChanged)
+                                        .on_submit(Message::SaveCardEdit)
+                                        .width(150)
+                                        .into(),
+                                    text_input("Answer", answer)
+                                        .on_input(Message::EditAnswerChanged)
+                                        .on_submit(Message::SaveCardEdit)
+                                        .width(150)
+                                        .into(),
+                                    row!(
+                                        Button::new("Save").on_press(Message::SaveCardEdit),
+                                        Button::new("Cancel").on_press(Message::CancelCardEdit),
+                                    )
+                                    .spacing(5)
+                                    .into(),
+                                ],
+                                _ => [
+                                    white(card.question.replace('\n', " ")).width(150).into(),
+                                    white(card.answer.replace('\n', " ")).width(150).into(),
+                                    Button::new("Edit").on_press(Message::EditCard(key)).into(),
+                                ],
+                            };
+                        let [question, answer, action] = cells;
+                        row!(
+                            checkbox("", self.selected_cards.contains(&key))
+                                .on_toggle(move |selected| Message::ToggleCard(key, selected)),
+                            question,
+                            answer,
+                            white(card_topic_names(&self.study_session, key)).width(110),
+                            container(swatch).width(70),
+                            white(card_states(card, now)).width(90),
+                            action,
+                        )
+                        .spacing(5)
+                        .align_y(Alignment::Center)
+                        .into()
+                    });
+
+                let selected = !self.selected_cards.is_empty();
+                let recolor_button = Button::new("Recolour")
+                    .on_press_maybe(selected.then_some(Message::PickCardsColor));
                 container(popup(
                     main_container,
-                    container(stack![
-                        rect,
-                        container(Button::new("Quiz").on_press(Message::NoPopup))
-                            .center_x(Length::Fill)
-                    ]),
+                    stack![
+                        RoundedRectangle::new(760.0, 460.0)
+                            .bg_color(Color::from_rgb8(81, 80, 80))
+                            .border_radius(20.0),
+                        column!(
+                            container(row!(
+                                text_input("Search the cards..", &self.card_search)
+                                    .on_input(Message::CardSearchChanged)
+                                    .width(300),
+                                pick_list(
+                                    filter_choices(),
+                                    filter_choices()
+                                        .into_iter()
+                                        .find(|choice| choice.key == self.card_filter),
+                                    Message::CardFilterSelected
+                                ),
+                                white(format!(
+                                    "{} of {} cards, {} selected",
+                                    order_len,
+                                    self.study_session.cards.len(),
+                                    self.selected_cards.len()
+                                )),
+                            )
+                            .spacing(10)
+                            .align_y(Alignment::Center))
+                            .padding(10)
+                            .center_x(Length::Fill),
+                            container(header_row).center_x(Length::Fill),
+                            container(Scrollable::new(Column::with_children(rows).spacing(5)).height(220))
+                                .center_x(Length::Fill),
+                            Space::new(0.0, 10.0),
+                            // Bulk actions work on the ticked cards.
+                            container(row!(
+                                Button::new("Delete")
+                                    .on_press_maybe(selected.then_some(Message::DeleteSelectedCards)),
+                                pick_list(
+                                    topic_choices(&self.study_session),
+                                    topic_choice(&self.study_session, self.bulk_topic)
+                                        .filter(|choice| choice.key.is_some()),
+                                    Message::BulkTopicSelected
+                                )
+                                .placeholder("Topic"),
+                                Button::new("Add to topic").on_press_maybe(
+                                    (selected && self.bulk_topic.is_some())
+                                        .then_some(Message::AddSelectedToTopic)
+                                ),
+                                Button::new("Remove from topic").on_press_maybe(
+                                    (selected && self.bulk_topic.is_some())
+                                        .then_some(Message::RemoveSelectedFromTopic)
+                                ),
+                                color_picker(
+                                    self.cards_color_picker,
+                                    Color::WHITE,
+                                    recolor_button,
+                                    Message::CancelCardsColor,
+                                    Message::RecolorSelectedCards,
+                                ),
+                            )
+                            .spacing(10)
+                            .align_y(Alignment::Center))
+                            .center_x(Length::Fill),
+                            Space::new(0.0, 10.0),
+                            // Suspended and buried cards are left out of quizzes.
+                            container(row!(
+                                Button::new("Suspend")
+                                    .on_press_maybe(selected.then_some(Message::SuspendSelected(true))),
+                                Button::new("Unsuspend")
+                                    .on_press_maybe(selected.then_some(Message::SuspendSelected(false))),
+                                Button::new("Bury until tomorrow")
+                                    .on_press_maybe(selected.then_some(Message::BurySelected(true))),
+                                Button::new("Unbury")
+                                    .on_press_maybe(selected.then_some(Message::BurySelected(false))),
+                                pick_list(
+                                    flag_choices(),
+                                    None::<Choice<Flag>>,
+                                    Message::FlagSelected
+                                )
+                                .placeholder("Flag"),
+                            )
+                            .spacing(10)
+                            .align_y(Alignment::Center))
+                            .center_x(Length::Fill),
+                            Space::new(0.0, 10.0),
+                            container(Button::new("Exit").on_press(Message::NoPopup))
+                                .center_x(Length::Fill)
+                        )
+                    ],
                     Message::None,
                 ))
             }
-            Popups::None => main_container,
+            Popups::None | Popups::Review => main_container,
             Popups::Quiz => container(popup(
                 main_container,
                 container(stack![
                     background_rect,
                     column!(
                         topic_scrollbar(self),
+                        container(
+                            checkbox("Include sub-topics", self.current_quiz.include_subtopics)
+                                .on_toggle(Message::IncludeSubtopics)
+                                .style(|_theme: &Theme, _status| checkbox::Style {
+                                    background: Color::WHITE.into(),
+                                    icon_color: Color::BLACK,
+                                    border: Border::default(),
+                                    text_color: Some(Color::WHITE),
+                                })
+                        )
+                        .center_x(Length::Fill),
                         Space::new(0.0, 20.0),
-                        container(Button::new("Start quiz").on_press(Message::StartQuiz))
+                        // Shuffled quizzes can be asked again in the same order with their seed.
+                        container(row!(
+                            pick_list(
+                                order_choices(),
+                                Some(Choice {
+                                    key: Some(self.current_quiz.order),
+                                    name: self.current_quiz.order.describe().to_string(),
+                                }),
+                                Message::QuizOrderSelected,
+                            ),
+                            text_input("Seed", &self.quiz_seed)
+                                .on_input(Message::QuizSeedChanged)
+                                .width(160),
+                        )
+                        .spacing(10))
+                            .center_x(Length::Fill),
+                        Space::new(0.0, 10.0),
+                        container(row!(
+                            Button::new("Start quiz").on_press(Message::StartQuiz),
+                            // Cards are flipped and graded by hand rather than typed.
+                            Button::new("Flip review").on_press(Message::StartReview),
+                        )
+                        .spacing(10))
+                            .center_x(Length::Fill),
+                        Space::new(0.0, 10.0),
+                        // An exam on some of the cards, with the time limit for all of them, like
+                        // 20m, or for each, like 30s each.
+                        container(row!(
+                            text_input("Questions", &self.exam_questions)
+                                .on_input(Message::ExamQuestionsChanged)
+                                .width(60),
+                            text_input("Time limit", &self.exam_limit)
+                                .on_input(Message::ExamLimitChanged)
+                                .width(100),
+                            Button::new("Start exam").on_press_maybe(
+                                (self.exam_questions.trim().parse::<usize>().is_ok()
+                                    && self.exam_limit.parse::<TimeLimit>().is_ok())
+                                .then_some(Message::StartExam)
+                            ),
+                        )
+                        .spacing(10))
                             .center_x(Length::Fill),
                         Space::new(0.0, 20.0),
                         container(Button::new("Exit").on_press(Message::EndQuiz))
@@ -220,9 +1371,9 @@ impl App {
                 });
 
                 let content = if let Some(qna) = self.current_quiz.qna_queue.front() {
-                    Text::new(qna.question.clone())
+                    markup::view(&qna.question)
                 } else {
-                    Text::new("Finished")
+                    Text::new("Finished").into()
                 };
 
                 let main_column = column!(
@@ -239,9 +1390,21 @@ impl App {
                         container(
                             container(column!(
                                 content,
-                                Button::new("Next")
-                                    .on_press(Message::UpdateQuiz(local_qna.clone()))
-                            ))
+                                text_input("Your answer", &self.quiz_answer)
+                                    .on_input(Message::QuizAnswerChanged)
+                                    .on_submit(Message::SubmitQuizAnswer),
+                                row!(
+                                    Button::new("Check").on_press_maybe(
+                                        (!local_qna.is_empty())
+                                            .then_some(Message::SubmitQuizAnswer)
+                                    ),
+                                    Button::new("Next")
+                                        .on_press(Message::UpdateQuiz(local_qna.clone())),
+                                )
+                                .spacing(5),
+                                Text::new(self.quiz_feedback.clone()),
+                            )
+                            .spacing(5))
                             .width(250)
                             .height(150)
                             .style(|_| container::Style::default().background(Color::WHITE)),
@@ -264,6 +1427,46 @@ impl App {
                     background_rect,
                     column!(
                         topic_scrollbar(self),
+                        Space::new(0.0, 10.0),
+                        // The operations work on the namespace the topics are filtered by.
+                        container(row!(
+                            Button::new("Enable all")
+                                .on_press_maybe(self.namespace_operation(NamespaceOperation::Enable)),
+                            Space::new(10.0, 0.0),
+                            Button::new("Disable all")
+                                .on_press_maybe(self.namespace_operation(NamespaceOperation::Disable)),
+                            Space::new(10.0, 0.0),
+                            Button::new("Quiz all")
+                                .on_press_maybe(self.namespace_operation(NamespaceOperation::Quiz)),
+                            Space::new(10.0, 0.0),
+                            Button::new("Export all")
+                                .on_press_maybe(self.namespace_operation(NamespaceOperation::Export)),
+                            Space::new(10.0, 0.0),
+                            Button::new("Delete all")
+                                .on_press_maybe(self.namespace_operation(NamespaceOperation::Delete)),
+                        ))
+                        .center_x(Length::Fill),
+                        Space::new(0.0, 10.0),
+                        container(row!(
+                            Button::new("Move all to").on_press_maybe(
+                                self.move_target.and_then(|target| {
+                                    self.namespace_operation(NamespaceOperation::Move(target))
+                                })
+                            ),
+                            Space::new(10.0, 0.0),
+                            Button::new("Add enabled to").on_press_maybe(
+                                self.move_target.map(|_| Message::AddEnabledToNamespace)
+                            ),
+                            Space::new(10.0, 0.0),
+                            pick_list(
+                                namespace_choices(&self.study_session, false),
+                                namespace_choice(&self.study_session, self.move_target)
+                                    .filter(|choice| choice.key.is_some()),
+                                Message::MoveTargetSelected
+                            ),
+                        ).align_y(Alignment::Center))
+                        .center_x(Length::Fill),
+                        Space::new(0.0, 10.0),
                         container(Button::new("Exit").on_press(Message::NoPopup))
                             .center_x(Length::Fill)
                     )
@@ -314,6 +1517,535 @@ impl App {
                 ],
                 Message::None,
             )),
+            Popups::Image => container(popup(
+                main_container,
+                stack![
+                    background_rect,
+                    column!(
+                        container("Image").padding(20).center_x(Length::Fill),
+                        Space::new(0.0, 20.0),
+                        container(
+                            text_input(
+                                "Path to an image for this card..",
+                                &self
+                                    .current_card
+                                    .image
+                                    .as_ref()
+                                    .map(|path| path.display().to_string())
+                                    .unwrap_or_default()
+                            )
+                            .on_input(Message::ImageChanged)
+                        )
+                        .center_x(Length::Fill),
+                        Space::new(0.0, 20.0),
+                        container(row!(Button::new("Exit").on_press(Message::NoPopup),))
+                            .center_x(Length::Fill)
+                    )
+                ],
+                Message::None,
+            )),
+            Popups::Import => container(popup(
+                main_container,
+                stack![
+                    background_rect,
+                    column!(
+                        container("Import a deck, a deck bundle, or a Mnemosyne or SuperMemo deck")
+                            .padding(20)
+                            .center_x(Length::Fill),
+                        container(
+                            text_input(
+                                "Path to a .json, .fcdeck, .cards, .xml or .txt file..",
+                                &self.import_path
+                            )
+                                .on_input(Message::ImportPathChanged)
+                                .on_submit(Message::SubmitImport)
+                                .width(500)
+                        )
+                        .center_x(Length::Fill),
+                        Space::new(0.0, 20.0),
+                        container(Text::new(self.import_status.clone()).color(Color::WHITE))
+                            .center_x(Length::Fill),
+                        Space::new(0.0, 20.0),
+                        container(row!(
+                            Button::new("Import").on_press(Message::SubmitImport),
+                            Space::new(10.0, 0.0),
+                            Button::new("Exit").on_press(Message::NoPopup),
+                        ))
+                        .center_x(Length::Fill)
+                    )
+                ],
+                Message::None,
+            )),
+            Popups::Export => container(popup(
+                main_container,
+                stack![
+                    background_rect,
+                    column!(
+                        container(if self.export_topics.is_some() {
+                            "Export the cards of the namespace"
+                        } else {
+                            "Export the cards of the selected topics"
+                        })
+                            .padding(20)
+                            .center_x(Length::Fill),
+                        container(
+                            text_input("Path to save the export to..", &self.export_path)
+                                .on_input(Message::ExportPathChanged)
+                                .width(500)
+                        )
+                        .center_x(Length::Fill),
+                        Space::new(0.0, 20.0),
+                        container(Text::new(self.export_status.clone()).color(Color::WHITE))
+                            .center_x(Length::Fill),
+                        Space::new(0.0, 20.0),
+                        container(row!(
+                            Button::new("Printable PDF").on_press(Message::ExportPdf),
+                            Space::new(10.0, 0.0),
+                            Button::new("HTML page").on_press(Message::ExportHtml),
+                            Space::new(10.0, 0.0),
+                            Button::new("Deck bundle").on_press(Message::ExportBundle),
+                            Space::new(10.0, 0.0),
+                            Button::new("Exit").on_press(Message::NoPopup),
+                        ))
+                        .center_x(Length::Fill)
+                    )
+                ],
+                Message::None,
+            )),
+            Popups::Suggest => container(popup(
+                main_container,
+                stack![
+                    background_rect,
+                    column!(
+                        container("Suggest topics for cards matching keyword or /regex/ rules")
+                            .padding(20)
+                            .center_x(Length::Fill),
+                        container(Text::new(
+                            self.study_session
+                                .topics
+                                .values()
+                                .filter(|topic| !topic.rules.is_empty())
+                                .map(|topic| {
+                                    let rules: Vec<String> =
+                                        topic.rules.iter().map(Rule::to_string).collect();
+                                    format!("{}: {}", topic.content, rules.join(", "))
+                                })
+                                .collect::<Vec<_>>()
+                                .join("\n")
+                        ).color(Color::WHITE))
+                        .center_x(Length::Fill),
+                        container(row!(
+                            text_input("Topic", &self.rule_topic)
+                                .on_input(Message::RuleTopicChanged)
+                                .width(150),
+                            Space::new(10.0, 0.0),
+                            text_input("Keyword or /regex/", &self.rule_text)
+                                .on_input(Message::RuleChanged)
+                                .on_submit(Message::AddRule)
+                                .width(250),
+                            Space::new(10.0, 0.0),
+                            Button::new("Add rule").on_press(Message::AddRule),
+                        ))
+                        .center_x(Length::Fill),
+                        container(Text::new(self.suggest_status.clone()).color(Color::WHITE))
+                            .padding(10)
+                            .center_x(Length::Fill),
+                        container(Scrollable::new(Column::with_children(
+                            self.suggestions.iter().enumerate().map(|(index, (suggestion, accepted))| {
+                                checkbox(suggestion.describe(&self.study_session), *accepted)
+                                    .on_toggle(move |accepted| Message::ToggleSuggestion(index, accepted))
+                                    .style(|_theme: &Theme, _status| checkbox::Style {
+                                        background: Color::WHITE.into(),
+                                        icon_color: Color::BLACK,
+                                        border: Border::default(),
+                                        text_color: Some(Color::WHITE),
+                                    })
+                                    .into()
+                            })
+                        ).spacing(5)).height(250))
+                        .center_x(Length::Fill),
+                        Space::new(0.0, 20.0),
+                        container(row!(
+                            Button::new("Apply").on_press(Message::ApplySuggestions),
+                            Space::new(10.0, 0.0),
+                            Button::new("Exit").on_press(Message::NoPopup),
+                        ))
+                        .center_x(Length::Fill)
+                    )
+                ],
+                Message::None,
+            )),
+            Popups::Stats => {
+                let white = |text: String| Text::new(text).color(Color::WHITE);
+                let study = &self.study_session;
+                let now = unix_now();
+                let recent = stats::daily(study, now, 30);
+                let year = stats::daily(study, now, 182);
+                let reviews: usize = recent.iter().map(|day| day.reviews).sum();
+                let correct: usize = recent.iter().map(|day| day.correct).sum();
+                let heatmap_size = Heatmap::size(year.len());
+                let new_per_day = self.new_per_day.trim().parse().unwrap_or(0);
+                let forecast =
+                    forecast::forecast(study, now, self.forecast_days, new_per_day);
+                let forecast_total: usize = forecast.iter().map(|day| day.total()).sum();
+                let busiest = forecast.iter().max_by_key(|day| day.total());
+                let forecast_buttons = [30, 90, 365].map(|days| {
+                    Button::new(Text::new(format!("{days} days")))
+                        .on_press_maybe(
+                            (self.forecast_days != days).then_some(Message::ForecastDays(days)),
+                        )
+                        .into()
+                });
+
+                let retention = stats::topic_retention(study).into_iter().map(|topic| {
+                    white(format!(
+                        "{}: {:.0}% of {}",
+                        study.topic_path(topic.topic),
+                        topic.retention() * 100.0,
+                        topic.reviews
+                    ))
+                    .into()
+                });
+                let hardest = stats::hardest_cards(study, 10).into_iter().map(|record| {
+                    white(format!(
+                        "{} ({} of {} wrong)",
+                        study.cards[record.card].question.replace('\n', " "),
+                        record.wrong,
+                        record.reviews
+                    ))
+                    .into()
+                });
+
+                container(popup(
+                    main_container,
+                    stack![
+                        RoundedRectangle::new(760.0, 600.0)
+                            .bg_color(Color::from_rgb8(81, 80, 80))
+                            .border_radius(20.0),
+                        column!(
+                            white(format!(
+                                "{} reviews in the last 30 days, {} correct. Bars are reviews per \
+                                 day, the line is accuracy.",
+                                reviews, correct
+                            )),
+                            canvas(BarChart::activity(&recent)).width(700).height(100),
+                            white("Reviews over the last 26 weeks".to_string()),
+                            canvas(Heatmap { days: year })
+                                .width(heatmap_size.width)
+                                .height(heatmap_size.height),
+                            row(forecast_buttons)
+                                .push(Space::new(10.0, 0.0))
+                                .push(
+                                    text_input("New cards per day", &self.new_per_day)
+                                        .on_input(Message::NewPerDayChanged)
+                                        .width(150),
+                                )
+                                .push(white(match busiest {
+                                    Some(day) => format!(
+                                        "{} reviews due, at most {} on {}",
+                                        forecast_total,
+                                        day.total(),
+                                        stats::date(day.day)
+                                    ),
+                                    None => String::new(),
+                                }))
+                                .spacing(5)
+                                .align_y(Alignment::Center),
+                            canvas(BarChart::forecast(&forecast)).width(700).height(80),
+                            row!(
+                                column!(
+                                    white("Retention by topic".to_string()),
+                                    Scrollable::new(Column::with_children(retention)).height(90),
+                                )
+                                .width(340),
+                                column!(
+                                    white("Hardest cards".to_string()),
+                                    Scrollable::new(Column::with_children(hardest)).height(90),
+                                )
+                                .width(340),
+                            )
+                            .spacing(20),
+                            container(Button::new("Exit").on_press(Message::NoPopup))
+                                .center_x(Length::Fill)
+                        )
+                        .spacing(8)
+                        .padding(20)
+                    ],
+                    Message::None,
+                ))
+            }
+            Popups::Leeches => {
+                let white = |text: String| Text::new(text).color(Color::WHITE);
+                let study = &self.study_session;
+                let leeches = study.leeches();
+                let leech_count = leeches.len();
+                // A leech is rewritten in place, like a card in the card browser.
+                let rows = leeches.into_iter().map(|key| {
+                    let card = &study.cards[key];
+                    let cells: [Element<'_, Message, Theme, Renderer>; 3] = match &self.card_edit {
+                        Some((edited, question, answer)) if *edited == key => [
+                            text_input("Question", question)
+                                .on_input(Message::EditQuestionChanged)
+                                .on_submit(Message::SaveCardEdit)
+                                .width(220)
+                                .into(),
+                            text_input("Answer", answer)
+                                .on_input(Message::EditAnswerChanged)
+                                .on_submit(Message::SaveCardEdit)
+                                .width(220)
+                                .into(),
+                            row!(
+                                Button::new("Save").on_press(Message::SaveCardEdit),
+                                Button::new("Cancel").on_press(Message::CancelCardEdit),
+                            )
+                            .spacing(5)
+                            .into(),
+                        ],
+                        _ => [
+                            white(card.question.replace('\n', " ")).width(220).into(),
+                            white(card.answer.replace('\n', " ")).width(220).into(),
+                            Button::new("Rewrite").on_press(Message::EditCard(key)).into(),
+                        ],
+                    };
+                    let [question, answer, action] = cells;
+                    row!(
+                        question,
+                        answer,
+                        white(format!(
+                            "{} lapses{}",
+                            card.schedule.lapses,
+                            if card.suspended { ", suspended" } else { "" }
+                        ))
+                        .width(120),
+                        action,
+                        Button::new("Forgive").on_press(Message::ForgiveLeech(key)),
+                    )
+                    .spacing(5)
+                    .align_y(Alignment::Center)
+                    .into()
+                });
+
+                container(popup(
+                    main_container,
+                    stack![
+                        RoundedRectangle::new(760.0, 420.0)
+                            .bg_color(Color::from_rgb8(81, 80, 80))
+                            .border_radius(20.0),
+                        column!(
+                            container(row!(
+                                white("Cards become leeches after more than".to_string()),
+                                text_input("8", &self.leech_threshold)
+                                    .on_input(Message::LeechThresholdChanged)
+                                    .width(50),
+                                white("lapses.".to_string()),
+                                checkbox("Suspend leeches", study.leech_settings.suspend)
+                                    .on_toggle(Message::SuspendLeeches)
+                                    .style(|_theme: &Theme, _status| checkbox::Style {
+                                        background: Color::WHITE.into(),
+                                        icon_color: Color::BLACK,
+                                        border: Border::default(),
+                                        text_color: Some(Color::WHITE),
+                                    }),
+                            )
+                            .spacing(10)
+                            .align_y(Alignment::Center))
+                            .center_x(Length::Fill),
+                            container(white(format!(
+                                "{leech_count} leeches. Rewrite them, then forgive them to quiz \
+                                 on them again."
+                            )))
+                            .center_x(Length::Fill),
+                            container(
+                                Scrollable::new(Column::with_children(rows).spacing(5))
+                                    .height(260)
+                            )
+                            .center_x(Length::Fill),
+                            container(Button::new("Exit").on_press(Message::NoPopup))
+                                .center_x(Length::Fill)
+                        )
+                        .spacing(10)
+                        .padding(20)
+                    ],
+                    Message::None,
+                ))
+            }
+            Popups::Exam => {
+                let white = |text: String| Text::new(text).color(Color::WHITE);
+                let Some(exam) = &self.current_quiz.exam else {
+                    return main_container;
+                };
+                let now = self.exam_clock.unwrap_or_else(Instant::now);
+                let content: Element<'_, Message, Theme, Renderer> = match exam.current() {
+                    Some(qna) => markup::view(&qna.question),
+                    None => Text::new("Finished").into(),
+                };
+                container(popup(
+                    main_container,
+                    stack![
+                        RoundedRectangle::new(500.0, 300.0)
+                            .bg_color(Color::from_rgb8(81, 80, 80))
+                            .border_radius(20.0),
+                        column!(
+                            container(row!(
+                                white(format!(
+                                    "Question {} of {}",
+                                    (exam.answers.len() + 1).min(exam.questions.len()),
+                                    exam.questions.len()
+                                )),
+                                Space::new(Length::Fill, 0.0),
+                                white(format!(
+                                    "{} left{}",
+                                    exam::clock(exam.remaining(now)),
+                                    match exam.limit {
+                                        TimeLimit::Total(_) => "",
+                                        TimeLimit::PerQuestion(_) => " for this question",
+                                    }
+                                )),
+                            ))
+                            .width(Length::Fill),
+                            // There is no feedback until the exam is handed in.
+                            container(
+                                column!(
+                                    content,
+                                    text_input("Your answer", &self.quiz_answer)
+                                        .on_input(Message::QuizAnswerChanged)
+                                        .on_submit(Message::SubmitExamAnswer),
+                                    Button::new("Next").on_press(Message::SubmitExamAnswer),
+                                )
+                                .spacing(5)
+                                .padding(10)
+                            )
+                            .width(Length::Fill)
+                            .height(150)
+                            .style(|_| container::Style::default().background(Color::WHITE)),
+                            container(Button::new("Hand in").on_press(Message::FinishExam))
+                                .center_x(Length::Fill)
+                        )
+                        .spacing(10)
+                        .padding(20)
+                        .width(500)
+                    ],
+                    Message::None,
+                ))
+            }
+            Popups::ExamReport(report) => {
+                let white = |text: String| Text::new(text).color(Color::WHITE);
+                let rows = report.results.iter().map(|result| {
+                    let mark = if result.correct { "Right" } else { "Wrong" };
+                    row!(
+                        white(result.question.question.replace('\n', " ")).width(200),
+                        white(result.answer.clone().unwrap_or("No answer".to_string()))
+                            .width(150),
+                        white(result.question.answer.replace('\n', " ")).width(150),
+                        white(mark.to_string()).width(60),
+                    )
+                    .spacing(5)
+                    .into()
+                });
+                container(popup(
+                    main_container,
+                    stack![
+                        RoundedRectangle::new(640.0, 420.0)
+                            .bg_color(Color::from_rgb8(81, 80, 80))
+                            .border_radius(20.0),
+                        column!(
+                            container(white(report.to_string())).center_x(Length::Fill),
+                            container(
+                                row!(
+                                    white("Question".to_string()).width(200),
+                                    white("Your answer".to_string()).width(150),
+                                    white("Answer".to_string()).width(150),
+                                    white("Mark".to_string()).width(60),
+                                )
+                                .spacing(5)
+                            )
+                            .center_x(Length::Fill),
+                            container(
+                                Scrollable::new(Column::with_children(rows).spacing(5))
+                                    .height(280)
+                            )
+                            .center_x(Length::Fill),
+                            container(Button::new("Exit").on_press(Message::NoPopup))
+                                .center_x(Length::Fill)
+                        )
+                        .spacing(10)
+                        .padding(20)
+                    ],
+                    Message::None,
+                ))
+            }
+            Popups::Namespaces => {
+                let order = self.study_session.ordered_namespaces();
+                let rows = order.iter().enumerate().map(|(index, &key)| {
+                    let namespace = &self.study_session.namespaces[key];
+                    let swatch_color: Color = namespace.color.into();
+                    let swatch = Button::new(Space::new(20.0, 20.0))
+                        .style(move |_, _| button::Style {
+                            background: Some(swatch_color.into()),
+                            border: Border::default().rounded(4.0),
+                            ..Default::default()
+                        })
+                        .on_press(Message::PickNamespaceColor(key));
+                    row!(
+                        color_picker(
+                            self.namespace_color_picker == Some(key),
+                            swatch_color,
+                            swatch,
+                            Message::CancelNamespaceColor,
+                            Message::SubmitNamespaceColor,
+                        ),
+                        Space::new(10.0, 0.0),
+                        match &self.namespace_rename {
+                            Some((renamed, name)) if *renamed == key => text_input("Name", name)
+                                .on_input(move |name| Message::NamespaceRenameChanged(key, name))
+                                .on_submit(Message::RenameNamespace(key, name.clone())),
+                            _ => text_input("Name", &namespace.name)
+                                .on_input(move |name| Message::NamespaceRenameChanged(key, name)),
+                        }
+                        .width(250),
+                        Space::new(10.0, 0.0),
+                        Button::new("Up").on_press_maybe(
+                            index.checked_sub(1).map(|above| Message::MoveNamespace(key, above))
+                        ),
+                        Space::new(5.0, 0.0),
+                        Button::new("Down").on_press_maybe(
+                            (index + 1 < order.len()).then_some(Message::MoveNamespace(key, index + 1))
+                        ),
+                        Space::new(5.0, 0.0),
+                        Button::new("Delete").on_press(Message::RemoveNamespace(key)),
+                    )
+                    .align_y(Alignment::Center)
+                    .into()
+                });
+
+                container(popup(
+                    main_container,
+                    stack![
+                        background_rect,
+                        column!(
+                            container("Namespaces group topics; a topic can be in several of them")
+                                .padding(20)
+                                .center_x(Length::Fill),
+                            container(Scrollable::new(Column::with_children(rows).spacing(10)).height(180))
+                                .center_x(Length::Fill),
+                            Space::new(0.0, 10.0),
+                            container(row!(
+                                text_input("New namespace", &self.namespace_name)
+                                    .on_input(Message::NamespaceNameChanged)
+                                    .on_submit(Message::AddNamespace)
+                                    .width(250),
+                                Space::new(10.0, 0.0),
+                                Button::new("Add").on_press(Message::AddNamespace),
+                            ))
+                            .center_x(Length::Fill),
+                            Space::new(0.0, 20.0),
+                            container(Button::new("Exit").on_press(Message::NoPopup))
+                                .center_x(Length::Fill)
+                        )
+                    ],
+                    Message::None,
+                ))
+            }
             Popups::Topics => container(popup(
                 main_container,
                 stack![
@@ -328,13 +2060,12 @@ impl App {
                                         //if let Some(topic_key) =
                                         //if let Some(topic_key) =
                                         //    self.study_session.current_topic
-                                        if self.study_session.current_topic.is_some()
+                                        if let Some(topic_key) = self.study_session.current_topic
                                         {
-                                            let topic_key = self.study_session.current_topic.unwrap();
                                             if let Some(topic) = self.study_session.topics.get(topic_key) {
                                                 column!(
                                                     Button::new("Submit").on_press(
-                                                        Message::SubmitTopic(Topic {content:topic.content.clone(),enabled:false,qna:topic.qna.clone(), topic_tag: TopicTag::Default })
+                                                        Message::SubmitTopic(Topic {id:topic.id,content:topic.content.clone(),enabled:false,qna:topic.qna.clone(), namespaces: topic.namespaces.clone(), parent: topic.parent, rules: topic.rules.clone(), query: topic.query.clone() })
                                                     ),
                                                     text_input("Put text here", &topic.content)
                                                         .on_input(Message::SetTopic),
@@ -347,7 +2078,7 @@ impl App {
                                                 text_input("Enter new topic", &self.study_session.staging_topic)
                                                     .on_input(Message::SetTopic),
                                                 Button::new("Submit").on_press(
-                                                    Message::SubmitTopic(Topic {content:self.study_session.staging_topic.clone(),enabled:false,qna:vec![], topic_tag: TopicTag::Default })
+                                                    Message::SubmitTopic(Topic {content:self.study_session.staging_topic.clone(),enabled:false,qna:vec![], namespaces: self.namespace.into_iter().collect(), parent: self.topic_parent, ..Default::default() })
                                                 ),
                                             )
                                         }))
@@ -361,6 +2092,45 @@ impl App {
                             topic_scrollbar(self).center_y(Length::Fill),
                             Space::new(60.0, 0.0),
                         ),
+                        container(row!(
+                            Text::new("Parent").color(Color::WHITE),
+                            Space::new(10.0, 0.0),
+                            pick_list(
+                                topic_choices(&self.study_session),
+                                topic_choice(&self.study_session, self.topic_parent),
+                                Message::TopicParentSelected
+                            ),
+                            Space::new(20.0, 0.0),
+                            pick_list(
+                                topic_choices(&self.study_session)
+                                    .into_iter()
+                                    .filter(|choice| choice.key.is_some())
+                                    .collect::<Vec<_>>(),
+                                topic_choice(&self.study_session, self.reparent_topic)
+                                    .filter(|choice| choice.key.is_some()),
+                                Message::ReparentTopicSelected
+                            )
+                            .placeholder("Topic to move"),
+                            Space::new(10.0, 0.0),
+                            Button::new("Move under parent").on_press_maybe(
+                                self.reparent_topic.map(|_| Message::ReparentTopic)
+                            ),
+                        ).align_y(Alignment::Center))
+                        .center_x(Length::Fill),
+                        Space::new(0.0, 10.0),
+                        // Smart topics take their name from the new topic field.
+                        container(row!(
+                            text_input("Smart topic query, e.g. topic:Biology AND lapses>3", &self.smart_query)
+                                .on_input(Message::SmartQueryChanged)
+                                .on_submit(Message::AddSmartTopic)
+                                .width(400),
+                            Space::new(10.0, 0.0),
+                            Button::new("Add smart topic").on_press(Message::AddSmartTopic),
+                        ).align_y(Alignment::Center))
+                        .center_x(Length::Fill),
+                        container(Text::new(self.topic_status.clone()).color(Color::WHITE))
+                            .padding(10)
+                            .center_x(Length::Fill),
                         container(Button::new("Exit").on_press(Message::NoPopup))
                             .center_x(Length::Fill)
                     ),
@@ -371,20 +2141,6 @@ impl App {
     }
 
     fn main_container(&self) -> Element<'_, Message, Theme, Renderer> {
-        let mut answer_column: Vec<Element<Message, Theme, Renderer>> =
-            vec![Button::new("Expand answers")
-                .on_press(Message::ExpandAnswers)
-                .into()];
-        if self.expand_answers {
-            answer_column.push(Text::new(self.current_card.answer.clone()).into());
-        }
-        let mut question_column = vec![Button::new("Expand questions")
-            .on_press(Message::ExpandQuestions)
-            .into()];
-        if self.expand_questions {
-            question_column.push(Text::new(self.current_card.question.clone()).into());
-        }
-
         let btn_style = button::Style {
             background: Some(Color::BLACK.into()),
             text_color: Color::WHITE,
@@ -402,6 +2158,20 @@ impl App {
                     Button::new("Flashcards").on_press(Message::Flashcards),
                     Button::new("Quiz").on_press(Message::Quiz),
                     Button::new("Configure").on_press(Message::Configure),
+                    Button::new("Import").on_press(Message::Import),
+                    Button::new("Export").on_press(Message::Export),
+                    Button::new("Namespaces").on_press(Message::Namespaces),
+                    Button::new("Suggest topics").on_press(Message::SuggestTopics),
+                    Button::new("Stats").on_press(Message::Stats),
+                    Button::new("Leeches").on_press(Message::Leeches),
+                    Button::new("Save").on_press(Message::Save),
+                    row![
+                        Button::new("Undo")
+                            .on_press_maybe(self.history.can_undo().then_some(Message::Undo)),
+                        Button::new("Redo")
+                            .on_press_maybe(self.history.can_redo().then_some(Message::Redo)),
+                    ]
+                    .spacing(5.),
                 ]
                 .align_x(alignment::Horizontal::Center)
                 .spacing(15.)
@@ -425,45 +2195,20 @@ impl App {
                         .style(move |_, _| btn_style)
                         .on_press(Message::ColorPicker),
                     Space::new(Length::Fixed(0.0), Length::Fixed(5.0)),
-                    Button::new("Image").style(move |_, _| btn_style),
+                    Button::new("Image")
+                        .style(move |_, _| btn_style)
+                        .on_press(Message::Image),
                     Space::new(Length::Fixed(0.0), Length::Fixed(5.0)),
                     Button::new("Submit")
                         .style(move |_, _| btn_style)
                         .on_press(Message::SubmitCard(self.current_card.clone())),
                 )),
                 Space::new(7.5, 0.0),
-                column!(card(
-                    "1",
-                    Column::new()
-                        .push(
-                            Column::new()
-                                .push(Column::with_children(question_column))
-                                .push(Space::new(0.0, 10.0))
-                                .push(Column::new().push(Column::with_children(answer_column)))
-                        )
-                        .push(Space::new(0.0, 50))
-                )
-                .foot(Row::with_children(
-                    self.current_card
-                        .topics
-                        .iter()
-                        .filter_map(|topic_key| self.study_session.topics.get(*topic_key))
-                        .flat_map(|topic| {
-                            vec![
-                                Text::new(topic.content.clone()).into(),
-                                Space::new(5, Length::Shrink).into(),
-                            ]
-                        })
-                        .collect::<Vec<_>>(),
-                ))
-                .style(|_theme: &Theme, _status| style::card::Style {
-                    head_background: self
-                        .current_card
-                        .bg_color
-                        .unwrap_or(Color::from_rgb8(255, 0, 0).into()),
-                    ..Default::default()
-                })
-                .width(Length::Fixed(400.0)))
+                // While reviewing, the card being reviewed takes the place of the new card.
+                match self.review_card() {
+                    Some(review) => review,
+                    None => self.editor_card(),
+                }
             ))
             .width(Length::Fixed(500.0))
             .height(Length::Fixed(300.0))
@@ -472,28 +2217,135 @@ impl App {
         ]
         .into()
     }
+
+    // A preview of the new card being made, with its question on the front and its answer on the
+    // back.
+    fn editor_card(&self) -> Element<'_, Message, Theme, Renderer> {
+        let color = self
+            .current_card
+            .bg_color
+            .map_or(Color::from_rgb8(255, 0, 0), Color::from);
+        let front = Column::new()
+            .push(markup::view(&self.current_card.question))
+            .push_maybe(self.current_card.image.as_ref().map(|path| {
+                Text::new(format!("Image: {}", path.display()))
+            }));
+        let topics = self
+            .current_card
+            .topics
+            .iter()
+            .filter_map(|topic_key| self.study_session.topics.get(*topic_key))
+            .flat_map(|topic| {
+                vec![
+                    Text::new(topic.content.clone()).into(),
+                    Space::new(5, Length::Shrink).into(),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        column!(
+            FlipCard::new(
+                front,
+                markup::view(&self.current_card.answer),
+                self.preview_flipped
+            )
+            .bg_color(color),
+            Space::new(0.0, 5.0),
+            Row::new()
+                .push(Button::new("Flip").on_press(Message::FlipPreview))
+                .push(Space::new(10, Length::Shrink))
+                .push(Row::with_children(topics))
+                .align_y(Alignment::Center)
+        )
+        .width(Length::Fixed(400.0))
+        .into()
+    }
+
+    // The card being reviewed in a flip review: the question until it is revealed, then the
+    // answer with buttons to grade it. None when not reviewing.
+    fn review_card(&self) -> Option<Element<'_, Message, Theme, Renderer>> {
+        if !matches!(self.current_popup, Popups::Review) {
+            return None;
+        }
+        let Some(qna) = self.current_quiz.qna_queue.front() else {
+            return Some(
+                card(
+                    Text::new("Finished"),
+                    Text::new("There are no cards left to review."),
+                )
+                .foot(Button::new("Back").on_press(Message::EndQuiz))
+                .width(Length::Fixed(400.0))
+                .into(),
+            );
+        };
+        let color = self
+            .study_session
+            .card_by_id(qna.id)
+            .and_then(|key| self.study_session.cards[key].bg_color)
+            .map_or(Color::from_rgb8(255, 0, 0), Color::from);
+        let left = self.current_quiz.qna_queue.len();
+        let foot: Row<'_, Message> = if self.answer_revealed {
+            Row::with_children(GRADES.map(|grade| {
+                Button::new(Text::new(grade_label(grade)))
+                    .on_press(Message::GradeCard(grade))
+                    .into()
+            }))
+        } else {
+            row!(
+                Button::new("Show answer").on_press(Message::RevealAnswer),
+                Button::new("Stop").on_press(Message::EndQuiz),
+            )
+        };
+        Some(
+            column!(
+                FlipCard::new(
+                    markup::view(&qna.question),
+                    column!(
+                        markup::view(&qna.question),
+                        Space::new(0.0, 10.0),
+                        markup::view(&qna.answer)
+                    ),
+                    self.answer_revealed
+                )
+                .bg_color(color)
+                .key(qna.id),
+                Space::new(0.0, 5.0),
+                row!(
+                    foot.spacing(5),
+                    Space::new(10, Length::Shrink),
+                    Text::new(format!("{left} left"))
+                )
+                .align_y(Alignment::Center)
+            )
+            .width(Length::Fixed(400.0))
+            .into(),
+        )
+    }
 }
 
 fn topic_scrollbar(app: &App) -> Container<'static, Message> {
     let mut topic_list = vec![];
 
-    let mut item_arr: SlotMap<TopicKey, Topic> = SlotMap::with_key();
-
-    let filter_tag: TopicTag = match app.current_popup {
-        Popups::Topics => TopicTag::Default,
-        Popups::Configure => TopicTag::Configure,
-        Popups::Quiz => TopicTag::Quiz,
-        _ => TopicTag::None,
-    };
-
-    for (_, topic) in app.study_session.topics.iter().filter(|(_, topic)| topic.topic_tag == filter_tag) {
-        item_arr.insert(topic.clone());
-        //item_arr.insert_with_key(|new_key| topic.clone());
-    }
+    // Topics are listed with their real keys, so selecting one selects it in the study session.
+    // Sub-topics follow their parent and are marked with one arrow per level.
+    let study = &app.study_session;
+    let namespace_order = study.ordered_namespaces();
+    let topics = study
+        .topic_tree()
+        .into_iter()
+        .map(|(id, depth)| (id, depth, &study.topics[id]))
+        .filter(|(_, _, topic)| app.namespace.is_none_or(|key| topic.namespaces.contains(&key)));
 
-    
-    
-    for (id, topic) in item_arr.iter() {
+    for (id, depth, topic) in topics {
+        // The topic is marked with the colour of its first namespace.
+        let namespace_color: Color = namespace_order
+            .iter()
+            .find(|key| topic.namespaces.contains(key))
+            .map_or(Color::TRANSPARENT, |&key| study.namespaces[key].color.into());
+        let namespace_strip: Element<'_, Message, Theme, Renderer> =
+            RoundedRectangle::new(100.0, 6.0)
+                .bg_color(namespace_color)
+                .into();
         let final_color = if topic.enabled {
             Color::WHITE
         } else {
@@ -517,7 +2369,12 @@ fn topic_scrollbar(app: &App) -> Container<'static, Message> {
                     topic_background,
                     container(
                         container(
-                            Button::new(Text::new(topic.content.clone()))
+                            Button::new(Text::new(format!(
+                                "{}{}{}",
+                                "› ".repeat(depth),
+                                topic.content,
+                                if topic.query.is_some() { " (smart)" } else { "" }
+                            )))
                                 .style(move |_, _| {
                                     button::Style {
                                         background: Some(final_color.into()),
@@ -527,6 +2384,8 @@ fn topic_scrollbar(app: &App) -> Container<'static, Message> {
                                 })
                                 .on_press(match app.current_popup {
                                     Popups::Configure => Message::SelectQuiz(id),
+                                    // Cards cannot be put in a smart topic by hand.
+                                    Popups::Topics if topic.query.is_some() => Message::None,
                                     Popups::Topics => Message::SelectTopic(id),
                                     _ => Message::None,
                                 })
@@ -534,6 +2393,7 @@ fn topic_scrollbar(app: &App) -> Container<'static, Message> {
                         .center(Length::Fill)
                     )
                 ],
+                namespace_strip,
             )
             .into(),
         );
@@ -541,10 +2401,21 @@ fn topic_scrollbar(app: &App) -> Container<'static, Message> {
         topic_list.push(Space::new(20.0, 0.0).into());
     }
 
-    container(
+    container(column!(
+        container(row!(
+            Text::new("Namespace").color(Color::WHITE),
+            Space::new(10.0, 0.0),
+            pick_list(
+                namespace_choices(study, true),
+                namespace_choice(study, app.namespace),
+                Message::NamespaceSelected
+            ),
+        ).align_y(Alignment::Center))
+        .center_x(Length::Fill),
+        Space::new(0.0, 10.0),
         container(
             Scrollable::new(Row::with_children(topic_list))
-                .height(100.0)
+                .height(110.0)
                 .width(300.0)
                 .style(|_, _| scrollable::Style {
                     container: container::Style::default().background(Color::from_rgb8(43, 43, 43)),
@@ -580,7 +2451,7 @@ fn topic_scrollbar(app: &App) -> Container<'static, Message> {
         .center(Length::Fill)
         .height(150)
         .width(350),
-    )
+    ))
     .center_x(Length::Fill)
     .padding(20.0)
 }
@@ -0,0 +1,38 @@
+//! Drawing of the light Markdown used in card text.
+//!
+//! The text is parsed by [`flashcard_core::markup`] and drawn with rich text spans, so cards look
+//! the same in the app as in the HTML export.
+use flashcard_core::markup::{parse, Line};
+use iced::advanced::graphics::core::Element;
+use iced::font::{Style as FontStyle, Weight};
+use iced::widget::{rich_text, span, text::Span, Column};
+use iced::{Font, Renderer, Theme};
+
+/// Draws card text in the app.
+pub fn view<'a, Message: Clone + 'static>(text: &str) -> Element<'a, Message, Theme, Renderer> {
+    Column::with_children(parse(text).into_iter().map(|line| {
+        let (bullet, fragments) = match line {
+            Line::Text(fragments) => (None, fragments),
+            Line::Item(fragments) => (Some(span("• ")), fragments),
+        };
+        let spans: Vec<Span<'static, Message>> = bullet
+            .into_iter()
+            .chain(fragments.into_iter().map(|fragment| {
+                let mut font = if fragment.style.code {
+                    Font::MONOSPACE
+                } else {
+                    Font::DEFAULT
+                };
+                if fragment.style.bold {
+                    font.weight = Weight::Bold;
+                }
+                if fragment.style.italic {
+                    font.style = FontStyle::Italic;
+                }
+                span(fragment.text).font(font)
+            }))
+            .collect();
+        rich_text(spans).into()
+    }))
+    .into()
+}